@@ -9,8 +9,8 @@ import { type NextRequest, NextResponse } from "next/server";
 
 const PROGRAM_ID = new PublicKey("2rfRD9jhyK4nwiWiDuixARYsmU3Euw2QMPjmSLHxxYpw");
 const RPC_URL = process.env.NEXT_PUBLIC_RPC_URL || "https://api.devnet.solana.com";
-const SERVICE_LISTING_SIZE = 218;
-const TASK_REQUEST_SIZE = 435;
+const SERVICE_LISTING_SIZE = 292;
+const TASK_REQUEST_SIZE = 579;
 
 const STATUS_MAP: Record<number, string> = {
   0: "open",
@@ -8,7 +8,7 @@ import { type NextRequest, NextResponse } from "next/server";
 
 const PROGRAM_ID = new PublicKey("2rfRD9jhyK4nwiWiDuixARYsmU3Euw2QMPjmSLHxxYpw");
 const RPC_URL = process.env.NEXT_PUBLIC_RPC_URL || "https://api.devnet.solana.com";
-const SERVICE_LISTING_SIZE = 218;
+const SERVICE_LISTING_SIZE = 292;
 
 function trimBytes(arr: number[]): string {
   const buf = Buffer.from(arr);
@@ -8,7 +8,7 @@ import { type NextRequest, NextResponse } from "next/server";
 
 const PROGRAM_ID = new PublicKey("2rfRD9jhyK4nwiWiDuixARYsmU3Euw2QMPjmSLHxxYpw");
 const RPC_URL = process.env.NEXT_PUBLIC_RPC_URL || "https://api.devnet.solana.com";
-const TASK_REQUEST_SIZE = 435;
+const TASK_REQUEST_SIZE = 578;
 
 // Task status enum mapping
 const STATUS_MAP: Record<number, string> = {
@@ -2,7 +2,7 @@
 
 import { BN } from "@coral-xyz/anchor";
 import { useAnchorWallet, useWallet } from "@solana/wallet-adapter-react";
-import { PublicKey, SystemProgram } from "@solana/web3.js";
+import { PublicKey } from "@solana/web3.js";
 import { useQueryClient } from "@tanstack/react-query";
 import { useState } from "react";
 import {
@@ -15,7 +15,7 @@ import {
 } from "@/components/ui/Dialog";
 import type { ServiceListing } from "@/lib/hooks/useServices";
 import { findTaskPda } from "@/lib/pda";
-import { getProgram } from "@/lib/program";
+import { createTaskAccounts, getProgram } from "@/lib/program";
 import { padBytes } from "@/lib/utils";
 
 export function CreateTaskModal({
@@ -73,12 +73,14 @@ export function CreateTaskModal({
 
       const tx = await program.methods
         .createTask(Array.from(taskId), padBytes(description, 256), new BN(deadline))
-        .accounts({
-          requester: publicKey,
-          serviceListing: serviceListingPda,
-          taskRequest: taskRequestPda,
-          systemProgram: SystemProgram.programId,
-        })
+        .accounts(
+          createTaskAccounts(
+            publicKey,
+            serviceListingPda,
+            new PublicKey(service.provider),
+            taskRequestPda,
+          ),
+        )
         .rpc();
 
       setTxSig(tx);
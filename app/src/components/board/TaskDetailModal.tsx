@@ -12,7 +12,7 @@ import {
   DialogTitle,
 } from "@/components/ui/Dialog";
 import type { TaskRequest } from "@/lib/hooks/useTasks";
-import { getProgram } from "@/lib/program";
+import { acceptResultAccounts, getProgram } from "@/lib/program";
 import { AddressDisplay } from "../shared/AddressDisplay";
 import { DeadlineTimer } from "../shared/DeadlineTimer";
 import { EscrowBadge } from "../shared/EscrowBadge";
@@ -77,12 +77,15 @@ export function TaskDetailModal({
       } else if (action === "accept") {
         await program.methods
           .acceptResult()
-          .accounts({
-            requester: publicKey,
-            taskRequest: new PublicKey(task.pda),
-            provider: new PublicKey(task.provider),
-            serviceListing: new PublicKey(task.serviceListing),
-          })
+          .accounts(
+            await acceptResultAccounts(
+              program,
+              publicKey,
+              new PublicKey(task.pda),
+              new PublicKey(task.provider),
+              new PublicKey(task.serviceListing),
+            ),
+          )
           .rpc();
         setSuccessMsg("Result accepted. Escrow released!");
       } else if (action === "dispute") {
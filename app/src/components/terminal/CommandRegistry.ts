@@ -1,14 +1,20 @@
 import { BN } from "@coral-xyz/anchor";
 import type { AnchorWallet } from "@solana/wallet-adapter-react";
-import { LAMPORTS_PER_SOL, PublicKey, SystemProgram } from "@solana/web3.js";
+import { LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
 import { PROGRAM_ID } from "@/lib/constants";
 import { findTaskPda } from "@/lib/pda";
-import { getConnection, getProgram, getReadonlyProgram } from "@/lib/program";
+import {
+  acceptResultAccounts,
+  createTaskAccounts,
+  getConnection,
+  getProgram,
+  getReadonlyProgram,
+} from "@/lib/program";
 import { lamportsToSol, padBytes, trimBytes } from "@/lib/utils";
 
 // Account sizes for filtering (to skip incompatible legacy accounts)
-const SERVICE_LISTING_SIZE = 218;
-const TASK_REQUEST_SIZE = 435;
+const SERVICE_LISTING_SIZE = 292;
+const TASK_REQUEST_SIZE = 579;
 
 // REKT Shield API
 const REKT_SHIELD_API = "https://web-production-c5ac4.up.railway.app/api/scan";
@@ -531,7 +537,7 @@ Type a command to get started. Example: stats
       filters: [{ dataSize: SERVICE_LISTING_SIZE }],
     });
 
-    const services: { pda: PublicKey; desc: string; price: number }[] = [];
+    const services: { pda: PublicKey; provider: PublicKey; desc: string; price: number }[] = [];
     for (const { pubkey, account } of serviceAccounts) {
       try {
         // eslint-disable-next-line @typescript-eslint/no-explicit-any
@@ -539,6 +545,7 @@ Type a command to get started. Example: stats
         if (decoded.isActive) {
           services.push({
             pda: pubkey,
+            provider: decoded.provider,
             desc: trimBytes(decoded.description),
             price: decoded.priceLamports.toNumber(),
           });
@@ -574,12 +581,7 @@ Type a command to get started. Example: stats
     try {
       const tx = await txProgram.methods
         .createTask(Array.from(taskId), padBytes(desc, 256), new BN(deadline))
-        .accounts({
-          requester: publicKey,
-          serviceListing: selected.pda,
-          taskRequest: taskRequestPda,
-          systemProgram: SystemProgram.programId,
-        })
+        .accounts(createTaskAccounts(publicKey, selected.pda, selected.provider, taskRequestPda))
         .rpc();
 
       return {
@@ -666,18 +668,15 @@ Type a command to get started. Example: stats
     if (!opts.d) return { output: "Error: -d <description> required", error: true };
     // eslint-disable-next-line @typescript-eslint/no-explicit-any
     const program = getProgram(wallet) as any;
+    const serviceListingPda = new PublicKey(opts["service-pda"]);
+    const service = await program.account.serviceListing.fetch(serviceListingPda);
     const taskId = crypto.getRandomValues(new Uint8Array(16));
     const [taskRequestPda] = findTaskPda(publicKey, taskId);
     const deadline =
       Math.floor(Date.now() / 1000) + parseInt(opts["deadline-minutes"] || "60", 10) * 60;
     const tx = await program.methods
       .createTask(Array.from(taskId), padBytes(opts.d, 256), new BN(deadline))
-      .accounts({
-        requester: publicKey,
-        serviceListing: new PublicKey(opts["service-pda"]),
-        taskRequest: taskRequestPda,
-        systemProgram: SystemProgram.programId,
-      })
+      .accounts(createTaskAccounts(publicKey, serviceListingPda, service.provider, taskRequestPda))
       .rpc();
     const data = { status: "ok", taskPda: taskRequestPda.toBase58(), tx };
     return { output: JSON.stringify(data, null, 2), data };
@@ -719,12 +718,15 @@ Type a command to get started. Example: stats
     const txProgram = getProgram(wallet) as any;
     const tx = await txProgram.methods
       .acceptResult()
-      .accounts({
-        requester: publicKey,
-        taskRequest: new PublicKey(opts["task-pda"]),
-        provider: decoded.provider,
-        serviceListing: decoded.serviceListing,
-      })
+      .accounts(
+        await acceptResultAccounts(
+          txProgram,
+          publicKey,
+          new PublicKey(opts["task-pda"]),
+          decoded.provider,
+          decoded.serviceListing,
+        ),
+      )
       .rpc();
     const data = { status: "ok", taskPda: opts["task-pda"], action: "accepted", tx };
     return { output: `✅ Result accepted! Payment released.\n   Tx: ${shortAddr(tx)}`, data };
@@ -39,8 +39,6 @@ const INSTRUCTION_DISCRIMINATORS: Record<string, AgentTransaction["type"]> = {
   "745ececdaa339c62": "unknown",
   // deactivate_service: [251, 86, 29, 182, 216, 170, 85, 155]
   fb561db6d8aa559b: "service",
-  // verify_reputation: [182, 23, 57, 13, 214, 61, 83, 208]
-  b617390dd63d53d0: "proof",
 };
 
 export interface AgentTransaction {
@@ -4,8 +4,8 @@ import { getConnection, getReadonlyProgram } from "../program";
 import { lamportsToSol, trimBytes } from "../utils";
 
 // Account sizes for filtering
-const SERVICE_LISTING_SIZE = 218;
-const TASK_REQUEST_SIZE = 435;
+const SERVICE_LISTING_SIZE = 292;
+const TASK_REQUEST_SIZE = 578;
 
 export interface AgentService {
   pda: string;
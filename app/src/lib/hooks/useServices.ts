@@ -25,8 +25,8 @@ export function useServices(providerFilter?: string) {
       try {
         // Use raw getProgramAccounts to fetch all accounts, then decode individually
         // This allows us to skip accounts that don't match the current schema
-        // Filter by dataSize=218 to only get accounts with current ServiceListing schema
-        const filters: GetProgramAccountsFilter[] = [{ dataSize: 218 }];
+        // Filter by dataSize=324 to only get accounts with current ServiceListing schema
+        const filters: GetProgramAccountsFilter[] = [{ dataSize: 324 }];
 
         if (providerFilter) {
           filters.push({
@@ -5,8 +5,8 @@ import { PROGRAM_ID } from "../constants";
 import { getConnection, getReadonlyProgram } from "../program";
 import { lamportsToSol, trimBytes } from "../utils";
 
-// TaskRequest account size: 435 bytes
-const TASK_REQUEST_SIZE = 435;
+// TaskRequest account size: 578 bytes
+const TASK_REQUEST_SIZE = 578;
 
 export interface TaskRequest {
   pda: string;
@@ -20,3 +20,14 @@ export function findTaskPda(
     PROGRAM_ID,
   );
 }
+
+export function findConfigPda(): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([Buffer.from("config")], PROGRAM_ID);
+}
+
+export function findProfilePda(provider: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("provider"), provider.toBuffer()],
+    PROGRAM_ID,
+  );
+}
@@ -1,8 +1,9 @@
 import { AnchorProvider, type Idl, Program } from "@coral-xyz/anchor";
 import type { AnchorWallet } from "@solana/wallet-adapter-react";
-import { Connection } from "@solana/web3.js";
+import { Connection, PublicKey, SystemProgram } from "@solana/web3.js";
 import idl from "../../public/idl.json";
 import { DEVNET_RPC } from "./constants";
+import { findConfigPda, findProfilePda } from "./pda";
 
 // eslint-disable-next-line @typescript-eslint/no-explicit-any
 type AnyProgram = Program<any>;
@@ -19,6 +20,55 @@ export function getProgram(wallet: AnchorWallet): AnyProgram {
   return new Program(idl as Idl, provider);
 }
 
+/** Accounts for `create_task` on a SOL-priced listing. */
+export function createTaskAccounts(
+  requester: PublicKey,
+  serviceListing: PublicKey,
+  provider: PublicKey,
+  taskRequest: PublicKey,
+) {
+  return {
+    requester,
+    serviceListing,
+    taskRequest,
+    providerProfile: findProfilePda(provider)[0],
+    config: findConfigPda()[0],
+    mint: null,
+    requesterTokenAccount: null,
+    escrowTokenAccount: null,
+    tokenProgram: null,
+    systemProgram: SystemProgram.programId,
+  };
+}
+
+/** Accounts for `accept_result` on a SOL task; the protocol fee goes to the config's treasury. */
+export async function acceptResultAccounts(
+  program: AnyProgram,
+  requester: PublicKey,
+  taskRequest: PublicKey,
+  provider: PublicKey,
+  serviceListing: PublicKey,
+) {
+  const [config] = findConfigPda();
+  // eslint-disable-next-line @typescript-eslint/no-explicit-any
+  const { treasury } = await (program.account as any).config.fetch(config);
+  return {
+    requester,
+    taskRequest,
+    provider,
+    // Bounties have no listing
+    serviceListing: serviceListing.equals(PublicKey.default) ? null : serviceListing,
+    providerProfile: findProfilePda(provider)[0],
+    config,
+    treasury,
+    mint: null,
+    escrowTokenAccount: null,
+    providerTokenAccount: null,
+    treasuryTokenAccount: null,
+    tokenProgram: null,
+  };
+}
+
 export function getReadonlyProgram(): AnyProgram {
   const connection = getConnection();
   const provider = {
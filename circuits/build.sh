@@ -0,0 +1,62 @@
+#!/usr/bin/env bash
+# Build and trusted setup of the circuits the program verifies.
+# Usage: BEACON=<hex> ./build.sh [circuit ...]   (default: every v2 circuit)
+#
+# For each circuit: compile with circom, run the Groth16 setup against the Hermez
+# powers of tau, add one phase 2 contribution with fresh secret entropy, close phase 2
+# with the public BEACON, verify the resulting zkey, export the verifying key and write
+# the proof fixtures the program tests verify (programs/agentpay/tests/fixtures/).
+#
+# The secret contribution is what makes the key sound: the beacon alone is public, and
+# anyone who knows every contribution to a zkey can forge proofs against it. The
+# entropy is read from /dev/urandom and never written down, so the zkey this produces
+# is only as trustworthy as the machine that ran it. Keys for mainnet should come from
+# a multi-party ceremony instead (see "Circuit Compilation" in the README).
+#
+# BEACON should be public randomness nobody could predict before the contributions
+# were made, e.g. the hash of a block mined after the last one.
+#
+# Requires circom >= 2.1 and node >= 18. Register the keys on-chain afterwards with
+#   agentpay upload-vk --circuit <name> --vk-version <n> --vk circuits/<name>_vk.json
+
+set -euo pipefail
+cd "$(dirname "$0")"
+
+# 2^19 constraints covers task_verify_chunked, the largest circuit (~263k)
+PTAU_POWER=${PTAU_POWER:-19}
+PTAU=${PTAU:-ptau/powersOfTau28_hez_final_${PTAU_POWER}.ptau}
+PTAU_URL=https://storage.googleapis.com/zkevm/ptau/powersOfTau28_hez_final_${PTAU_POWER}.ptau
+BEACON_ITERATIONS=10
+
+if [[ ! "${BEACON:-}" =~ ^[0-9a-fA-F]{64}$ ]]; then
+  echo "BEACON must be set to 32 bytes of public randomness in hex" >&2
+  exit 1
+fi
+
+if [ "$#" -gt 0 ]; then
+  CIRCUITS=("$@")
+else
+  CIRCUITS=(task_verify_v2 task_verify_chunked result_delivery)
+fi
+
+npm ci --no-audit --no-fund
+
+if [ ! -f "$PTAU" ]; then
+  mkdir -p "$(dirname "$PTAU")"
+  curl -fL -o "$PTAU" "$PTAU_URL"
+fi
+
+for circuit in "${CIRCUITS[@]}"; do
+  echo "== $circuit"
+  circom "$circuit.circom" --r1cs --wasm -o .
+  npx snarkjs groth16 setup "$circuit.r1cs" "$PTAU" "${circuit}_0000.zkey"
+  npx snarkjs zkey contribute "${circuit}_0000.zkey" "${circuit}_0001.zkey" \
+    -n="agentpay $circuit" -e="$(head -c 64 /dev/urandom | od -An -tx1 | tr -d ' \n')"
+  npx snarkjs zkey beacon "${circuit}_0001.zkey" "$circuit.zkey" "$BEACON" "$BEACON_ITERATIONS" \
+    -n="agentpay $circuit beacon"
+  npx snarkjs zkey verify "$circuit.r1cs" "$PTAU" "$circuit.zkey"
+  npx snarkjs zkey export verificationkey "$circuit.zkey" "${circuit}_vk.json"
+  rm "${circuit}_0000.zkey" "${circuit}_0001.zkey" "$circuit.r1cs"
+done
+
+node fixtures.js "${CIRCUITS[@]}"
@@ -0,0 +1,90 @@
+// Poseidon Merkle tree over result chunks, matching task_verify_chunked.circom
+// Usage: node chunk_tree.js <result_file> [chunk_index]
+//
+// Prints the circuit input (chunks, resultRoot, chunkCount) and, if chunk_index is
+// given, the inclusion proof for that chunk. Anyone holding a chunk and its proof can
+// check it against the task's on-chain result_hash with verifyInclusion().
+
+const { buildPoseidon } = require("circomlibjs");
+const fs = require("fs");
+
+const DEPTH = 10;
+const N = 1 << DEPTH;
+const CHUNK_BYTES = 31; // stays below the BN254 field modulus
+
+// Split a byte payload into at most N field elements (big-endian, 31 bytes each)
+function toChunks(payload) {
+  const count = Math.max(1, Math.ceil(payload.length / CHUNK_BYTES));
+  if (count > N) {
+    throw new Error(`Result too large: ${payload.length} bytes, max ${N * CHUNK_BYTES}`);
+  }
+  const chunks = new Array(N).fill(0n);
+  for (let i = 0; i < count; i++) {
+    const slice = payload.subarray(i * CHUNK_BYTES, (i + 1) * CHUNK_BYTES);
+    chunks[i] = slice.length ? BigInt("0x" + Buffer.from(slice).toString("hex")) : 0n;
+  }
+  return { chunks, count };
+}
+
+// Heap layout: node k has children 2k+1 and 2k+2, leaf i sits at N - 1 + i
+function buildTree(poseidon, chunks) {
+  const nodes = new Array(2 * N - 1);
+  for (let i = 0; i < N; i++) nodes[N - 1 + i] = chunks[i];
+  for (let k = N - 2; k >= 0; k--) {
+    nodes[k] = poseidon.F.toObject(poseidon([nodes[2 * k + 1], nodes[2 * k + 2]]));
+  }
+  return nodes;
+}
+
+// Sibling hashes from leaf to root
+function inclusionProof(nodes, index) {
+  const siblings = [];
+  let k = N - 1 + index;
+  while (k > 0) {
+    siblings.push(k % 2 === 1 ? nodes[k + 1] : nodes[k - 1]);
+    k = Math.floor((k - 1) / 2);
+  }
+  return siblings;
+}
+
+function verifyInclusion(poseidon, root, index, chunk, siblings) {
+  let hash = BigInt(chunk);
+  let k = N - 1 + index;
+  for (const sibling of siblings) {
+    const pair = k % 2 === 1 ? [hash, BigInt(sibling)] : [BigInt(sibling), hash];
+    hash = poseidon.F.toObject(poseidon(pair));
+    k = Math.floor((k - 1) / 2);
+  }
+  return hash === BigInt(root);
+}
+
+module.exports = { DEPTH, N, CHUNK_BYTES, toChunks, buildTree, inclusionProof, verifyInclusion };
+
+if (require.main === module) {
+  (async () => {
+    const inputPath = process.argv[2];
+    if (!inputPath) {
+      console.error("Usage: node chunk_tree.js <result_file> [chunk_index]");
+      process.exit(1);
+    }
+    const poseidon = await buildPoseidon();
+    const { chunks, count } = toChunks(fs.readFileSync(inputPath));
+    const nodes = buildTree(poseidon, chunks);
+    const out = {
+      input: {
+        chunks: chunks.map(String),
+        resultRoot: nodes[0].toString(),
+        chunkCount: count.toString(),
+      },
+    };
+    if (process.argv[3] !== undefined) {
+      const index = Number(process.argv[3]);
+      out.proof = {
+        index,
+        chunk: chunks[index].toString(),
+        siblings: inclusionProof(nodes, index).map(String),
+      };
+    }
+    console.log(JSON.stringify(out, null, 2));
+  })();
+}
@@ -0,0 +1,256 @@
+// Proof fixtures for the program tests, generated from the real zkeys by build.sh
+// Usage: node fixtures.js [circuit ...]
+//
+// Every proof is bound to the same task: TASK_ID created by the requester with
+// REQUESTER_SEED and served by the provider with PROVIDER_SEED, so the tests can
+// rebuild that task on-chain. Each fixture holds the verifying key, the proof and
+// the public inputs already in the byte layout the program takes.
+
+const snarkjs = require("snarkjs");
+const { buildBabyjub, buildPoseidon } = require("circomlibjs");
+const crypto = require("crypto");
+const fs = require("fs");
+const path = require("path");
+const { toChunks, buildTree } = require("./chunk_tree");
+
+const PROGRAM_ID = "2rfRD9jhyK4nwiWiDuixARYsmU3Euw2QMPjmSLHxxYpw";
+const REQUESTER_SEED = Buffer.alloc(32, 1);
+const PROVIDER_SEED = Buffer.alloc(32, 2);
+const TASK_ID = Buffer.alloc(16, 0xa5);
+const OUT_DIR = path.join(__dirname, "../programs/agentpay/tests/fixtures");
+
+// BN254 base field (proof coordinates) and scalar field (signals)
+const Q = BigInt("21888242871839275222246405745257275088696311157297823662689037894645226208583");
+const R = BigInt("21888242871839275222246405745257275088548364400416034343698204186575808495617");
+
+// ============================================================================
+// Solana keys
+// ============================================================================
+
+const BASE58 = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+function base58Decode(text) {
+  let n = 0n;
+  for (const c of text) n = n * 58n + BigInt(BASE58.indexOf(c));
+  return toBytes32(n);
+}
+
+// Ed25519 public key of a 32-byte seed, as Keypair::from_seed / Keypair.fromSeed derive it
+function publicKeyFromSeed(seed) {
+  const pkcs8 = Buffer.concat([Buffer.from("302e020100300506032b657004220420", "hex"), seed]);
+  const key = crypto.createPrivateKey({ key: pkcs8, format: "der", type: "pkcs8" });
+  return crypto.createPublicKey(key).export({ format: "der", type: "spki" }).subarray(-32);
+}
+
+function modPow(base, exp, mod) {
+  let result = 1n;
+  base %= mod;
+  while (exp > 0n) {
+    if (exp & 1n) result = (result * base) % mod;
+    base = (base * base) % mod;
+    exp >>= 1n;
+  }
+  return result;
+}
+
+// Whether 32 bytes decompress to an Ed25519 point, as the runtime checks for program
+// addresses: x^2 = (y^2 - 1) / (d y^2 + 1) must have a root
+function isOnCurve(bytes) {
+  const p = 2n ** 255n - 19n;
+  const d = (p - ((121665n * modPow(121666n, p - 2n, p)) % p)) % p;
+  const yBytes = Buffer.from(bytes);
+  yBytes[31] &= 0x7f;
+  const y = BigInt(`0x${yBytes.reverse().toString("hex")}`) % p;
+  const y2 = (y * y) % p;
+  const x2 = (((y2 - 1n + p) % p) * modPow((d * y2 + 1n) % p, p - 2n, p)) % p;
+  return x2 === 0n || modPow(x2, (p - 1n) / 2n, p) === 1n;
+}
+
+function findProgramAddress(seeds, programId) {
+  for (let bump = 255; bump >= 0; bump--) {
+    const hash = crypto
+      .createHash("sha256")
+      .update(Buffer.concat([...seeds, Buffer.from([bump]), programId]))
+      .update("ProgramDerivedAddress")
+      .digest();
+    if (!isOnCurve(hash)) return hash;
+  }
+  throw new Error("No program address found");
+}
+
+// ============================================================================
+// Encodings
+// ============================================================================
+
+function toBytes32(n) {
+  return Buffer.from(n.toString(16).padStart(64, "0"), "hex");
+}
+
+// Big-endian 128-bit halves of a key, matching zk::pubkey_to_field_elements
+function keyHalves(key) {
+  return [
+    BigInt(`0x${key.subarray(0, 16).toString("hex")}`),
+    BigInt(`0x${key.subarray(16).toString("hex")}`),
+  ];
+}
+
+function g1(point, negate = false) {
+  const y = BigInt(point[1]);
+  return [...toBytes32(BigInt(point[0])), ...toBytes32(negate ? (Q - y) % Q : y)];
+}
+
+// G2 coordinates go c1 before c0, as groth16-solana expects
+function g2(point) {
+  return [point[0][1], point[0][0], point[1][1], point[1][0]].flatMap((c) => [
+    ...toBytes32(BigInt(c)),
+  ]);
+}
+
+function verifyingKey(vk) {
+  return {
+    alpha_g1: g1(vk.vk_alpha_1),
+    beta_g2: g2(vk.vk_beta_2),
+    gamma_g2: g2(vk.vk_gamma_2),
+    delta_g2: g2(vk.vk_delta_2),
+    ic: vk.IC.map((point) => g1(point)),
+  };
+}
+
+// ============================================================================
+// Circuit inputs
+// ============================================================================
+
+function resultField(text) {
+  const sha = crypto.createHash("sha256").update(text).digest();
+  return BigInt(`0x${sha.subarray(0, 31).toString("hex")}`);
+}
+
+async function circuitInputs(circuit, binding) {
+  const poseidon = await buildPoseidon();
+  const hash = (values) => poseidon.F.toObject(poseidon(values));
+
+  switch (circuit) {
+    case "task_verify_v2": {
+      const result = resultField("agentpay task_verify_v2 fixture");
+      return {
+        input: { result, expectedHash: hash([result]), ...binding },
+        public: ["expectedHash", "taskHi", "taskLo", "providerHi", "providerLo"],
+      };
+    }
+    case "task_verify_chunked": {
+      const payload = Buffer.from("agentpay task_verify_chunked fixture ".repeat(4));
+      const { chunks, count } = toChunks(payload);
+      const nodes = buildTree(poseidon, chunks);
+      return {
+        input: { chunks, resultRoot: nodes[0], chunkCount: BigInt(count), ...binding },
+        public: ["resultRoot", "chunkCount", "taskHi", "taskLo", "providerHi", "providerLo"],
+      };
+    }
+    case "result_delivery": {
+      const babyJub = await buildBabyjub();
+      const point = (p) => p.map((c) => babyJub.F.toObject(c));
+      const result = resultField("agentpay result_delivery fixture");
+      const encSecret = resultField("agentpay requester encryption key");
+      const ephemeralSecret = resultField("agentpay provider ephemeral key");
+      const encKey = babyJub.mulPointEscalar(babyJub.Base8, encSecret);
+      const [ephemeralX, ephemeralY] = point(
+        babyJub.mulPointEscalar(babyJub.Base8, ephemeralSecret),
+      );
+      const shared = point(babyJub.mulPointEscalar(encKey, ephemeralSecret));
+      const [encKeyX, encKeyY] = point(encKey);
+      return {
+        input: {
+          result,
+          ephemeralSecret,
+          expectedHash: hash([result]),
+          ciphertext: (result + hash(shared)) % R,
+          encKeyX,
+          encKeyY,
+          ephemeralX,
+          ephemeralY,
+          ...binding,
+        },
+        public: [
+          "expectedHash",
+          "ciphertext",
+          "encKeyX",
+          "encKeyY",
+          "ephemeralX",
+          "ephemeralY",
+          "taskHi",
+          "taskLo",
+          "providerHi",
+          "providerLo",
+        ],
+      };
+    }
+    default:
+      throw new Error(`Unknown circuit: ${circuit}`);
+  }
+}
+
+// ============================================================================
+// Main
+// ============================================================================
+
+async function main() {
+  const circuits = process.argv.slice(2);
+  if (circuits.length === 0) {
+    console.error("Usage: node fixtures.js <circuit> [circuit ...]");
+    process.exit(1);
+  }
+
+  const programId = base58Decode(PROGRAM_ID);
+  const requester = publicKeyFromSeed(REQUESTER_SEED);
+  const provider = publicKeyFromSeed(PROVIDER_SEED);
+  const task = findProgramAddress([Buffer.from("task"), requester, TASK_ID], programId);
+  const [taskHi, taskLo] = keyHalves(task);
+  const [providerHi, providerLo] = keyHalves(provider);
+  const binding = { taskHi, taskLo, providerHi, providerLo };
+
+  fs.mkdirSync(OUT_DIR, { recursive: true });
+  for (const circuit of circuits) {
+    const { input, public: names } = await circuitInputs(circuit, binding);
+    const { proof, publicSignals } = await snarkjs.groth16.fullProve(
+      input,
+      path.join(__dirname, `${circuit}_js/${circuit}.wasm`),
+      path.join(__dirname, `${circuit}.zkey`),
+    );
+
+    // The program rebuilds these inputs itself; a reordering here would go unnoticed
+    const expected = names.map((name) => input[name].toString());
+    if (JSON.stringify(publicSignals) !== JSON.stringify(expected)) {
+      throw new Error(`${circuit}: public signals ${publicSignals} != ${expected}`);
+    }
+    const vk = JSON.parse(fs.readFileSync(path.join(__dirname, `${circuit}_vk.json`), "utf-8"));
+    if (!(await snarkjs.groth16.verify(vk, publicSignals, proof))) {
+      throw new Error(`${circuit}: proof does not verify`);
+    }
+
+    const fixture = {
+      circuit,
+      requester_seed: [...REQUESTER_SEED],
+      provider_seed: [...PROVIDER_SEED],
+      task_id: [...TASK_ID],
+      task: [...task],
+      provider: [...provider],
+      vk: verifyingKey(vk),
+      proof: {
+        a: g1(proof.pi_a, true),
+        b: g2(proof.pi_b),
+        c: g1(proof.pi_c),
+      },
+      public_inputs: publicSignals.map((signal) => [...toBytes32(BigInt(signal))]),
+    };
+    const out = path.join(OUT_DIR, `${circuit}.json`);
+    fs.writeFileSync(out, `${JSON.stringify(fixture)}\n`);
+    console.log(`${circuit}: ${out}`);
+  }
+}
+
+main()
+  .then(() => process.exit(0))
+  .catch((e) => {
+    console.error(e);
+    process.exit(1);
+  });
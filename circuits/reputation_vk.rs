@@ -1,46 +0,0 @@
-use groth16_solana::groth16::Groth16Verifyingkey;
-
-pub const REPUTATION_VK: Groth16Verifyingkey = Groth16Verifyingkey {
-	nr_pubinputs: 2,
-
-	vk_alpha_g1: [
-		38,19,44,130,112,116,240,152,213,139,150,1,38,90,193,90,96,81,200,187,237,58,174,185,229,216,180,164,37,111,192,189,
-		43,202,179,4,92,16,26,148,244,139,135,137,218,136,228,229,73,70,66,124,207,198,253,170,93,41,210,168,54,123,125,4,
-	],
-
-	vk_beta_g2: [
-		4,161,213,94,47,152,25,108,19,182,163,167,50,186,86,251,228,56,217,122,182,133,130,197,168,93,200,68,0,217,18,79,
-		46,197,213,239,33,12,207,187,14,65,64,111,32,65,206,11,1,51,112,48,208,233,89,150,53,164,103,65,217,60,39,85,
-		17,198,44,5,142,102,94,130,246,180,205,137,28,93,169,68,196,3,73,153,11,110,118,13,170,88,136,238,30,137,229,132,
-		1,125,59,108,163,70,115,1,210,209,54,157,213,66,205,234,96,165,133,34,210,202,5,11,131,149,101,129,121,179,157,63,
-	],
-
-	vk_gamma_g2: [
-		25,142,147,147,146,13,72,58,114,96,191,183,49,251,93,37,241,170,73,51,53,169,231,18,151,228,133,183,174,243,18,194,
-		24,0,222,239,18,31,30,118,66,106,0,102,94,92,68,121,103,67,34,212,247,94,218,221,70,222,189,92,217,146,246,237,
-		9,6,137,208,88,95,240,117,236,158,153,173,105,12,51,149,188,75,49,51,112,179,142,243,85,172,218,220,209,34,151,91,
-		18,200,94,165,219,140,109,235,74,171,113,128,141,203,64,143,227,209,231,105,12,67,211,123,76,230,204,1,102,250,125,170,
-	],
-
-	vk_delta_g2: [
-		4,67,30,108,4,83,24,192,41,216,174,233,68,65,240,145,191,169,229,252,100,129,45,50,40,133,214,192,11,150,61,74,
-		47,36,16,121,208,176,56,17,236,88,39,220,64,16,27,220,216,185,72,54,113,150,27,32,76,96,135,104,38,149,234,200,
-		35,74,171,37,179,123,180,246,13,78,188,155,99,189,101,102,198,161,14,69,41,7,249,79,147,241,83,240,72,68,216,201,
-		4,39,79,68,2,12,237,50,121,109,229,120,13,130,121,55,230,242,214,35,37,185,188,97,119,226,113,166,98,78,81,43,
-	],
-
-	vk_ic: &[
-		[
-			21,68,183,123,226,88,115,34,87,70,82,101,252,148,65,13,93,224,53,192,28,155,229,3,195,234,70,151,121,77,185,71,
-			18,120,219,23,4,15,252,250,105,27,202,123,92,167,111,153,136,11,227,139,154,186,44,49,166,210,33,12,159,161,85,46,
-		],
-		[
-			14,171,238,104,187,202,30,193,40,235,106,32,73,169,116,107,241,157,63,53,127,5,249,56,114,155,173,27,96,35,44,4,
-			39,139,18,154,223,156,246,62,53,113,75,187,122,216,55,61,130,185,170,228,165,17,149,248,162,181,28,81,157,166,83,156,
-		],
-		[
-			30,47,80,48,4,195,146,125,34,184,212,193,173,218,42,67,239,92,230,25,176,99,102,56,98,19,2,29,3,119,234,211,
-			3,123,187,214,136,135,48,147,25,118,70,37,198,187,2,121,37,54,211,97,5,153,48,26,106,37,182,231,236,178,253,68,
-		],
-	]
-};
\ No newline at end of file
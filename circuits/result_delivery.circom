@@ -0,0 +1,92 @@
+pragma circom 2.1.0;
+
+include "node_modules/circomlib/circuits/poseidon.circom";
+include "node_modules/circomlib/circuits/babyjub.circom";
+include "node_modules/circomlib/circuits/escalarmulany.circom";
+include "node_modules/circomlib/circuits/bitify.circom";
+
+// ZK Encrypted Result Delivery
+// Proves: the ciphertext posted on-chain decrypts, under the requester's key,
+// to a result whose Poseidon hash is expectedHash.
+//
+// Encryption is ECDH on BabyJubJub plus a Poseidon one-time pad:
+//   ephemeral = ephemeralSecret * Base8            (published with the ciphertext)
+//   shared    = ephemeralSecret * encKey           (requester recomputes as encSecret * ephemeral)
+//   ciphertext = result + Poseidon(shared.x, shared.y)
+// The requester decrypts with result = ciphertext - Poseidon(shared.x, shared.y).
+//
+// As in task_verify_v2, the task PDA and provider key are public inputs split into
+// 128-bit halves so the proof cannot be replayed on another task.
+
+template ResultDelivery() {
+    // Private inputs
+    signal input result;          // the result value (field element)
+    signal input ephemeralSecret; // provider's one-time encryption scalar
+
+    // Public inputs
+    signal input expectedHash; // Poseidon hash of the result
+    signal input ciphertext;   // encrypted result posted on-chain
+    signal input encKeyX;      // requester's BabyJubJub public key
+    signal input encKeyY;
+    signal input ephemeralX;   // provider's ephemeral public key
+    signal input ephemeralY;
+    signal input taskHi;       // task PDA, high 128 bits
+    signal input taskLo;       // task PDA, low 128 bits
+    signal input providerHi;   // provider key, high 128 bits
+    signal input providerLo;   // provider key, low 128 bits
+
+    // 1. The result is the pre-image of the expected hash
+    component hasher = Poseidon(1);
+    hasher.inputs[0] <== result;
+    hasher.out === expectedHash;
+
+    // 2. The published ephemeral key belongs to ephemeralSecret
+    component ephemeral = BabyPbk();
+    ephemeral.in <== ephemeralSecret;
+    ephemeral.Ax === ephemeralX;
+    ephemeral.Ay === ephemeralY;
+
+    // 3. Shared point = ephemeralSecret * requester key (key must be on the curve)
+    component keyCheck = BabyCheck();
+    keyCheck.x <== encKeyX;
+    keyCheck.y <== encKeyY;
+
+    component secretBits = Num2Bits(253);
+    secretBits.in <== ephemeralSecret;
+
+    component shared = EscalarMulAny(253);
+    for (var i = 0; i < 253; i++) {
+        shared.e[i] <== secretBits.out[i];
+    }
+    shared.p[0] <== encKeyX;
+    shared.p[1] <== encKeyY;
+
+    // 4. ciphertext = result + Poseidon(shared)
+    component pad = Poseidon(2);
+    pad.inputs[0] <== shared.out[0];
+    pad.inputs[1] <== shared.out[1];
+    ciphertext === result + pad.out;
+
+    // 5. Bind the task and provider, squared as in task_verify_v2
+    signal taskHiSq;
+    signal taskLoSq;
+    signal providerHiSq;
+    signal providerLoSq;
+    taskHiSq <== taskHi * taskHi;
+    taskLoSq <== taskLo * taskLo;
+    providerHiSq <== providerHi * providerHi;
+    providerLoSq <== providerLo * providerLo;
+}
+
+component main {public [
+    expectedHash,
+    ciphertext,
+    encKeyX,
+    encKeyY,
+    ephemeralX,
+    ephemeralY,
+    taskHi,
+    taskLo,
+    providerHi,
+    providerLo
+]} = ResultDelivery();
@@ -0,0 +1,91 @@
+pragma circom 2.1.0;
+
+include "node_modules/circomlib/circuits/poseidon.circom";
+include "node_modules/circomlib/circuits/comparators.circom";
+include "node_modules/circomlib/circuits/bitify.circom";
+
+// ZK Chunked Task Completion Proof
+// Proves: the provider knows result chunks whose Poseidon Merkle root is resultRoot,
+// for one specific task and provider (same binding as task_verify_v2).
+//
+// The result is split into up to 2^DEPTH chunks, one field element each (31 bytes of
+// payload per chunk). Chunks at index >= chunkCount must be zero. Inner nodes are
+// Poseidon(left, right) over a perfect binary tree.
+//
+// To reveal chunk i later, publish it with its DEPTH sibling hashes; anyone can
+// recompute the root and compare it with the task's on-chain result_hash.
+
+template TaskVerifyChunked(DEPTH) {
+    var N = 1 << DEPTH;
+
+    // Private input: the result chunks (field elements)
+    signal input chunks[N];
+
+    // Public inputs
+    signal input resultRoot;  // Merkle root of the chunks
+    signal input chunkCount;  // number of meaningful chunks (1..N)
+    signal input taskHi;      // task PDA, high 128 bits
+    signal input taskLo;      // task PDA, low 128 bits
+    signal input providerHi;  // provider key, high 128 bits
+    signal input providerLo;  // provider key, low 128 bits
+
+    // 1. 1 <= chunkCount <= N (range-check the bits first so the comparators are sound)
+    component countBits = Num2Bits(DEPTH + 1);
+    countBits.in <== chunkCount;
+
+    component countMax = LessEqThan(DEPTH + 1);
+    countMax.in[0] <== chunkCount;
+    countMax.in[1] <== N;
+    countMax.out === 1;
+
+    component countMin = GreaterEqThan(DEPTH + 1);
+    countMin.in[0] <== chunkCount;
+    countMin.in[1] <== 1;
+    countMin.out === 1;
+
+    // 2. Padding chunks are zero
+    component isPadding[N];
+    for (var i = 0; i < N; i++) {
+        isPadding[i] = GreaterEqThan(DEPTH + 1);
+        isPadding[i].in[0] <== i;
+        isPadding[i].in[1] <== chunkCount;
+        isPadding[i].out * chunks[i] === 0;
+    }
+
+    // 3. Merkle root, heap layout: node k has children 2k+1 and 2k+2,
+    //    leaf i sits at N - 1 + i
+    signal nodes[2 * N - 1];
+    for (var i = 0; i < N; i++) {
+        nodes[N - 1 + i] <== chunks[i];
+    }
+
+    component hashers[N - 1];
+    for (var k = N - 2; k >= 0; k--) {
+        hashers[k] = Poseidon(2);
+        hashers[k].inputs[0] <== nodes[2 * k + 1];
+        hashers[k].inputs[1] <== nodes[2 * k + 2];
+        nodes[k] <== hashers[k].out;
+    }
+
+    nodes[0] === resultRoot;
+
+    // 4. Bind the task and provider, squared as in task_verify_v2
+    signal taskHiSq;
+    signal taskLoSq;
+    signal providerHiSq;
+    signal providerLoSq;
+    taskHiSq <== taskHi * taskHi;
+    taskLoSq <== taskLo * taskLo;
+    providerHiSq <== providerHi * providerHi;
+    providerLoSq <== providerLo * providerLo;
+}
+
+// 1024 chunks, about 31 KB of result payload
+component main {public [
+    resultRoot,
+    chunkCount,
+    taskHi,
+    taskLo,
+    providerHi,
+    providerLo
+]} = TaskVerifyChunked(10);
@@ -0,0 +1,48 @@
+pragma circom 2.1.0;
+
+include "node_modules/circomlib/circuits/poseidon.circom";
+
+// ZK Task Completion Proof (v2, task-bound)
+// Proves: Poseidon(secret_result) == expected_hash
+// for one specific task and provider. The task PDA and provider key are public
+// inputs, so a proof lifted from one task's transaction cannot be replayed on another.
+//
+// Solana keys are 256 bits and do not fit in a BN254 field element, so each key is
+// passed as two 128-bit halves (big-endian: hi = bytes 0..16, lo = bytes 16..32).
+//
+// The halves play no part in the hash, but a Groth16 proof only commits to public
+// inputs that appear in some constraint: left unconstrained, the same proof would
+// verify for any task and provider. Squaring each half into a throwaway signal is
+// the cheapest constraint that pulls it in. task_verify_chunked and result_delivery
+// bind the same four halves the same way.
+
+template TaskVerifyV2() {
+    // Private input: the actual result value (field element)
+    signal input result;
+
+    // Public inputs
+    signal input expectedHash; // Poseidon hash of the result
+    signal input taskHi;       // task PDA, high 128 bits
+    signal input taskLo;       // task PDA, low 128 bits
+    signal input providerHi;   // provider key, high 128 bits
+    signal input providerLo;   // provider key, low 128 bits
+
+    // Compute Poseidon hash of the result
+    component hasher = Poseidon(1);
+    hasher.inputs[0] <== result;
+
+    // Constrain: hash of result must equal expected hash
+    hasher.out === expectedHash;
+
+    // Bind the task and provider (see the header)
+    signal taskHiSq;
+    signal taskLoSq;
+    signal providerHiSq;
+    signal providerLoSq;
+    taskHiSq <== taskHi * taskHi;
+    taskLoSq <== taskLo * taskLo;
+    providerHiSq <== providerHi * providerHi;
+    providerLoSq <== providerLo * providerLo;
+}
+
+component main {public [expectedHash, taskHi, taskLo, providerHi, providerLo]} = TaskVerifyV2();
@@ -3,6 +3,7 @@
 import { createHash, randomBytes } from "node:crypto";
 import { readFileSync } from "node:fs";
 import { homedir } from "node:os";
+import { pathToFileURL } from "node:url";
 import web3 from "@solana/web3.js";
 import { Command } from "commander";
 
@@ -66,36 +67,54 @@ function bigIntToBytes32BE(n) {
   return Buffer.from(hex, "hex");
 }
 
+const BN254_Q = BigInt(
+  "21888242871839275222246405745257275088696311157297823662689037894645226208583",
+);
+
+// G1 point as x || y big-endian, optionally negated (y -> Q - y)
+function g1ToBytes(point, negate = false) {
+  const y = BigInt(point[1]);
+  return Buffer.concat([
+    bigIntToBytes32BE(BigInt(point[0])),
+    bigIntToBytes32BE(negate ? (BN254_Q - y) % BN254_Q : y),
+  ]);
+}
+
+// G2 point: snarkjs gives [[x_c0, x_c1], [y_c0, y_c1]], groth16-solana expects
+// [x_c1, x_c0, y_c1, y_c0] big-endian
+function g2ToBytes(point) {
+  return Buffer.concat(
+    [point[0][1], point[0][0], point[1][1], point[1][0]].map((c) => bigIntToBytes32BE(BigInt(c))),
+  );
+}
+
 function unstringifyProof(proof) {
-  // Convert snarkjs proof format to groth16-solana byte format
-  // proof_a: negate and convert to big-endian bytes
-  // proof_b: convert to big-endian bytes
-  // proof_c: convert to big-endian bytes
-
-  const a = Buffer.alloc(64);
-  const pi_a_x = BigInt(proof.pi_a[0]);
-  const pi_a_y = BigInt(proof.pi_a[1]);
-  // Negate proof_a (negate y coordinate on BN254)
-  const P = BigInt("21888242871839275222246405745257275088696311157297823662689037894645226208583");
-  const neg_y = (P - pi_a_y) % P;
-  bigIntToBytes32BE(pi_a_x).copy(a, 0);
-  bigIntToBytes32BE(neg_y).copy(a, 32);
-
-  const b = Buffer.alloc(128);
-  // G2 point: proof_b[0] = [x_c1, x_c0], proof_b[1] = [y_c1, y_c0]
-  // groth16-solana expects: [x_c0, x_c1, y_c0, y_c1] in big-endian
-  bigIntToBytes32BE(BigInt(proof.pi_b[0][1])).copy(b, 0);
-  bigIntToBytes32BE(BigInt(proof.pi_b[0][0])).copy(b, 32);
-  bigIntToBytes32BE(BigInt(proof.pi_b[1][1])).copy(b, 64);
-  bigIntToBytes32BE(BigInt(proof.pi_b[1][0])).copy(b, 96);
-
-  const c = Buffer.alloc(64);
-  bigIntToBytes32BE(BigInt(proof.pi_c[0])).copy(c, 0);
-  bigIntToBytes32BE(BigInt(proof.pi_c[1])).copy(c, 32);
-
-  return { a, b, c };
+  // Convert snarkjs proof format to groth16-solana byte format:
+  // proof_a negated, proof_b and proof_c as-is, all big-endian
+  return {
+    a: g1ToBytes(proof.pi_a, true),
+    b: g2ToBytes(proof.pi_b),
+    c: g1ToBytes(proof.pi_c),
+  };
+}
+
+// Big-endian high and low 128 bits of a key, as the circuits bind it
+// (zk::pubkey_to_field_elements on-chain)
+function keyToFieldElements(key) {
+  const bytes = key.toBuffer();
+  return [
+    BigInt(`0x${bytes.subarray(0, 16).toString("hex")}`),
+    BigInt(`0x${bytes.subarray(16).toString("hex")}`),
+  ];
 }
 
+// Circuits with an on-chain verifying key: the ZkCircuit variant and its seed byte
+const ZK_CIRCUITS = {
+  task_verify_v2: { variant: { taskVerify: {} }, seed: 0 },
+  result_delivery: { variant: { resultDelivery: {} }, seed: 1 },
+  task_verify_chunked: { variant: { taskVerifyChunked: {} }, seed: 2 },
+};
+
 // ============================================================================
 // PDA derivation
 // ============================================================================
@@ -114,6 +133,31 @@ function findTaskPda(requester, taskId) {
   );
 }
 
+function findConfigPda() {
+  return PublicKey.findProgramAddressSync([Buffer.from("config")], PROGRAM_ID);
+}
+
+function findProfilePda(provider) {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("provider"), provider.toBuffer()],
+    PROGRAM_ID,
+  );
+}
+
+function findVerifyingKeyPda(circuit, version) {
+  const versionBytes = Buffer.alloc(2);
+  versionBytes.writeUInt16LE(version);
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("vk"), Buffer.from([ZK_CIRCUITS[circuit].seed]), versionBytes],
+    PROGRAM_ID,
+  );
+}
+
+// Unclaimed bounties have no provider, and so no profile to book the outcome on
+function taskProfilePda(task) {
+  return task.provider.equals(PublicKey.default) ? null : findProfilePda(task.provider)[0];
+}
+
 // ============================================================================
 // CLI
 // ============================================================================
@@ -137,7 +181,18 @@ cli
   .description("Register a service on-chain with a description and price")
   .requiredOption("-d, --description <text>", "Service description (max 128 chars)")
   .requiredOption("-p, --price <sol>", "Price per task in SOL")
+  .requiredOption("--arbiter <pubkey>", "Arbiter who rules on disputes")
   .option("--min-reputation <n>", "Minimum reputation score required (0 = no minimum)", "0")
+  .option(
+    "--review-window <seconds>",
+    "Seconds the requester has to review a result before it auto-accepts (at most 30 days)",
+    "86400",
+  )
+  .option(
+    "--cancellation-fee-bps <bps>",
+    "Share of escrow the provider keeps if the requester cancels, in bps",
+    "0",
+  )
   .action(async (opts) => {
     const { program, keypair } = getProgram(cli.opts().url, cli.opts().keypair);
     const serviceId = randomBytes(16);
@@ -149,10 +204,16 @@ cli
         padBytes(opts.description, 128),
         new BN(solToLamports(opts.price)),
         new BN(parseInt(opts.minReputation, 10)),
+        new PublicKey(opts.arbiter),
+        new BN(parseInt(opts.reviewWindow, 10)),
+        parseInt(opts.cancellationFeeBps, 10),
       )
       .accounts({
         provider: keypair.publicKey,
         serviceListing: serviceListingPda,
+        providerProfile: findProfilePda(keypair.publicKey)[0],
+        config: findConfigPda()[0],
+        mint: null,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
@@ -164,6 +225,7 @@ cli
         servicePda: serviceListingPda.toBase58(),
         description: opts.description,
         priceSol: opts.price,
+        arbiter: opts.arbiter,
         tx,
       }),
     );
@@ -172,8 +234,8 @@ cli
 // ── list-services ───────────────────────────────────────────────────────────
 
 // Account sizes for filtering (to skip incompatible legacy accounts)
-const SERVICE_LISTING_SIZE = 218;
-const TASK_REQUEST_SIZE = 435;
+const SERVICE_LISTING_SIZE = 292;
+const TASK_REQUEST_SIZE = 579;
 
 cli
   .command("list-services")
@@ -290,6 +352,12 @@ cli
         requester: keypair.publicKey,
         serviceListing: serviceListingPda,
         taskRequest: taskRequestPda,
+        providerProfile: findProfilePda(serviceAccount.provider)[0],
+        config: findConfigPda()[0],
+        mint: null,
+        requesterTokenAccount: null,
+        escrowTokenAccount: null,
+        tokenProgram: null,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
@@ -402,33 +470,72 @@ cli
   .description("Submit a ZK-verified result for a task (Groth16 proof verified on-chain)")
   .requiredOption("--task-pda <pda>", "Task request PDA address")
   .requiredOption("-r, --result <text>", "Result text (will be Poseidon hashed & ZK proved)")
+  .option(
+    "--chunked",
+    "Commit to the whole result as a Merkle root over 31-byte chunks (task_verify_chunked) instead of a hash of its SHA256",
+  )
+  .option("--vk-version <n>", "Version of the registered verifying key", "1")
   .option(
     "--circuits-dir <path>",
-    "Path to compiled circuits directory",
+    "Path to compiled circuits directory (see circuits/build.sh)",
     new URL("../circuits", import.meta.url).pathname,
   )
   .action(async (opts) => {
     const { program, keypair } = getProgram(cli.opts().url, cli.opts().keypair);
+    const taskPda = new PublicKey(opts.taskPda);
+    const circuit = opts.chunked ? "task_verify_chunked" : "task_verify_v2";
+    const [verifyingKey] = findVerifyingKeyPda(circuit, parseInt(opts.vkVersion, 10));
+    const circuitsDir = opts.circuitsDir;
 
     // Dynamically import snarkjs and circomlibjs
     const snarkjs = await import("snarkjs");
     const { buildPoseidon } = await import("circomlibjs");
-
-    // Compute Poseidon hash of the result (as field element)
     const poseidon = await buildPoseidon();
-    // Convert result text to a field element (use first 31 bytes of SHA256 to stay in field)
-    const resultSha = createHash("sha256").update(opts.result).digest();
-    const resultField = BigInt(`0x${resultSha.subarray(0, 31).toString("hex")}`);
-    const poseidonHash = poseidon.F.toString(poseidon([resultField]));
 
-    console.error("Generating ZK proof...");
+    // The committed value and the chunk count the program records for it
+    let commitment;
+    let chunkCount;
+    if (opts.chunked) {
+      const { toChunks, buildTree } = await import(
+        pathToFileURL(`${circuitsDir}/chunk_tree.js`).href
+      );
+      const { chunks, count } = toChunks(Buffer.from(opts.result, "utf-8"));
+      const nodes = buildTree(poseidon, chunks);
+      commitment = {
+        chunks: chunks.map(String),
+        resultRoot: nodes[0].toString(),
+        chunkCount: count.toString(),
+      };
+      chunkCount = count;
+    } else {
+      // Compute Poseidon hash of the result (as field element)
+      // Convert result text to a field element (use first 31 bytes of SHA256 to stay in field)
+      const resultSha = createHash("sha256").update(opts.result).digest();
+      const resultField = BigInt(`0x${resultSha.subarray(0, 31).toString("hex")}`);
+      commitment = {
+        result: resultField.toString(),
+        expectedHash: poseidon.F.toString(poseidon([resultField])),
+      };
+      chunkCount = 1;
+    }
+
+    // The proof is bound to this task and provider, so it cannot be replayed elsewhere
+    const [taskHi, taskLo] = keyToFieldElements(taskPda);
+    const [providerHi, providerLo] = keyToFieldElements(keypair.publicKey);
+
+    console.error(`Generating ZK proof (${circuit})...`);
 
     // Generate Groth16 proof
-    const circuitsDir = opts.circuitsDir;
     const { proof, publicSignals } = await snarkjs.groth16.fullProve(
-      { result: resultField.toString(), expectedHash: poseidonHash },
-      `${circuitsDir}/task_verify_js/task_verify.wasm`,
-      `${circuitsDir}/task_verify.zkey`,
+      {
+        ...commitment,
+        taskHi: taskHi.toString(),
+        taskLo: taskLo.toString(),
+        providerHi: providerHi.toString(),
+        providerLo: providerLo.toString(),
+      },
+      `${circuitsDir}/${circuit}_js/${circuit}.wasm`,
+      `${circuitsDir}/${circuit}.zkey`,
     );
 
     console.error("ZK proof generated. Submitting to Solana...");
@@ -437,7 +544,8 @@ cli
     // groth16-solana expects: proof_a negated big-endian, proof_b big-endian, proof_c big-endian
     const proofParsed = unstringifyProof(proof);
 
-    // Convert public signal (Poseidon hash) to 32-byte big-endian
+    // The first public signal (Poseidon hash or chunk root) becomes the result hash;
+    // the program rebuilds the count, task and provider inputs itself
     const hashBigInt = BigInt(publicSignals[0]);
     const resultHashBytes = bigIntToBytes32BE(hashBigInt);
 
@@ -447,10 +555,12 @@ cli
         Array.from(proofParsed.b),
         Array.from(proofParsed.c),
         Array.from(resultHashBytes),
+        chunkCount,
       )
       .accounts({
         provider: keypair.publicKey,
-        taskRequest: new PublicKey(opts.taskPda),
+        taskRequest: taskPda,
+        verifyingKey,
       })
       .rpc();
 
@@ -459,6 +569,7 @@ cli
         status: "ok",
         taskPda: opts.taskPda,
         resultHash: Buffer.from(resultHashBytes).toString("hex"),
+        chunkCount,
         zkVerified: true,
         tx,
       }),
@@ -475,14 +586,25 @@ cli
   .requiredOption("--service-pda <pda>", "Service listing PDA address")
   .action(async (opts) => {
     const { program, keypair } = getProgram(cli.opts().url, cli.opts().keypair);
+    const [configPda] = findConfigPda();
+    const config = await program.account.config.fetch(configPda);
+    const provider = new PublicKey(opts.provider);
 
     const tx = await program.methods
       .acceptResult()
       .accounts({
         requester: keypair.publicKey,
         taskRequest: new PublicKey(opts.taskPda),
-        provider: new PublicKey(opts.provider),
+        provider,
         serviceListing: new PublicKey(opts.servicePda),
+        providerProfile: findProfilePda(provider)[0],
+        config: configPda,
+        treasury: config.treasury,
+        mint: null,
+        escrowTokenAccount: null,
+        providerTokenAccount: null,
+        treasuryTokenAccount: null,
+        tokenProgram: null,
       })
       .rpc();
 
@@ -532,12 +654,19 @@ cli
   .requiredOption("--requester <pubkey>", "Requester wallet to refund")
   .action(async (opts) => {
     const { program } = getProgram(cli.opts().url, cli.opts().keypair);
+    const taskRequestPda = new PublicKey(opts.taskPda);
+    const task = await program.account.taskRequest.fetch(taskRequestPda);
 
     const tx = await program.methods
       .expireTask()
       .accounts({
         requester: new PublicKey(opts.requester),
-        taskRequest: new PublicKey(opts.taskPda),
+        taskRequest: taskRequestPda,
+        providerProfile: taskProfilePda(task),
+        mint: null,
+        escrowTokenAccount: null,
+        requesterTokenAccount: null,
+        tokenProgram: null,
       })
       .rpc();
 
@@ -645,4 +774,70 @@ cli
     );
   });
 
+// ── upload-vk ───────────────────────────────────────────────────────────────
+
+cli
+  .command("upload-vk")
+  .description("Register a circuit's verifying key on-chain (as protocol admin)")
+  .requiredOption(
+    "-c, --circuit <name>",
+    `Circuit name (${Object.keys(ZK_CIRCUITS).join(", ")})`,
+  )
+  .requiredOption("--vk-version <n>", "Key version, bumped on every trusted setup")
+  .requiredOption("--vk <path>", "snarkjs verification key JSON (circuits/<name>_vk.json)")
+  .action(async (opts) => {
+    const { program, keypair } = getProgram(cli.opts().url, cli.opts().keypair);
+
+    const circuit = ZK_CIRCUITS[opts.circuit];
+    if (!circuit) {
+      console.log(JSON.stringify({ status: "error", message: `Unknown circuit: ${opts.circuit}` }));
+      process.exit(1);
+    }
+    const version = parseInt(opts.vkVersion, 10);
+    const vk = JSON.parse(readFileSync(opts.vk, "utf-8"));
+    const [verifyingKey] = findVerifyingKeyPda(opts.circuit, version);
+
+    const initTx = await program.methods
+      .initVerifyingKey(
+        circuit.variant,
+        version,
+        vk.nPublic,
+        Array.from(g1ToBytes(vk.vk_alpha_1)),
+        Array.from(g2ToBytes(vk.vk_beta_2)),
+        Array.from(g2ToBytes(vk.vk_gamma_2)),
+        Array.from(g2ToBytes(vk.vk_delta_2)),
+      )
+      .accounts({
+        admin: keypair.publicKey,
+        verifyingKey,
+      })
+      .rpc();
+
+    // IC holds nPublic + 1 points; append them a few at a time to stay under the
+    // transaction size limit
+    const ic = vk.IC.map((point) => Array.from(g1ToBytes(point)));
+    const appendTxs = [];
+    for (let i = 0; i < ic.length; i += 8) {
+      appendTxs.push(
+        await program.methods
+          .appendVerifyingKeyIc(ic.slice(i, i + 8))
+          .accounts({
+            admin: keypair.publicKey,
+            verifyingKey,
+          })
+          .rpc(),
+      );
+    }
+
+    console.log(
+      JSON.stringify({
+        status: "ok",
+        circuit: opts.circuit,
+        version,
+        verifyingKey: verifyingKey.toBase58(),
+        txs: [initTx, ...appendTxs],
+      }),
+    );
+  });
+
 cli.parse();
@@ -0,0 +1,775 @@
+//! `agentpay`: command-line client for operators and agents.
+//!
+//! Every command prints one JSON object on stdout. With `--dry-run` nothing is
+//! signed or sent; the unsigned transaction is printed as base64 (bincode wire
+//! format) together with the keys that must sign it, so it can be signed offline.
+
+mod proof;
+mod render;
+mod rpc;
+
+use std::cell::OnceCell;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use agentpay_client::state::{ServiceListing, TaskRequest, ZkCircuit};
+use agentpay_client::{accounts, instructions, pda, Instruction, TokenAccounts, ID};
+use anchor_lang::Discriminator;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anyhow::{anyhow, ensure, Context as _, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use clap::{Parser, Subcommand};
+use serde_json::{json, Value};
+use solana_sdk::hash::hash;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    message::Message,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair},
+    signer::Signer,
+    transaction::Transaction,
+};
+
+use crate::rpc::Rpc;
+
+const DEFAULT_URL: &str = "https://api.devnet.solana.com";
+
+/// Compute limit for instructions that run a Groth16 pairing check.
+const ZK_COMPUTE_UNITS: u32 = 1_000_000;
+
+#[derive(Parser)]
+#[command(
+    name = "agentpay",
+    version,
+    about = "AgentPay — agent-to-agent payments on Solana"
+)]
+struct Cli {
+    /// Keypair that signs and pays for transactions
+    #[arg(
+        short,
+        long,
+        global = true,
+        env = "AGENTPAY_KEYPAIR",
+        default_value = "~/.config/solana/id.json"
+    )]
+    keypair: String,
+
+    /// Solana RPC URL
+    #[arg(short, long, global = true, default_value = DEFAULT_URL)]
+    url: String,
+
+    /// Print the unsigned transaction instead of signing and sending it
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Key to build a dry-run transaction for, in place of the keypair file
+    #[arg(long, global = true, requires = "dry_run")]
+    signer: Option<Pubkey>,
+
+    /// Recent blockhash for a dry-run transaction (fetched from the RPC by default)
+    #[arg(long, global = true, requires = "dry_run")]
+    blockhash: Option<Hash>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Register a new service listing (as provider)
+    RegisterService {
+        /// Service description (max 128 bytes)
+        #[arg(short, long)]
+        description: String,
+        /// Price in lamports, or in base units of --mint
+        #[arg(short, long)]
+        price: u64,
+        /// Arbiter who rules on disputes
+        #[arg(long)]
+        arbiter: Pubkey,
+        /// Price the listing in this SPL / Token-2022 mint instead of SOL
+        #[arg(long)]
+        mint: Option<Pubkey>,
+        /// Seconds the requester has to review a result before it auto-accepts (at most 30 days)
+        #[arg(long, default_value_t = 86_400)]
+        review_window: i64,
+        /// Share of escrow the provider keeps if the requester cancels, in bps
+        #[arg(long, default_value_t = 0)]
+        cancellation_fee_bps: u16,
+        /// Completed tasks the provider must prove before requesters can book it (0 = none)
+        #[arg(long, default_value_t = 0)]
+        min_reputation: u64,
+        /// Service id as 32 hex characters (random by default)
+        #[arg(long, value_parser = parse_hex::<16>)]
+        service_id: Option<[u8; 16]>,
+    },
+    /// Deactivate a service listing (as provider)
+    DeactivateService {
+        /// Service listing PDA
+        #[arg(long)]
+        listing: Pubkey,
+    },
+    /// List service listings
+    ListServices {
+        /// Only listings of this provider
+        #[arg(long)]
+        provider: Option<Pubkey>,
+        /// Include inactive listings
+        #[arg(long)]
+        all: bool,
+    },
+    /// Create a task and lock payment in escrow (as requester)
+    CreateTask {
+        /// Service listing PDA
+        #[arg(long)]
+        listing: Pubkey,
+        /// Task description (max 256 bytes)
+        #[arg(short, long)]
+        description: String,
+        /// Deadline in minutes from now
+        #[arg(long, default_value_t = 60)]
+        deadline_minutes: i64,
+        /// Task id as 32 hex characters (random by default)
+        #[arg(long, value_parser = parse_hex::<16>)]
+        task_id: Option<[u8; 16]>,
+    },
+    /// Post a bounty any provider meeting a reputation bar can claim (as requester)
+    CreateBounty {
+        /// Bounty description (max 256 bytes)
+        #[arg(short, long)]
+        description: String,
+        /// Reward in lamports, or in base units of --mint
+        #[arg(short, long)]
+        amount: u64,
+        /// Arbiter who rules on disputes
+        #[arg(long)]
+        arbiter: Pubkey,
+        /// Pay the bounty in this SPL / Token-2022 mint instead of SOL
+        #[arg(long)]
+        mint: Option<Pubkey>,
+        /// Deadline in minutes from now
+        #[arg(long, default_value_t = 60)]
+        deadline_minutes: i64,
+        /// Completed tasks a provider needs to claim the bounty (0 = anyone)
+        #[arg(long, default_value_t = 0)]
+        min_reputation: u64,
+        /// Seconds a claimant has to submit before others may claim (at most 30 days)
+        #[arg(long, default_value_t = 3_600)]
+        claim_timeout: i64,
+        /// Seconds the requester has to review a result before it auto-accepts (at most 30 days)
+        #[arg(long, default_value_t = 86_400)]
+        review_window: i64,
+        /// Task id as 32 hex characters (random by default)
+        #[arg(long, value_parser = parse_hex::<16>)]
+        task_id: Option<[u8; 16]>,
+    },
+    /// Claim an open bounty, or one whose claim has lapsed (as provider)
+    ClaimBounty {
+        /// Task request PDA
+        #[arg(long)]
+        task: Pubkey,
+    },
+    /// Withdraw an unclaimed bounty and refund its escrow (as requester)
+    CancelBounty {
+        /// Task request PDA
+        #[arg(long)]
+        task: Pubkey,
+    },
+    /// List tasks
+    ListTasks {
+        /// Only tasks of this requester
+        #[arg(long)]
+        requester: Option<Pubkey>,
+        /// Only tasks of this provider
+        #[arg(long)]
+        provider: Option<Pubkey>,
+        /// Only tasks in this status (open, inProgress, submitted, completed, ...)
+        #[arg(long)]
+        status: Option<String>,
+    },
+    /// Submit a result hash for a task (as provider)
+    SubmitResult {
+        /// Task request PDA
+        #[arg(long)]
+        task: Pubkey,
+        /// Result text, SHA-256 hashed
+        #[arg(short, long, required_unless_present = "result_hash")]
+        result: Option<String>,
+        /// Precomputed result hash as 64 hex characters
+        #[arg(long, conflicts_with = "result", value_parser = parse_hex::<32>)]
+        result_hash: Option<[u8; 32]>,
+    },
+    /// Submit a result with a Groth16 proof generated by snarkjs (as provider)
+    SubmitResultZk {
+        /// Task request PDA
+        #[arg(long)]
+        task: Pubkey,
+        /// snarkjs proof.json
+        #[arg(long)]
+        proof: PathBuf,
+        /// snarkjs public.json
+        #[arg(long)]
+        public: PathBuf,
+        /// Proof is from task_verify_chunked rather than task_verify_v2
+        #[arg(long)]
+        chunked: bool,
+        /// Verifying key version the proof was generated against
+        #[arg(long, default_value_t = 1)]
+        vk_version: u16,
+    },
+    /// Accept a submitted result and release escrow to the provider (as requester)
+    AcceptResult {
+        /// Task request PDA
+        #[arg(long)]
+        task: Pubkey,
+    },
+    /// Dispute a submitted result, locking escrow until the arbiter rules (as requester)
+    DisputeTask {
+        /// Task request PDA
+        #[arg(long)]
+        task: Pubkey,
+    },
+    /// Review a completed or disputed task (as requester)
+    SubmitReview {
+        /// Task request PDA
+        #[arg(long)]
+        task: Pubkey,
+        /// Score from 1 to 5
+        #[arg(short, long, value_parser = clap::value_parser!(u8).range(1..=5))]
+        score: u8,
+        /// Review text, SHA-256 hashed
+        #[arg(short, long, required_unless_present = "review_hash")]
+        review: Option<String>,
+        /// Precomputed review hash as 64 hex characters
+        #[arg(long, conflicts_with = "review", value_parser = parse_hex::<32>)]
+        review_hash: Option<[u8; 32]>,
+    },
+    /// Expire a task past its deadline and refund the requester (anyone)
+    ExpireTask {
+        /// Task request PDA
+        #[arg(long)]
+        task: Pubkey,
+    },
+    /// Fetch and decode any AgentPay account
+    Decode {
+        /// Account address
+        address: Pubkey,
+    },
+}
+
+fn parse_hex<const N: usize>(text: &str) -> std::result::Result<[u8; N], String> {
+    let bytes = hex::decode(text).map_err(|err| err.to_string())?;
+    bytes
+        .try_into()
+        .map_err(|_| format!("expected {} hex characters", N * 2))
+}
+
+fn fixed<const N: usize>(field: &str, text: &str) -> Result<[u8; N]> {
+    agentpay_client::text(text).ok_or_else(|| anyhow!("{field} is longer than {N} bytes"))
+}
+
+fn random_id() -> [u8; 16] {
+    rand::random()
+}
+
+fn now() -> Result<i64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn read_json(path: &PathBuf) -> Result<Value> {
+    let text =
+        std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display()))
+}
+
+struct Context {
+    rpc: Rpc,
+    keypair_path: PathBuf,
+    keypair: OnceCell<Keypair>,
+    signer: Option<Pubkey>,
+    dry_run: bool,
+    blockhash: Option<Hash>,
+}
+
+impl Context {
+    fn new(cli: &Cli) -> Self {
+        Self {
+            rpc: Rpc::new(cli.url.clone()),
+            keypair_path: expand_home(&cli.keypair),
+            keypair: OnceCell::new(),
+            signer: cli.signer,
+            dry_run: cli.dry_run,
+            blockhash: cli.blockhash,
+        }
+    }
+
+    /// The keypair file, read on first use so read-only commands work without one.
+    fn keypair(&self) -> Result<&Keypair> {
+        if self.keypair.get().is_none() {
+            let path = &self.keypair_path;
+            let keypair = read_keypair_file(path)
+                .map_err(|err| anyhow!("reading keypair {}: {err}", path.display()))?;
+            let _ = self.keypair.set(keypair);
+        }
+        Ok(self.keypair.get().expect("keypair just read"))
+    }
+
+    /// Fee payer and, for commands that act as the caller, the acting party.
+    fn payer(&self) -> Result<Pubkey> {
+        match self.signer {
+            Some(signer) => Ok(signer),
+            None => Ok(self.keypair()?.pubkey()),
+        }
+    }
+
+    fn fetch(&self, address: &Pubkey) -> Result<rpc::Account> {
+        let account = self
+            .rpc
+            .account(address)?
+            .ok_or_else(|| anyhow!("account {address} not found"))?;
+        ensure!(
+            account.owner == ID,
+            "{address} is not owned by the AgentPay program"
+        );
+        Ok(account)
+    }
+
+    fn listing(&self, address: &Pubkey) -> Result<ServiceListing> {
+        Ok(accounts::service_listing(&self.fetch(address)?.data)?)
+    }
+
+    fn task(&self, address: &Pubkey) -> Result<TaskRequest> {
+        Ok(accounts::task_request(&self.fetch(address)?.data)?)
+    }
+
+    fn treasury(&self) -> Result<Pubkey> {
+        Ok(accounts::config(&self.fetch(&pda::config())?.data)?.treasury)
+    }
+
+    /// Associated token accounts of the parties, or `None` for a native SOL escrow.
+    fn token_accounts(
+        &self,
+        mint: &Pubkey,
+        requester: &Pubkey,
+        provider: &Pubkey,
+    ) -> Result<Option<TokenAccounts>> {
+        if *mint == Pubkey::default() {
+            return Ok(None);
+        }
+        let token_program = self
+            .rpc
+            .account(mint)?
+            .ok_or_else(|| anyhow!("mint {mint} not found"))?
+            .owner;
+        let ata = |wallet: &Pubkey| {
+            get_associated_token_address_with_program_id(wallet, mint, &token_program)
+        };
+        Ok(Some(TokenAccounts {
+            mint: *mint,
+            token_program,
+            requester: ata(requester),
+            provider: ata(provider),
+            treasury: ata(&self.treasury()?),
+        }))
+    }
+
+    /// Sign and send `instructions` (or print them unsigned with `--dry-run`), then
+    /// print `output` with the signature or transaction added.
+    fn submit(&self, instructions: &[Instruction], mut output: Value) -> Result<()> {
+        let blockhash = match self.blockhash {
+            Some(blockhash) => blockhash,
+            None => self.rpc.latest_blockhash()?,
+        };
+        let mut message = Message::new(instructions, Some(&self.payer()?));
+        message.recent_blockhash = blockhash;
+        let mut transaction = Transaction::new_unsigned(message);
+        let signers = transaction.message.account_keys
+            [..transaction.message.header.num_required_signatures as usize]
+            .iter()
+            .map(Pubkey::to_string)
+            .collect::<Vec<_>>();
+
+        if self.dry_run {
+            output["status"] = json!("dry-run");
+            output["signers"] = json!(signers);
+            output["blockhash"] = json!(blockhash.to_string());
+            output["transaction"] = json!(BASE64.encode(bincode::serialize(&transaction)?));
+        } else {
+            transaction
+                .try_sign(&[self.keypair()?], blockhash)
+                .map_err(|err| {
+                    anyhow!(
+                        "{err}: this transaction must be signed by {}",
+                        signers.join(", ")
+                    )
+                })?;
+            output["tx"] = json!(self.rpc.send(&transaction)?.to_string());
+        }
+        println!("{output}");
+        Ok(())
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let ctx = Context::new(&cli);
+    match cli.command {
+        Command::RegisterService {
+            description,
+            price,
+            arbiter,
+            mint,
+            review_window,
+            cancellation_fee_bps,
+            min_reputation,
+            service_id,
+        } => {
+            let provider = ctx.payer()?;
+            let service_id = service_id.unwrap_or_else(random_id);
+            let listing = pda::service(&provider, &service_id);
+            let ix = instructions::register_service(
+                &provider,
+                service_id,
+                fixed("description", &description)?,
+                price,
+                min_reputation,
+                arbiter,
+                review_window,
+                cancellation_fee_bps,
+                mint,
+            );
+            ctx.submit(
+                &[ix],
+                json!({
+                    "status": "ok",
+                    "serviceId": hex::encode(service_id),
+                    "servicePda": listing.to_string(),
+                    "provider": provider.to_string(),
+                }),
+            )
+        }
+
+        Command::DeactivateService { listing } => {
+            let state = ctx.listing(&listing)?;
+            let ix = instructions::deactivate_service(&state.provider, &listing);
+            ctx.submit(
+                &[ix],
+                json!({ "status": "ok", "servicePda": listing.to_string(), "action": "deactivated" }),
+            )
+        }
+
+        Command::ListServices { provider, all } => {
+            let mut filters = vec![(0, ServiceListing::DISCRIMINATOR)];
+            if let Some(provider) = &provider {
+                filters.push((8, provider.as_ref()));
+            }
+            let services: Vec<Value> = ctx
+                .rpc
+                .program_accounts(&ID, &filters)?
+                .iter()
+                // Skip accounts left in an older layout
+                .filter_map(|(address, account)| {
+                    let listing = accounts::service_listing(&account.data).ok()?;
+                    (all || listing.is_active).then(|| render::service_listing(address, &listing))
+                })
+                .collect();
+            println!(
+                "{}",
+                json!({ "status": "ok", "count": services.len(), "services": services })
+            );
+            Ok(())
+        }
+
+        Command::CreateTask {
+            listing,
+            description,
+            deadline_minutes,
+            task_id,
+        } => {
+            let state = ctx.listing(&listing)?;
+            ensure!(state.is_active, "service {listing} is not active");
+            let requester = ctx.payer()?;
+            let token = ctx.token_accounts(&state.mint, &requester, &state.provider)?;
+            let task_id = task_id.unwrap_or_else(random_id);
+            let deadline = now()? + deadline_minutes * 60;
+            let ix = instructions::create_task(
+                &requester,
+                &listing,
+                &state.provider,
+                task_id,
+                fixed("description", &description)?,
+                deadline,
+                token.as_ref(),
+            );
+            ctx.submit(
+                &[ix],
+                json!({
+                    "status": "ok",
+                    "taskId": hex::encode(task_id),
+                    "taskPda": pda::task(&requester, &task_id).to_string(),
+                    "servicePda": listing.to_string(),
+                    "provider": state.provider.to_string(),
+                    "deadline": deadline,
+                }),
+            )
+        }
+
+        Command::CreateBounty {
+            description,
+            amount,
+            arbiter,
+            mint,
+            deadline_minutes,
+            min_reputation,
+            claim_timeout,
+            review_window,
+            task_id,
+        } => {
+            let requester = ctx.payer()?;
+            // No provider yet; only the requester's and the escrow's token accounts are used
+            let token =
+                ctx.token_accounts(&mint.unwrap_or_default(), &requester, &Pubkey::default())?;
+            let task_id = task_id.unwrap_or_else(random_id);
+            let deadline = now()? + deadline_minutes * 60;
+            let ix = instructions::create_bounty(
+                &requester,
+                task_id,
+                fixed("description", &description)?,
+                amount,
+                deadline,
+                min_reputation,
+                claim_timeout,
+                arbiter,
+                review_window,
+                token.as_ref(),
+            );
+            ctx.submit(
+                &[ix],
+                json!({
+                    "status": "ok",
+                    "taskId": hex::encode(task_id),
+                    "taskPda": pda::task(&requester, &task_id).to_string(),
+                    "amount": amount,
+                    "minReputation": min_reputation,
+                    "deadline": deadline,
+                }),
+            )
+        }
+
+        Command::ClaimBounty { task } => {
+            let state = ctx.task(&task)?;
+            ensure!(state.is_bounty(), "task {task} is not a bounty");
+            let provider = ctx.payer()?;
+            let ix = instructions::claim_bounty(&provider, &task);
+            ctx.submit(
+                &[ix],
+                json!({
+                    "status": "ok",
+                    "taskPda": task.to_string(),
+                    "provider": provider.to_string(),
+                    "action": "claimed",
+                }),
+            )
+        }
+
+        Command::CancelBounty { task } => {
+            let state = ctx.task(&task)?;
+            let token = ctx.token_accounts(&state.mint, &state.requester, &state.provider)?;
+            let ix = instructions::cancel_bounty(&task, &state, token.as_ref());
+            ctx.submit(
+                &[ix],
+                json!({ "status": "ok", "taskPda": task.to_string(), "action": "cancelled" }),
+            )
+        }
+
+        Command::ListTasks {
+            requester,
+            provider,
+            status,
+        } => {
+            let mut filters = vec![(0, TaskRequest::DISCRIMINATOR)];
+            if let Some(requester) = &requester {
+                filters.push((8, requester.as_ref()));
+            }
+            if let Some(provider) = &provider {
+                filters.push((40, provider.as_ref()));
+            }
+            let tasks: Vec<Value> = ctx
+                .rpc
+                .program_accounts(&ID, &filters)?
+                .iter()
+                .filter_map(|(address, account)| {
+                    let task = accounts::task_request(&account.data).ok()?;
+                    let wanted = status
+                        .as_deref()
+                        .is_none_or(|status| status == render::task_status(task.status));
+                    wanted.then(|| render::task_request(address, &task))
+                })
+                .collect();
+            println!(
+                "{}",
+                json!({ "status": "ok", "count": tasks.len(), "tasks": tasks })
+            );
+            Ok(())
+        }
+
+        Command::SubmitResult {
+            task,
+            result,
+            result_hash,
+        } => {
+            let state = ctx.task(&task)?;
+            let result_hash = match (result_hash, result) {
+                (Some(hash), _) => hash,
+                (None, Some(result)) => hash(result.as_bytes()).to_bytes(),
+                (None, None) => unreachable!("clap requires --result or --result-hash"),
+            };
+            let ix = instructions::submit_result(&task, &state, result_hash);
+            ctx.submit(
+                &[ix],
+                json!({
+                    "status": "ok",
+                    "taskPda": task.to_string(),
+                    "resultHash": hex::encode(result_hash),
+                }),
+            )
+        }
+
+        Command::SubmitResultZk {
+            task,
+            proof,
+            public,
+            chunked,
+            vk_version,
+        } => {
+            let state = ctx.task(&task)?;
+            let groth16 = proof::proof(&read_json(&proof)?)?;
+            let signals = proof::public_signals(&read_json(&public)?)?;
+            let (circuit, chunk_count) = if chunked {
+                // [resultRoot, chunkCount, ...]
+                let count = signals
+                    .get(1)
+                    .ok_or_else(|| anyhow!("public.json is missing chunkCount"))?;
+                ensure!(count[..28] == [0u8; 28], "chunkCount does not fit in a u32");
+                let count = u32::from_be_bytes(count[28..].try_into()?);
+                (ZkCircuit::TaskVerifyChunked, count)
+            } else {
+                (ZkCircuit::TaskVerify, 1)
+            };
+            ensure!(
+                signals.len() == circuit.public_inputs(),
+                "public.json has {} signals; the {} circuit has {}",
+                signals.len(),
+                if chunked {
+                    "task_verify_chunked"
+                } else {
+                    "task_verify_v2"
+                },
+                circuit.public_inputs()
+            );
+            let result_hash = signals[0];
+            let ixs = [
+                ComputeBudgetInstruction::set_compute_unit_limit(ZK_COMPUTE_UNITS),
+                instructions::submit_result_zk(
+                    &task,
+                    &state,
+                    circuit,
+                    vk_version,
+                    &groth16,
+                    result_hash,
+                    chunk_count,
+                ),
+            ];
+            ctx.submit(
+                &ixs,
+                json!({
+                    "status": "ok",
+                    "taskPda": task.to_string(),
+                    "resultHash": hex::encode(result_hash),
+                    "chunkCount": chunk_count,
+                    "zkVerified": true,
+                }),
+            )
+        }
+
+        Command::AcceptResult { task } => {
+            let state = ctx.task(&task)?;
+            let token = ctx.token_accounts(&state.mint, &state.requester, &state.provider)?;
+            let ix = instructions::accept_result(&task, &state, &ctx.treasury()?, token.as_ref());
+            ctx.submit(
+                &[ix],
+                json!({ "status": "ok", "taskPda": task.to_string(), "action": "accepted" }),
+            )
+        }
+
+        Command::DisputeTask { task } => {
+            let state = ctx.task(&task)?;
+            let ix = instructions::dispute_task(&task, &state);
+            ctx.submit(
+                &[ix],
+                json!({ "status": "ok", "taskPda": task.to_string(), "action": "disputed" }),
+            )
+        }
+
+        Command::SubmitReview {
+            task,
+            score,
+            review,
+            review_hash,
+        } => {
+            let state = ctx.task(&task)?;
+            let review_hash = match (review_hash, review) {
+                (Some(hash), _) => hash,
+                (None, Some(review)) => hash(review.as_bytes()).to_bytes(),
+                (None, None) => unreachable!("clap requires --review or --review-hash"),
+            };
+            let ix = instructions::submit_review(&task, &state, score, review_hash);
+            ctx.submit(
+                &[ix],
+                json!({
+                    "status": "ok",
+                    "taskPda": task.to_string(),
+                    "reviewPda": pda::review(&task).to_string(),
+                    "score": score,
+                    "reviewHash": hex::encode(review_hash),
+                }),
+            )
+        }
+
+        Command::ExpireTask { task } => {
+            let state = ctx.task(&task)?;
+            let token = ctx.token_accounts(&state.mint, &state.requester, &state.provider)?;
+            let ix = instructions::expire_task(&task, &state, token.as_ref());
+            ctx.submit(
+                &[ix],
+                json!({ "status": "ok", "taskPda": task.to_string(), "action": "expired" }),
+            )
+        }
+
+        Command::Decode { address } => {
+            let account = ctx.fetch(&address)?;
+            let mut output = render::any(&address, &account.data)?;
+            output["status"] = json!("ok");
+            output["address"] = json!(address.to_string());
+            println!("{output}");
+            Ok(())
+        }
+    }
+}
+
+fn main() {
+    if let Err(err) = run(Cli::parse()) {
+        println!(
+            "{}",
+            json!({ "status": "error", "message": format!("{err:#}") })
+        );
+        std::process::exit(1);
+    }
+}
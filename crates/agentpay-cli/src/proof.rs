@@ -0,0 +1,134 @@
+//! Conversion of snarkjs `proof.json` / `public.json` into the big-endian encoding
+//! the on-chain Groth16 verifier expects.
+
+use agentpay_client::Proof;
+use anyhow::{anyhow, bail, ensure, Result};
+use serde_json::Value;
+
+/// BN254 base field modulus, big-endian.
+const FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d, 0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x47,
+];
+
+/// A decimal field element as 32 big-endian bytes.
+pub fn field(decimal: &str) -> Result<[u8; 32]> {
+    ensure!(!decimal.is_empty(), "empty field element");
+    let mut out = [0u8; 32];
+    for digit in decimal.chars() {
+        let mut carry = digit
+            .to_digit(10)
+            .ok_or_else(|| anyhow!("invalid field element {decimal:?}"))?;
+        for byte in out.iter_mut().rev() {
+            let value = *byte as u32 * 10 + carry;
+            *byte = value as u8;
+            carry = value >> 8;
+        }
+        ensure!(
+            carry == 0,
+            "field element {decimal:?} does not fit in 32 bytes"
+        );
+    }
+    Ok(out)
+}
+
+/// `-y mod p`, as the verifier takes `pi_a` with its y coordinate negated.
+fn negate(y: [u8; 32]) -> [u8; 32] {
+    if y == [0u8; 32] {
+        return y;
+    }
+    let mut out = [0u8; 32];
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let mut value = FIELD_MODULUS[i] as i16 - y[i] as i16 - borrow;
+        borrow = (value < 0) as i16;
+        if value < 0 {
+            value += 256;
+        }
+        out[i] = value as u8;
+    }
+    out
+}
+
+fn coordinate(value: &Value) -> Result<[u8; 32]> {
+    field(
+        value
+            .as_str()
+            .ok_or_else(|| anyhow!("expected a decimal string, got {value}"))?,
+    )
+}
+
+/// A snarkjs Groth16 proof (`pi_a`, `pi_b`, `pi_c`) in verifier encoding.
+pub fn proof(json: &Value) -> Result<Proof> {
+    let (a, b, c) = (&json["pi_a"], &json["pi_b"], &json["pi_c"]);
+    if a.is_null() || b.is_null() || c.is_null() {
+        bail!("not a snarkjs proof: missing pi_a, pi_b or pi_c");
+    }
+
+    let mut proof = Proof {
+        a: [0u8; 64],
+        b: [0u8; 128],
+        c: [0u8; 64],
+    };
+    proof.a[..32].copy_from_slice(&coordinate(&a[0])?);
+    proof.a[32..].copy_from_slice(&negate(coordinate(&a[1])?));
+    // G2 coordinates are (c0, c1) in snarkjs but (c1, c0) on chain
+    for (i, value) in [&b[0][1], &b[0][0], &b[1][1], &b[1][0]]
+        .into_iter()
+        .enumerate()
+    {
+        proof.b[i * 32..(i + 1) * 32].copy_from_slice(&coordinate(value)?);
+    }
+    proof.c[..32].copy_from_slice(&coordinate(&c[0])?);
+    proof.c[32..].copy_from_slice(&coordinate(&c[1])?);
+    Ok(proof)
+}
+
+/// The public signals of a snarkjs `public.json`, in circuit order.
+pub fn public_signals(json: &Value) -> Result<Vec<[u8; 32]>> {
+    json.as_array()
+        .ok_or_else(|| anyhow!("public signals must be a JSON array"))?
+        .iter()
+        .map(coordinate)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn field_parses_decimal_big_endian() {
+        let mut expected = [0u8; 32];
+        expected[30..].copy_from_slice(&[0x01, 0x00]);
+        assert_eq!(field("256").unwrap(), expected);
+        assert!(field("12a").is_err());
+        assert!(field(&"9".repeat(80)).is_err());
+    }
+
+    #[test]
+    fn negation_is_modulo_the_field() {
+        let one = field("1").unwrap();
+        let mut minus_one = FIELD_MODULUS;
+        minus_one[31] -= 1;
+        assert_eq!(negate(one), minus_one);
+        assert_eq!(negate(minus_one), one);
+        assert_eq!(negate([0u8; 32]), [0u8; 32]);
+    }
+
+    #[test]
+    fn proof_swaps_g2_coordinates() {
+        let json = json!({
+            "pi_a": ["1", "2", "1"],
+            "pi_b": [["3", "4"], ["5", "6"], ["1", "0"]],
+            "pi_c": ["7", "8", "1"],
+        });
+        let proof = proof(&json).unwrap();
+        assert_eq!(proof.a[31], 1);
+        assert_eq!(proof.a[32..], negate(field("2").unwrap()));
+        let b: Vec<u8> = proof.b.chunks(32).map(|chunk| chunk[31]).collect();
+        assert_eq!(b, [4, 3, 6, 5]);
+        assert_eq!((proof.c[31], proof.c[63]), (7, 8));
+    }
+}
@@ -0,0 +1,221 @@
+//! JSON views of the program's accounts, in the same shape the JS CLI prints.
+
+use agentpay_client::accounts;
+use agentpay_client::state::{
+    Config, EncryptedDelivery, Milestone, MilestoneStatus, ProviderProfile, Review, ServiceListing,
+    TaskRequest, TaskStatus, VerifyingKeyAccount, ZkCircuit,
+};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::Discriminator;
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+
+/// Zero-padded fixed-size text field, trimmed.
+pub fn text(bytes: &[u8]) -> String {
+    let end = bytes
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// `None` for the default key, which the program uses for "unset" (e.g. native SOL mint).
+fn optional(key: &Pubkey) -> Value {
+    if *key == Pubkey::default() {
+        Value::Null
+    } else {
+        json!(key.to_string())
+    }
+}
+
+pub fn task_status(status: TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Open => "open",
+        TaskStatus::Submitted => "submitted",
+        TaskStatus::Completed => "completed",
+        TaskStatus::Disputed => "disputed",
+        TaskStatus::Expired => "expired",
+        TaskStatus::DisputePending => "disputePending",
+        TaskStatus::InProgress => "inProgress",
+        TaskStatus::Cancelled => "cancelled",
+    }
+}
+
+fn milestone_status(status: MilestoneStatus) -> &'static str {
+    match status {
+        MilestoneStatus::Pending => "pending",
+        MilestoneStatus::Submitted => "submitted",
+        MilestoneStatus::Released => "released",
+        MilestoneStatus::DisputePending => "disputePending",
+        MilestoneStatus::Resolved => "resolved",
+    }
+}
+
+fn circuit(circuit: ZkCircuit) -> &'static str {
+    match circuit {
+        ZkCircuit::TaskVerify => "taskVerify",
+        ZkCircuit::ResultDelivery => "resultDelivery",
+        ZkCircuit::TaskVerifyChunked => "taskVerifyChunked",
+    }
+}
+
+pub fn service_listing(address: &Pubkey, listing: &ServiceListing) -> Value {
+    json!({
+        "pda": address.to_string(),
+        "provider": listing.provider.to_string(),
+        "serviceId": hex::encode(listing.service_id),
+        "description": text(&listing.description),
+        "price": listing.price_lamports,
+        "mint": optional(&listing.mint),
+        "isActive": listing.is_active,
+        "tasksCompleted": listing.tasks_completed,
+        "createdAt": listing.created_at,
+        "minReputation": listing.min_reputation,
+        "arbiter": listing.arbiter.to_string(),
+        "reviewWindow": listing.review_window,
+        "cancellationFeeBps": listing.cancellation_fee_bps,
+    })
+}
+
+pub fn task_request(address: &Pubkey, task: &TaskRequest) -> Value {
+    json!({
+        "pda": address.to_string(),
+        "taskId": hex::encode(task.task_id),
+        "requester": task.requester.to_string(),
+        "provider": task.provider.to_string(),
+        "serviceListing": task.service_listing.to_string(),
+        "description": text(&task.description),
+        "amount": task.amount_lamports,
+        "mint": optional(&task.mint),
+        "status": task_status(task.status),
+        "resultHash": hex::encode(task.result_hash),
+        "deadline": task.deadline,
+        "createdAt": task.created_at,
+        "submittedAt": task.submitted_at,
+        "zkVerified": task.zk_verified,
+        "zkVkVersion": task.zk_vk_version,
+        "resultChunkCount": task.result_chunk_count,
+        "arbiter": task.arbiter.to_string(),
+        "disputedAt": task.disputed_at,
+        "reviewWindow": task.review_window,
+        "cancellationFeeBps": task.cancellation_fee_bps,
+        "protocolFeeBps": task.protocol_fee_bps,
+        "milestoneCount": task.milestone_count,
+        "milestonesSettled": task.milestones_settled,
+        "milestoneAllocated": task.milestone_allocated,
+        "releasedAmount": task.released_amount,
+        "bounty": task.is_bounty(),
+        "minReputation": task.min_reputation,
+        "claimTimeout": task.claim_timeout,
+        "claimedAt": task.claimed_at,
+    })
+}
+
+fn provider_profile(profile: &ProviderProfile) -> Value {
+    json!({
+        "provider": profile.provider.to_string(),
+        "tasksCompleted": profile.tasks_completed,
+        "tasksDisputed": profile.tasks_disputed,
+        "tasksExpired": profile.tasks_expired,
+        "totalEarned": profile.total_earned,
+        "createdAt": profile.created_at,
+        "reviewCount": profile.review_count,
+        "reviewScoreTotal": profile.review_score_total,
+    })
+}
+
+fn review(review: &Review) -> Value {
+    json!({
+        "task": review.task.to_string(),
+        "reviewer": review.reviewer.to_string(),
+        "provider": review.provider.to_string(),
+        "serviceListing": review.service_listing.to_string(),
+        "score": review.score,
+        "reviewHash": hex::encode(review.review_hash),
+        "createdAt": review.created_at,
+    })
+}
+
+fn config(config: &Config) -> Value {
+    json!({
+        "admin": config.admin.to_string(),
+        "treasury": config.treasury.to_string(),
+        "feeBps": config.fee_bps,
+        "paused": config.paused,
+    })
+}
+
+fn milestone(milestone: &Milestone) -> Value {
+    json!({
+        "task": milestone.task.to_string(),
+        "index": milestone.index,
+        "amount": milestone.amount,
+        "description": text(&milestone.description),
+        "status": milestone_status(milestone.status),
+        "resultHash": hex::encode(milestone.result_hash),
+        "submittedAt": milestone.submitted_at,
+    })
+}
+
+fn encrypted_delivery(delivery: &EncryptedDelivery) -> Value {
+    json!({
+        "task": delivery.task.to_string(),
+        "encryptionKey": hex::encode(delivery.encryption_key),
+        "expectedHash": hex::encode(delivery.expected_hash),
+        "ephemeralKey": hex::encode(delivery.ephemeral_key),
+        "ciphertext": hex::encode(delivery.ciphertext),
+        "deliveredAt": delivery.delivered_at,
+    })
+}
+
+fn verifying_key(key: &VerifyingKeyAccount) -> Value {
+    json!({
+        "circuit": circuit(key.circuit),
+        "version": key.version,
+        "nrPubinputs": key.nr_pubinputs,
+        "icLoaded": key.ic.len(),
+        "revoked": key.revoked,
+        "createdAt": key.created_at,
+    })
+}
+
+/// Identify a program account by its discriminator and render it as
+/// `{ "type": ..., "account": ... }`.
+pub fn any(address: &Pubkey, data: &[u8]) -> Result<Value> {
+    let discriminator = data.get(..8).unwrap_or_default();
+    let (kind, account) = if discriminator == ServiceListing::DISCRIMINATOR {
+        (
+            "serviceListing",
+            service_listing(address, &accounts::service_listing(data)?),
+        )
+    } else if discriminator == TaskRequest::DISCRIMINATOR {
+        (
+            "taskRequest",
+            task_request(address, &accounts::task_request(data)?),
+        )
+    } else if discriminator == ProviderProfile::DISCRIMINATOR {
+        (
+            "providerProfile",
+            provider_profile(&accounts::provider_profile(data)?),
+        )
+    } else if discriminator == Review::DISCRIMINATOR {
+        ("review", review(&accounts::review(data)?))
+    } else if discriminator == Config::DISCRIMINATOR {
+        ("config", config(&accounts::config(data)?))
+    } else if discriminator == Milestone::DISCRIMINATOR {
+        ("milestone", milestone(&accounts::milestone(data)?))
+    } else if discriminator == EncryptedDelivery::DISCRIMINATOR {
+        (
+            "encryptedDelivery",
+            encrypted_delivery(&accounts::encrypted_delivery(data)?),
+        )
+    } else if discriminator == VerifyingKeyAccount::DISCRIMINATOR {
+        (
+            "verifyingKey",
+            verifying_key(&accounts::verifying_key(data)?),
+        )
+    } else {
+        bail!("{address} is not an AgentPay account");
+    };
+    Ok(json!({ "type": kind, "account": account }))
+}
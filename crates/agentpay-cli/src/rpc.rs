@@ -0,0 +1,138 @@
+//! Minimal JSON-RPC client: just the calls the CLI needs, over blocking HTTP.
+
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::{json, Value};
+use solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Signature, transaction::Transaction};
+
+/// How long `send` waits for a transaction to reach `confirmed`.
+const CONFIRM_ATTEMPTS: u32 = 60;
+const CONFIRM_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct Account {
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+}
+
+pub struct Rpc {
+    url: String,
+}
+
+impl Rpc {
+    pub fn new(url: String) -> Self {
+        Self { url }
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let mut response: Value = ureq::post(&self.url)
+            .send_json(request)
+            .with_context(|| format!("{method} request to {} failed", self.url))?
+            .into_json()?;
+        if let Some(error) = response.get("error") {
+            bail!("{method} failed: {error}");
+        }
+        Ok(response["result"].take())
+    }
+
+    pub fn account(&self, address: &Pubkey) -> Result<Option<Account>> {
+        let result = self.call(
+            "getAccountInfo",
+            json!([address.to_string(), { "encoding": "base64", "commitment": "confirmed" }]),
+        )?;
+        match &result["value"] {
+            Value::Null => Ok(None),
+            value => parse_account(value).map(Some),
+        }
+    }
+
+    /// Program accounts of `program` matching every `(offset, bytes)` filter.
+    pub fn program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: &[(usize, &[u8])],
+    ) -> Result<Vec<(Pubkey, Account)>> {
+        let filters: Vec<Value> = filters
+            .iter()
+            .map(|(offset, bytes)| {
+                json!({ "memcmp": { "offset": offset, "bytes": BASE64.encode(bytes), "encoding": "base64" } })
+            })
+            .collect();
+        let result = self.call(
+            "getProgramAccounts",
+            json!([program.to_string(), {
+                "encoding": "base64",
+                "commitment": "confirmed",
+                "filters": filters,
+            }]),
+        )?;
+        result
+            .as_array()
+            .ok_or_else(|| anyhow!("getProgramAccounts returned {result}"))?
+            .iter()
+            .map(|entry| {
+                let address = parse_pubkey(&entry["pubkey"])?;
+                Ok((address, parse_account(&entry["account"])?))
+            })
+            .collect()
+    }
+
+    pub fn latest_blockhash(&self) -> Result<Hash> {
+        let result = self.call("getLatestBlockhash", json!([{ "commitment": "confirmed" }]))?;
+        let blockhash = result["value"]["blockhash"]
+            .as_str()
+            .ok_or_else(|| anyhow!("getLatestBlockhash returned {result}"))?;
+        Ok(Hash::from_str(blockhash)?)
+    }
+
+    /// Send a signed transaction and wait until it is confirmed.
+    pub fn send(&self, transaction: &Transaction) -> Result<Signature> {
+        let wire = BASE64.encode(bincode::serialize(transaction)?);
+        let result = self.call(
+            "sendTransaction",
+            json!([wire, { "encoding": "base64", "preflightCommitment": "confirmed" }]),
+        )?;
+        let signature = result
+            .as_str()
+            .ok_or_else(|| anyhow!("sendTransaction returned {result}"))?;
+
+        for _ in 0..CONFIRM_ATTEMPTS {
+            let statuses = self.call("getSignatureStatuses", json!([[signature]]))?;
+            let status = &statuses["value"][0];
+            if !status.is_null() {
+                if !status["err"].is_null() {
+                    bail!("transaction {signature} failed: {}", status["err"]);
+                }
+                if matches!(
+                    status["confirmationStatus"].as_str(),
+                    Some("confirmed" | "finalized")
+                ) {
+                    return Ok(Signature::from_str(signature)?);
+                }
+            }
+            sleep(CONFIRM_INTERVAL);
+        }
+        bail!("transaction {signature} was not confirmed in time")
+    }
+}
+
+fn parse_pubkey(value: &Value) -> Result<Pubkey> {
+    let text = value
+        .as_str()
+        .ok_or_else(|| anyhow!("expected a public key, got {value}"))?;
+    Ok(Pubkey::from_str(text)?)
+}
+
+fn parse_account(value: &Value) -> Result<Account> {
+    let data = value["data"][0]
+        .as_str()
+        .ok_or_else(|| anyhow!("expected base64 account data, got {value}"))?;
+    Ok(Account {
+        owner: parse_pubkey(&value["owner"])?,
+        data: BASE64.decode(data)?,
+    })
+}
@@ -0,0 +1,45 @@
+//! Decoders for account data fetched from chain. Each checks the account
+//! discriminator, so data from the wrong account type is rejected.
+
+use agentpay::state::{
+    Config, EncryptedDelivery, Milestone, ProviderProfile, Review, ServiceListing, TaskRequest,
+    VerifyingKeyAccount,
+};
+use anchor_lang::{AccountDeserialize, Result};
+
+/// Decode any of the program's accounts from its raw data.
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+pub fn service_listing(data: &[u8]) -> Result<ServiceListing> {
+    decode(data)
+}
+
+pub fn provider_profile(data: &[u8]) -> Result<ProviderProfile> {
+    decode(data)
+}
+
+pub fn task_request(data: &[u8]) -> Result<TaskRequest> {
+    decode(data)
+}
+
+pub fn config(data: &[u8]) -> Result<Config> {
+    decode(data)
+}
+
+pub fn milestone(data: &[u8]) -> Result<Milestone> {
+    decode(data)
+}
+
+pub fn review(data: &[u8]) -> Result<Review> {
+    decode(data)
+}
+
+pub fn encrypted_delivery(data: &[u8]) -> Result<EncryptedDelivery> {
+    decode(data)
+}
+
+pub fn verifying_key(data: &[u8]) -> Result<VerifyingKeyAccount> {
+    decode(data)
+}
@@ -0,0 +1,925 @@
+//! Instruction builders, one per program handler.
+//!
+//! Builders take the signer and whatever state the program checks the accounts
+//! against: settlement builders need the decoded [`TaskRequest`] (for the provider,
+//! requester and listing) and the config's treasury. Token-denominated tasks also
+//! pass their [`TokenAccounts`]; native SOL tasks pass `None`.
+//!
+//! The Groth16 instructions (`submit_result_zk`, `submit_result_encrypted`) need
+//! about 1M compute units; prepend a compute-budget instruction when sending them.
+
+use agentpay::state::{TaskRequest, ZkCircuit};
+use agentpay::{accounts, instruction, ID};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+
+use crate::pda;
+
+/// Token accounts of an SPL / Token-2022 task, owned by each party.
+#[derive(Clone, Copy, Debug)]
+pub struct TokenAccounts {
+    pub mint: Pubkey,
+    /// SPL Token or Token-2022, whichever owns `mint`
+    pub token_program: Pubkey,
+    pub requester: Pubkey,
+    pub provider: Pubkey,
+    pub treasury: Pubkey,
+}
+
+/// A Groth16 proof in the encoding the program verifies.
+#[derive(Clone, Copy, Debug)]
+pub struct Proof {
+    /// G1, negated, big-endian
+    pub a: [u8; 64],
+    /// G2, big-endian
+    pub b: [u8; 128],
+    /// G1, big-endian
+    pub c: [u8; 64],
+}
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Optional token accounts of a settlement, in the order every settlement struct
+/// declares them.
+struct Escrow {
+    mint: Option<Pubkey>,
+    escrow_token_account: Option<Pubkey>,
+    token_program: Option<Pubkey>,
+}
+
+impl Escrow {
+    fn of(task: &Pubkey, token: Option<&TokenAccounts>) -> Self {
+        Self {
+            mint: token.map(|token| token.mint),
+            escrow_token_account: token.map(|_| pda::escrow(task)),
+            token_program: token.map(|token| token.token_program),
+        }
+    }
+}
+
+/// The task's listing, which completing settlements bump; bounties have none.
+fn listing(state: &TaskRequest) -> Option<Pubkey> {
+    (!state.is_bounty()).then_some(state.service_listing)
+}
+
+/// The provider's profile, which settlements book; an unclaimed bounty has none.
+fn profile(state: &TaskRequest) -> Option<Pubkey> {
+    state
+        .has_provider()
+        .then(|| pda::provider_profile(&state.provider))
+}
+
+// ============================================================================
+// Config and verifying keys
+// ============================================================================
+
+/// One-time config setup; `admin` must be the program's upgrade authority.
+pub fn initialize_config(admin: &Pubkey, fee_bps: u16, treasury: Pubkey) -> Instruction {
+    ix(
+        accounts::InitializeConfig {
+            admin: *admin,
+            config: pda::config(),
+            program_data: pda::program_data(),
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::InitializeConfig { fee_bps, treasury },
+    )
+}
+
+pub fn set_protocol_fee(admin: &Pubkey, fee_bps: u16) -> Instruction {
+    ix(
+        accounts::UpdateConfig {
+            admin: *admin,
+            config: pda::config(),
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::SetProtocolFee { fee_bps },
+    )
+}
+
+pub fn set_paused(admin: &Pubkey, paused: bool) -> Instruction {
+    ix(
+        accounts::UpdateConfig {
+            admin: *admin,
+            config: pda::config(),
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::SetPaused { paused },
+    )
+}
+
+/// Both the current and the new admin sign.
+pub fn transfer_admin(admin: &Pubkey, new_admin: &Pubkey) -> Instruction {
+    ix(
+        accounts::TransferAdmin {
+            admin: *admin,
+            new_admin: *new_admin,
+            config: pda::config(),
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::TransferAdmin {},
+    )
+}
+
+/// Register a verifying key; its IC points follow in `append_verifying_key_ic`.
+#[allow(clippy::too_many_arguments)]
+pub fn init_verifying_key(
+    admin: &Pubkey,
+    circuit: ZkCircuit,
+    version: u16,
+    nr_pubinputs: u8,
+    alpha_g1: [u8; 64],
+    beta_g2: [u8; 128],
+    gamma_g2: [u8; 128],
+    delta_g2: [u8; 128],
+) -> Instruction {
+    ix(
+        accounts::InitVerifyingKey {
+            admin: *admin,
+            config: pda::config(),
+            verifying_key: pda::verifying_key(circuit, version),
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::InitVerifyingKey {
+            circuit,
+            version,
+            nr_pubinputs,
+            alpha_g1,
+            beta_g2,
+            gamma_g2,
+            delta_g2,
+        },
+    )
+}
+
+pub fn append_verifying_key_ic(
+    admin: &Pubkey,
+    circuit: ZkCircuit,
+    version: u16,
+    ic: Vec<[u8; 64]>,
+) -> Instruction {
+    ix(
+        accounts::UpdateVerifyingKey {
+            admin: *admin,
+            config: pda::config(),
+            verifying_key: pda::verifying_key(circuit, version),
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::AppendVerifyingKeyIc { ic },
+    )
+}
+
+pub fn revoke_verifying_key(admin: &Pubkey, circuit: ZkCircuit, version: u16) -> Instruction {
+    ix(
+        accounts::UpdateVerifyingKey {
+            admin: *admin,
+            config: pda::config(),
+            verifying_key: pda::verifying_key(circuit, version),
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::RevokeVerifyingKey {},
+    )
+}
+
+// ============================================================================
+// Services
+// ============================================================================
+
+/// Register a listing priced in lamports, or in base units of `mint` if given.
+#[allow(clippy::too_many_arguments)]
+pub fn register_service(
+    provider: &Pubkey,
+    service_id: [u8; 16],
+    description: [u8; 128],
+    price: u64,
+    min_reputation: u64,
+    arbiter: Pubkey,
+    review_window: i64,
+    cancellation_fee_bps: u16,
+    mint: Option<Pubkey>,
+) -> Instruction {
+    ix(
+        accounts::RegisterService {
+            provider: *provider,
+            service_listing: pda::service(provider, &service_id),
+            provider_profile: pda::provider_profile(provider),
+            config: pda::config(),
+            mint,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::RegisterService {
+            service_id,
+            description,
+            price_lamports: price,
+            min_reputation,
+            arbiter,
+            review_window,
+            cancellation_fee_bps,
+        },
+    )
+}
+
+/// Change any of a listing's terms; `None` leaves a field unchanged.
+pub fn update_service(
+    provider: &Pubkey,
+    service_listing: &Pubkey,
+    price: Option<u64>,
+    description: Option<[u8; 128]>,
+    min_reputation: Option<u64>,
+    cancellation_fee_bps: Option<u16>,
+) -> Instruction {
+    ix(
+        accounts::UpdateService {
+            provider: *provider,
+            service_listing: *service_listing,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::UpdateService {
+            price_lamports: price,
+            description,
+            min_reputation,
+            cancellation_fee_bps,
+        },
+    )
+}
+
+pub fn deactivate_service(provider: &Pubkey, service_listing: &Pubkey) -> Instruction {
+    ix(
+        accounts::DeactivateService {
+            provider: *provider,
+            service_listing: *service_listing,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::DeactivateService {},
+    )
+}
+
+pub fn reactivate_service(provider: &Pubkey, service_listing: &Pubkey) -> Instruction {
+    ix(
+        accounts::UpdateService {
+            provider: *provider,
+            service_listing: *service_listing,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::ReactivateService {},
+    )
+}
+
+// ============================================================================
+// Tasks
+// ============================================================================
+
+/// Create a task on `service_listing`, offered by `provider`, and lock its price in
+/// escrow. `token` is required when the listing is priced in a token.
+pub fn create_task(
+    requester: &Pubkey,
+    service_listing: &Pubkey,
+    provider: &Pubkey,
+    task_id: [u8; 16],
+    description: [u8; 256],
+    deadline: i64,
+    token: Option<&TokenAccounts>,
+) -> Instruction {
+    let task = pda::task(requester, &task_id);
+    let escrow = Escrow::of(&task, token);
+    ix(
+        accounts::CreateTask {
+            requester: *requester,
+            service_listing: *service_listing,
+            task_request: task,
+            provider_profile: pda::provider_profile(provider),
+            config: pda::config(),
+            mint: escrow.mint,
+            requester_token_account: token.map(|token| token.requester),
+            escrow_token_account: escrow.escrow_token_account,
+            token_program: escrow.token_program,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::CreateTask {
+            task_id,
+            description,
+            deadline,
+        },
+    )
+}
+
+/// Post an open bounty. Pass `token` (with `provider` unused) to escrow an SPL /
+/// Token-2022 amount instead of native SOL.
+#[allow(clippy::too_many_arguments)]
+pub fn create_bounty(
+    requester: &Pubkey,
+    task_id: [u8; 16],
+    description: [u8; 256],
+    amount: u64,
+    deadline: i64,
+    min_reputation: u64,
+    claim_timeout: i64,
+    arbiter: Pubkey,
+    review_window: i64,
+    token: Option<&TokenAccounts>,
+) -> Instruction {
+    let task = pda::task(requester, &task_id);
+    let escrow = Escrow::of(&task, token);
+    ix(
+        accounts::CreateBounty {
+            requester: *requester,
+            task_request: task,
+            config: pda::config(),
+            mint: escrow.mint,
+            requester_token_account: token.map(|token| token.requester),
+            escrow_token_account: escrow.escrow_token_account,
+            token_program: escrow.token_program,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::CreateBounty {
+            task_id,
+            description,
+            amount,
+            deadline,
+            min_reputation,
+            claim_timeout,
+            arbiter,
+            review_window,
+        },
+    )
+}
+
+pub fn claim_bounty(provider: &Pubkey, task: &Pubkey) -> Instruction {
+    ix(
+        accounts::ClaimBounty {
+            provider: *provider,
+            task_request: *task,
+            provider_profile: pda::provider_profile(provider),
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::ClaimBounty {},
+    )
+}
+
+pub fn cancel_bounty(
+    task: &Pubkey,
+    state: &TaskRequest,
+    token: Option<&TokenAccounts>,
+) -> Instruction {
+    let escrow = Escrow::of(task, token);
+    ix(
+        accounts::CancelBounty {
+            requester: state.requester,
+            task_request: *task,
+            mint: escrow.mint,
+            escrow_token_account: escrow.escrow_token_account,
+            requester_token_account: token.map(|token| token.requester),
+            token_program: escrow.token_program,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::CancelBounty {},
+    )
+}
+
+pub fn start_task(task: &Pubkey, state: &TaskRequest) -> Instruction {
+    ix(
+        accounts::StartTask {
+            provider: state.provider,
+            task_request: *task,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::StartTask {},
+    )
+}
+
+pub fn cancel_task(
+    task: &Pubkey,
+    state: &TaskRequest,
+    treasury: &Pubkey,
+    token: Option<&TokenAccounts>,
+) -> Instruction {
+    let escrow = Escrow::of(task, token);
+    ix(
+        accounts::CancelTask {
+            requester: state.requester,
+            task_request: *task,
+            provider: state.provider,
+            provider_profile: profile(state),
+            config: pda::config(),
+            treasury: *treasury,
+            mint: escrow.mint,
+            escrow_token_account: escrow.escrow_token_account,
+            requester_token_account: token.map(|token| token.requester),
+            provider_token_account: token.map(|token| token.provider),
+            treasury_token_account: token.map(|token| token.treasury),
+            token_program: escrow.token_program,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::CancelTask {},
+    )
+}
+
+pub fn submit_result(task: &Pubkey, state: &TaskRequest, result_hash: [u8; 32]) -> Instruction {
+    ix(
+        accounts::SubmitResult {
+            provider: state.provider,
+            task_request: *task,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::SubmitResult { result_hash },
+    )
+}
+
+/// Submit a result with a Groth16 proof against the `TaskVerify` key (`chunk_count`
+/// = 1) or the `TaskVerifyChunked` key (Merkle root over `chunk_count` chunks).
+#[allow(clippy::too_many_arguments)]
+pub fn submit_result_zk(
+    task: &Pubkey,
+    state: &TaskRequest,
+    circuit: ZkCircuit,
+    vk_version: u16,
+    proof: &Proof,
+    result_hash: [u8; 32],
+    chunk_count: u32,
+) -> Instruction {
+    ix(
+        accounts::SubmitResultZk {
+            provider: state.provider,
+            task_request: *task,
+            verifying_key: pda::verifying_key(circuit, vk_version),
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::SubmitResultZk {
+            proof_a: proof.a,
+            proof_b: proof.b,
+            proof_c: proof.c,
+            result_hash,
+            chunk_count,
+        },
+    )
+}
+
+pub fn accept_result(
+    task: &Pubkey,
+    state: &TaskRequest,
+    treasury: &Pubkey,
+    token: Option<&TokenAccounts>,
+) -> Instruction {
+    let escrow = Escrow::of(task, token);
+    ix(
+        accounts::AcceptResult {
+            requester: state.requester,
+            task_request: *task,
+            provider: state.provider,
+            service_listing: listing(state),
+            provider_profile: pda::provider_profile(&state.provider),
+            config: pda::config(),
+            treasury: *treasury,
+            mint: escrow.mint,
+            escrow_token_account: escrow.escrow_token_account,
+            provider_token_account: token.map(|token| token.provider),
+            treasury_token_account: token.map(|token| token.treasury),
+            token_program: escrow.token_program,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::AcceptResult {},
+    )
+}
+
+pub fn dispute_task(task: &Pubkey, state: &TaskRequest) -> Instruction {
+    ix(
+        accounts::DisputeTask {
+            requester: state.requester,
+            task_request: *task,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::DisputeTask {},
+    )
+}
+
+/// Permissionless: any fee payer can send it once the review window has passed.
+pub fn auto_accept(
+    task: &Pubkey,
+    state: &TaskRequest,
+    treasury: &Pubkey,
+    token: Option<&TokenAccounts>,
+) -> Instruction {
+    let escrow = Escrow::of(task, token);
+    ix(
+        accounts::AutoAccept {
+            task_request: *task,
+            provider: state.provider,
+            service_listing: listing(state),
+            provider_profile: pda::provider_profile(&state.provider),
+            config: pda::config(),
+            treasury: *treasury,
+            mint: escrow.mint,
+            escrow_token_account: escrow.escrow_token_account,
+            provider_token_account: token.map(|token| token.provider),
+            treasury_token_account: token.map(|token| token.treasury),
+            token_program: escrow.token_program,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::AutoAccept {},
+    )
+}
+
+/// Signed by the task's arbiter; `provider_bps` of the escrow goes to the provider.
+pub fn resolve_dispute(
+    task: &Pubkey,
+    state: &TaskRequest,
+    treasury: &Pubkey,
+    token: Option<&TokenAccounts>,
+    provider_bps: u16,
+) -> Instruction {
+    let escrow = Escrow::of(task, token);
+    ix(
+        accounts::ResolveDispute {
+            arbiter: state.arbiter,
+            task_request: *task,
+            requester: state.requester,
+            provider: state.provider,
+            provider_profile: pda::provider_profile(&state.provider),
+            config: pda::config(),
+            treasury: *treasury,
+            mint: escrow.mint,
+            escrow_token_account: escrow.escrow_token_account,
+            requester_token_account: token.map(|token| token.requester),
+            provider_token_account: token.map(|token| token.provider),
+            treasury_token_account: token.map(|token| token.treasury),
+            token_program: escrow.token_program,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::ResolveDispute { provider_bps },
+    )
+}
+
+/// Signed by `party`, the task's requester or provider, once the arbiter has let the
+/// arbitration window pass; refunds the requester.
+pub fn expire_dispute(
+    party: &Pubkey,
+    task: &Pubkey,
+    state: &TaskRequest,
+    token: Option<&TokenAccounts>,
+) -> Instruction {
+    let escrow = Escrow::of(task, token);
+    ix(
+        accounts::ExpireDispute {
+            party: *party,
+            task_request: *task,
+            requester: state.requester,
+            provider_profile: pda::provider_profile(&state.provider),
+            mint: escrow.mint,
+            escrow_token_account: escrow.escrow_token_account,
+            requester_token_account: token.map(|token| token.requester),
+            token_program: escrow.token_program,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::ExpireDispute {},
+    )
+}
+
+/// Permissionless: any fee payer can send it once the deadline has passed.
+pub fn expire_task(
+    task: &Pubkey,
+    state: &TaskRequest,
+    token: Option<&TokenAccounts>,
+) -> Instruction {
+    let escrow = Escrow::of(task, token);
+    ix(
+        accounts::ExpireTask {
+            requester: state.requester,
+            task_request: *task,
+            provider_profile: profile(state),
+            mint: escrow.mint,
+            escrow_token_account: escrow.escrow_token_account,
+            requester_token_account: token.map(|token| token.requester),
+            token_program: escrow.token_program,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::ExpireTask {},
+    )
+}
+
+pub fn close_task(
+    task: &Pubkey,
+    state: &TaskRequest,
+    token: Option<&TokenAccounts>,
+) -> Instruction {
+    let escrow = Escrow::of(task, token);
+    ix(
+        accounts::CloseTask {
+            requester: state.requester,
+            task_request: *task,
+            review: state.review_open.then(|| pda::review(task)),
+            mint: escrow.mint,
+            escrow_token_account: escrow.escrow_token_account,
+            token_program: escrow.token_program,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::CloseTask {},
+    )
+}
+
+pub fn submit_review(
+    task: &Pubkey,
+    state: &TaskRequest,
+    score: u8,
+    review_hash: [u8; 32],
+) -> Instruction {
+    ix(
+        accounts::SubmitReview {
+            requester: state.requester,
+            task_request: *task,
+            provider_profile: pda::provider_profile(&state.provider),
+            review: pda::review(task),
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::SubmitReview { score, review_hash },
+    )
+}
+
+// ============================================================================
+// Milestones
+// ============================================================================
+
+pub fn add_milestone(
+    task: &Pubkey,
+    state: &TaskRequest,
+    index: u8,
+    amount: u64,
+    description: [u8; 128],
+) -> Instruction {
+    ix(
+        accounts::AddMilestone {
+            requester: state.requester,
+            task_request: *task,
+            milestone: pda::milestone(task, index),
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::AddMilestone {
+            index,
+            amount,
+            description,
+        },
+    )
+}
+
+pub fn submit_milestone(
+    task: &Pubkey,
+    state: &TaskRequest,
+    index: u8,
+    result_hash: [u8; 32],
+) -> Instruction {
+    ix(
+        accounts::SubmitMilestone {
+            provider: state.provider,
+            task_request: *task,
+            milestone: pda::milestone(task, index),
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::SubmitMilestone { result_hash },
+    )
+}
+
+pub fn accept_milestone(
+    task: &Pubkey,
+    state: &TaskRequest,
+    index: u8,
+    treasury: &Pubkey,
+    token: Option<&TokenAccounts>,
+) -> Instruction {
+    let escrow = Escrow::of(task, token);
+    ix(
+        accounts::AcceptMilestone {
+            requester: state.requester,
+            task_request: *task,
+            milestone: pda::milestone(task, index),
+            provider: state.provider,
+            service_listing: listing(state),
+            provider_profile: pda::provider_profile(&state.provider),
+            config: pda::config(),
+            treasury: *treasury,
+            mint: escrow.mint,
+            escrow_token_account: escrow.escrow_token_account,
+            provider_token_account: token.map(|token| token.provider),
+            treasury_token_account: token.map(|token| token.treasury),
+            token_program: escrow.token_program,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::AcceptMilestone {},
+    )
+}
+
+/// Permissionless: any fee payer can send it once the milestone's review window
+/// has passed.
+pub fn auto_accept_milestone(
+    task: &Pubkey,
+    state: &TaskRequest,
+    index: u8,
+    treasury: &Pubkey,
+    token: Option<&TokenAccounts>,
+) -> Instruction {
+    let escrow = Escrow::of(task, token);
+    ix(
+        accounts::AutoAcceptMilestone {
+            task_request: *task,
+            milestone: pda::milestone(task, index),
+            provider: state.provider,
+            service_listing: listing(state),
+            provider_profile: pda::provider_profile(&state.provider),
+            config: pda::config(),
+            treasury: *treasury,
+            mint: escrow.mint,
+            escrow_token_account: escrow.escrow_token_account,
+            provider_token_account: token.map(|token| token.provider),
+            treasury_token_account: token.map(|token| token.treasury),
+            token_program: escrow.token_program,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::AutoAcceptMilestone {},
+    )
+}
+
+pub fn dispute_milestone(task: &Pubkey, state: &TaskRequest, index: u8) -> Instruction {
+    ix(
+        accounts::DisputeMilestone {
+            requester: state.requester,
+            task_request: *task,
+            milestone: pda::milestone(task, index),
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::DisputeMilestone {},
+    )
+}
+
+/// Signed by the task's arbiter; `provider_bps` of the milestone goes to the provider.
+pub fn resolve_milestone_dispute(
+    task: &Pubkey,
+    state: &TaskRequest,
+    index: u8,
+    treasury: &Pubkey,
+    token: Option<&TokenAccounts>,
+    provider_bps: u16,
+) -> Instruction {
+    let escrow = Escrow::of(task, token);
+    ix(
+        accounts::ResolveMilestoneDispute {
+            arbiter: state.arbiter,
+            task_request: *task,
+            milestone: pda::milestone(task, index),
+            requester: state.requester,
+            provider: state.provider,
+            provider_profile: pda::provider_profile(&state.provider),
+            config: pda::config(),
+            treasury: *treasury,
+            mint: escrow.mint,
+            escrow_token_account: escrow.escrow_token_account,
+            requester_token_account: token.map(|token| token.requester),
+            provider_token_account: token.map(|token| token.provider),
+            treasury_token_account: token.map(|token| token.treasury),
+            token_program: escrow.token_program,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::ResolveMilestoneDispute { provider_bps },
+    )
+}
+
+pub fn close_milestone(task: &Pubkey, state: &TaskRequest, index: u8) -> Instruction {
+    ix(
+        accounts::CloseMilestone {
+            requester: state.requester,
+            task_request: *task,
+            milestone: pda::milestone(task, index),
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::CloseMilestone {},
+    )
+}
+
+// ============================================================================
+// Encrypted delivery
+// ============================================================================
+
+/// Ask for the result encrypted to `encryption_key`; a non-zero `expected_hash`
+/// pays the provider as soon as a matching result is proven.
+pub fn request_encrypted_delivery(
+    task: &Pubkey,
+    state: &TaskRequest,
+    encryption_key: [u8; 64],
+    expected_hash: [u8; 32],
+) -> Instruction {
+    ix(
+        accounts::RequestEncryptedDelivery {
+            requester: state.requester,
+            task_request: *task,
+            delivery: pda::delivery(task),
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::RequestEncryptedDelivery {
+            encryption_key,
+            expected_hash,
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn submit_result_encrypted(
+    task: &Pubkey,
+    state: &TaskRequest,
+    vk_version: u16,
+    treasury: &Pubkey,
+    token: Option<&TokenAccounts>,
+    proof: &Proof,
+    result_hash: [u8; 32],
+    ephemeral_key: [u8; 64],
+    ciphertext: [u8; 32],
+) -> Instruction {
+    let escrow = Escrow::of(task, token);
+    ix(
+        accounts::SubmitResultEncrypted {
+            provider: state.provider,
+            task_request: *task,
+            delivery: pda::delivery(task),
+            verifying_key: pda::verifying_key(ZkCircuit::ResultDelivery, vk_version),
+            service_listing: listing(state),
+            provider_profile: pda::provider_profile(&state.provider),
+            config: pda::config(),
+            treasury: *treasury,
+            mint: escrow.mint,
+            escrow_token_account: escrow.escrow_token_account,
+            provider_token_account: token.map(|token| token.provider),
+            treasury_token_account: token.map(|token| token.treasury),
+            token_program: escrow.token_program,
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::SubmitResultEncrypted {
+            proof_a: proof.a,
+            proof_b: proof.b,
+            proof_c: proof.c,
+            result_hash,
+            ephemeral_key,
+            ciphertext,
+        },
+    )
+}
+
+pub fn close_delivery(task: &Pubkey, state: &TaskRequest) -> Instruction {
+    ix(
+        accounts::CloseDelivery {
+            requester: state.requester,
+            task_request: *task,
+            delivery: pda::delivery(task),
+            event_authority: pda::event_authority(),
+            program: ID,
+        },
+        instruction::CloseDelivery {},
+    )
+}
@@ -0,0 +1,44 @@
+//! Client SDK for the AgentPay program: PDA derivation, typed instruction builders
+//! for every handler, and decoders for the program's accounts.
+//!
+//! Everything here is pure; nothing talks to an RPC node. Fetch account data with
+//! whatever client you already use, decode it with [`accounts`], and sign and send
+//! the [`Instruction`]s built by [`instructions`].
+//!
+//! ```no_run
+//! use agentpay_client::{accounts, instructions, pda, text};
+//! # use agentpay_client::Pubkey;
+//! # let (requester, listing, provider) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+//! # let task_data = Vec::<u8>::new();
+//! let task_id = [7u8; 16];
+//! let create = instructions::create_task(
+//!     &requester,
+//!     &listing,
+//!     &provider,
+//!     task_id,
+//!     text("Summarize the attached paper").unwrap(),
+//!     1_700_000_000,
+//!     None,
+//! );
+//!
+//! let task = pda::task(&requester, &task_id);
+//! let state = accounts::task_request(&task_data).unwrap();
+//! ```
+
+pub mod accounts;
+pub mod instructions;
+pub mod pda;
+
+pub use agentpay::{errors, events, state, ID};
+pub use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+pub use anchor_lang::solana_program::pubkey::Pubkey;
+pub use instructions::{Proof, TokenAccounts};
+
+/// Zero-padded fixed-size field (descriptions, service and task ids) holding `text`,
+/// or `None` if it does not fit.
+pub fn text<const N: usize>(text: &str) -> Option<[u8; N]> {
+    let bytes = text.as_bytes();
+    let mut out = [0u8; N];
+    out.get_mut(..bytes.len())?.copy_from_slice(bytes);
+    Some(out)
+}
@@ -0,0 +1,62 @@
+//! Addresses of the program's PDAs, derived from the same seeds the program checks.
+
+use agentpay::state::ZkCircuit;
+use agentpay::ID;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::pubkey::Pubkey;
+
+/// Protocol config singleton: `["config"]`
+pub fn config() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &ID).0
+}
+
+/// Signer of the self-CPI that carries the program's events: `["__event_authority"]`
+pub fn event_authority() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &ID).0
+}
+
+/// The program's upgradeable-loader data account, whose upgrade authority may
+/// initialize the config.
+pub fn program_data() -> Pubkey {
+    Pubkey::find_program_address(&[ID.as_ref()], &bpf_loader_upgradeable::id()).0
+}
+
+/// Service listing: `["service", provider, service_id]`
+pub fn service(provider: &Pubkey, service_id: &[u8; 16]) -> Pubkey {
+    Pubkey::find_program_address(&[b"service", provider.as_ref(), service_id], &ID).0
+}
+
+/// Reputation of a provider across all of their listings: `["provider", provider]`
+pub fn provider_profile(provider: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"provider", provider.as_ref()], &ID).0
+}
+
+/// Task request, which also holds native SOL escrow: `["task", requester, task_id]`
+pub fn task(requester: &Pubkey, task_id: &[u8; 16]) -> Pubkey {
+    Pubkey::find_program_address(&[b"task", requester.as_ref(), task_id], &ID).0
+}
+
+/// Milestone of a task: `["milestone", task, index]`
+pub fn milestone(task: &Pubkey, index: u8) -> Pubkey {
+    Pubkey::find_program_address(&[b"milestone", task.as_ref(), &[index]], &ID).0
+}
+
+/// Token escrow account of an SPL / Token-2022 task: `["escrow", task]`
+pub fn escrow(task: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"escrow", task.as_ref()], &ID).0
+}
+
+/// Encrypted delivery of a task: `["delivery", task]`
+pub fn delivery(task: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"delivery", task.as_ref()], &ID).0
+}
+
+/// Requester's review of a settled task: `["review", task]`
+pub fn review(task: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"review", task.as_ref()], &ID).0
+}
+
+/// Groth16 verifying key: `["vk", circuit, version (LE)]`
+pub fn verifying_key(circuit: ZkCircuit, version: u16) -> Pubkey {
+    Pubkey::find_program_address(&[b"vk", &[circuit as u8], &version.to_le_bytes()], &ID).0
+}
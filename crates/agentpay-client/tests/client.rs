@@ -0,0 +1,204 @@
+//! Builders and decoders checked offline against the program crate's own types.
+
+use agentpay::state::{ServiceListing, TaskRequest, TaskStatus};
+use agentpay_client::{accounts, instructions, pda, text, Pubkey, TokenAccounts, ID};
+use anchor_lang::{AccountSerialize, AnchorDeserialize, Discriminator};
+
+/// A decoded task with every field zeroed, then the parties filled in.
+fn task_state() -> TaskRequest {
+    let mut data = TaskRequest::DISCRIMINATOR.to_vec();
+    data.resize(TaskRequest::SIZE, 0);
+    let mut state = accounts::task_request(&data).unwrap();
+    state.requester = Pubkey::new_unique();
+    state.provider = Pubkey::new_unique();
+    state.arbiter = Pubkey::new_unique();
+    state.service_listing = Pubkey::new_unique();
+    state
+}
+
+fn token_accounts() -> TokenAccounts {
+    TokenAccounts {
+        mint: Pubkey::new_unique(),
+        token_program: Pubkey::new_unique(),
+        requester: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        treasury: Pubkey::new_unique(),
+    }
+}
+
+#[test]
+fn decoders_round_trip_and_check_the_discriminator() {
+    let mut state = task_state();
+    state.status = TaskStatus::Submitted;
+    state.amount_lamports = 42;
+
+    let mut data = Vec::new();
+    state.try_serialize(&mut data).unwrap();
+    let decoded = accounts::task_request(&data).unwrap();
+    assert_eq!(decoded.requester, state.requester);
+    assert_eq!(decoded.amount_lamports, 42);
+    assert!(decoded.status == TaskStatus::Submitted);
+
+    assert!(accounts::service_listing(&data).is_err());
+    assert!(accounts::decode::<ServiceListing>(&data[..8]).is_err());
+}
+
+#[test]
+fn create_task_derives_the_task_and_escrow_addresses() {
+    let requester = Pubkey::new_unique();
+    let listing = Pubkey::new_unique();
+    let provider = Pubkey::new_unique();
+    let task_id = [3u8; 16];
+    let task = pda::task(&requester, &task_id);
+    let description = text("Translate the attached contract").unwrap();
+
+    let native = instructions::create_task(
+        &requester,
+        &listing,
+        &provider,
+        task_id,
+        description,
+        1_700_000_000,
+        None,
+    );
+    assert_eq!(native.program_id, ID);
+    assert!(native.accounts[0].pubkey == requester && native.accounts[0].is_signer);
+    assert_eq!(native.accounts[2].pubkey, task);
+    assert_eq!(native.accounts[3].pubkey, pda::provider_profile(&provider));
+    assert!(!native
+        .accounts
+        .iter()
+        .any(|meta| meta.pubkey == pda::escrow(&task)));
+
+    let (discriminator, args) = native
+        .data
+        .split_at(agentpay::instruction::CreateTask::DISCRIMINATOR.len());
+    assert_eq!(
+        discriminator,
+        agentpay::instruction::CreateTask::DISCRIMINATOR
+    );
+    let args = agentpay::instruction::CreateTask::try_from_slice(args).unwrap();
+    assert_eq!(args.task_id, task_id);
+    assert_eq!(args.deadline, 1_700_000_000);
+
+    let token = token_accounts();
+    let tokens = instructions::create_task(
+        &requester,
+        &listing,
+        &provider,
+        task_id,
+        description,
+        1_700_000_000,
+        Some(&token),
+    );
+    assert_eq!(tokens.accounts.len(), native.accounts.len());
+    let escrow = tokens
+        .accounts
+        .iter()
+        .find(|meta| meta.pubkey == pda::escrow(&task))
+        .unwrap();
+    assert!(escrow.is_writable);
+    assert!(tokens
+        .accounts
+        .iter()
+        .any(|meta| meta.pubkey == token.requester && meta.is_writable));
+}
+
+#[test]
+fn settlement_signers_come_from_the_task() {
+    let task = Pubkey::new_unique();
+    let state = task_state();
+    let treasury = Pubkey::new_unique();
+    let signers = |ix: &agentpay_client::Instruction| -> Vec<Pubkey> {
+        ix.accounts
+            .iter()
+            .filter(|meta| meta.is_signer)
+            .map(|meta| meta.pubkey)
+            .collect()
+    };
+
+    let accept = instructions::accept_result(&task, &state, &treasury, None);
+    assert_eq!(signers(&accept), [state.requester]);
+    let resolve = instructions::resolve_dispute(&task, &state, &treasury, None, 5_000);
+    assert_eq!(signers(&resolve), [state.arbiter]);
+    let expire_dispute = instructions::expire_dispute(&state.provider, &task, &state, None);
+    assert_eq!(signers(&expire_dispute), [state.provider]);
+    let submit = instructions::submit_result(&task, &state, [1u8; 32]);
+    assert_eq!(signers(&submit), [state.provider]);
+    let review = instructions::submit_review(&task, &state, 5, [2u8; 32]);
+    assert_eq!(signers(&review), [state.requester]);
+    assert_eq!(review.accounts[3].pubkey, pda::review(&task));
+
+    // Cranks are permissionless; only the fee payer signs
+    let crank = instructions::auto_accept(&task, &state, &treasury, None);
+    assert!(signers(&crank).is_empty());
+    let expire = instructions::expire_task(&task, &state, None);
+    assert!(signers(&expire).is_empty());
+}
+
+#[test]
+fn native_settlements_leave_the_token_accounts_empty() {
+    let task = Pubkey::new_unique();
+    let state = task_state();
+    let treasury = Pubkey::new_unique();
+    let token = token_accounts();
+
+    // Anchor encodes an omitted optional account as the program id
+    let native = instructions::resolve_dispute(&task, &state, &treasury, None, 5_000);
+    let omitted = native
+        .accounts
+        .iter()
+        .filter(|meta| meta.pubkey == ID)
+        .count();
+    assert_eq!(omitted, 7); // six token accounts and the program itself
+
+    let tokens = instructions::resolve_dispute(&task, &state, &treasury, Some(&token), 5_000);
+    for account in [
+        token.mint,
+        token.token_program,
+        token.requester,
+        token.provider,
+        token.treasury,
+        pda::escrow(&task),
+    ] {
+        assert!(tokens.accounts.iter().any(|meta| meta.pubkey == account));
+    }
+}
+
+#[test]
+fn close_task_closes_the_review_once_there_is_one() {
+    let task = Pubkey::new_unique();
+    let mut state = task_state();
+    let review = |ix: &agentpay_client::Instruction| {
+        ix.accounts
+            .iter()
+            .find(|meta| meta.pubkey == pda::review(&task))
+            .map(|meta| meta.is_writable)
+    };
+
+    assert_eq!(review(&instructions::close_task(&task, &state, None)), None);
+    state.review_open = true;
+    assert_eq!(
+        review(&instructions::close_task(&task, &state, None)),
+        Some(true)
+    );
+}
+
+#[test]
+fn events_are_signed_by_the_event_authority() {
+    let state = task_state();
+    let ix = instructions::start_task(&Pubkey::new_unique(), &state);
+    let [.., authority, program] = ix.accounts.as_slice() else {
+        panic!("missing event accounts");
+    };
+    assert_eq!(authority.pubkey, pda::event_authority());
+    assert_eq!(program.pubkey, ID);
+}
+
+#[test]
+fn text_pads_and_rejects_overflow() {
+    let id: [u8; 16] = text("svc").unwrap();
+    assert_eq!(&id[..3], b"svc");
+    assert!(id[3..].iter().all(|&byte| byte == 0));
+    assert!(text::<4>("too long").is_none());
+}
@@ -254,6 +254,13 @@ export function validateToolParams(toolName, params) {
     case "submit_result_zk":
       validated.taskPda = validatePda(params.taskPda);
       validated.result = sanitizeInput(params.result, "result");
+      if (params.vkVersion !== undefined) {
+        validated.vkVersion = validateNumber(params.vkVersion, {
+          min: 1,
+          max: 65535,
+          name: "vkVersion",
+        });
+      }
       break;
 
     case "accept_result":
@@ -23,8 +23,8 @@ export const PROGRAM_ID = new PublicKey("2rfRD9jhyK4nwiWiDuixARYsmU3Euw2QMPjmSLH
 export const DEFAULT_RPC = process.env.AGENTPAY_RPC || "https://api.devnet.solana.com";
 
 // Account sizes for filtering (skip legacy accounts)
-export const SERVICE_LISTING_SIZE = 218;
-export const TASK_REQUEST_SIZE = 435;
+export const SERVICE_LISTING_SIZE = 292;
+export const TASK_REQUEST_SIZE = 579;
 
 // ============================================================================
 // IDL Loading
@@ -106,6 +106,30 @@ export function deriveTaskPda(requester, taskId) {
   return pda;
 }
 
+export function deriveConfigPda() {
+  const [pda] = PublicKey.findProgramAddressSync([Buffer.from("config")], PROGRAM_ID);
+  return pda;
+}
+
+export function deriveProfilePda(provider) {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("provider"), provider.toBuffer()],
+    PROGRAM_ID,
+  );
+  return pda;
+}
+
+// circuit is the ZkCircuit discriminant (0 = task_verify_v2)
+export function deriveVerifyingKeyPda(circuit, version) {
+  const versionBytes = Buffer.alloc(2);
+  versionBytes.writeUInt16LE(version);
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("vk"), Buffer.from([circuit]), versionBytes],
+    PROGRAM_ID,
+  );
+  return pda;
+}
+
 // ============================================================================
 // Utility Functions
 // ============================================================================
@@ -6,18 +6,23 @@
 
 import { createHash } from "node:crypto";
 import { existsSync } from "node:fs";
+import { createRequire } from "node:module";
 import { dirname, join } from "node:path";
 import { fileURLToPath } from "node:url";
 import { PublicKey } from "@solana/web3.js";
-import { getProgram } from "./program.mjs";
+import { deriveVerifyingKeyPda, getProgram } from "./program.mjs";
 
 const __filename = fileURLToPath(import.meta.url);
 const __dirname = dirname(__filename);
 
-// ZK circuit paths
+// ZK circuit paths, built by circuits/build.sh
 const CIRCUITS_DIR = join(__dirname, "../../circuits");
-const WASM_PATH = join(CIRCUITS_DIR, "task_verify/task_verify_js/task_verify.wasm");
-const ZKEY_PATH = join(CIRCUITS_DIR, "task_verify/task_verify_0001.zkey");
+const WASM_PATH = join(CIRCUITS_DIR, "task_verify_v2_js/task_verify_v2.wasm");
+const ZKEY_PATH = join(CIRCUITS_DIR, "task_verify_v2.zkey");
+
+// circomlibjs comes from the circuits package, which build.sh installs alongside
+// the artifacts above
+const requireFromCircuits = createRequire(join(CIRCUITS_DIR, "package.json"));
 
 /**
  * Convert BigInt to 32-byte big-endian buffer
@@ -55,6 +60,18 @@ function formatProofForSolana(proof) {
   return { a, b, c };
 }
 
+/**
+ * Split a public key into its big-endian high and low 128 bits, the field
+ * elements the circuits bind a task and provider to
+ */
+function keyToFieldElements(key) {
+  const bytes = key.toBuffer();
+  return [
+    BigInt(`0x${bytes.subarray(0, 16).toString("hex")}`),
+    BigInt(`0x${bytes.subarray(16).toString("hex")}`),
+  ];
+}
+
 /**
  * Submit a result for a task (without ZK proof)
  * @param {Object} params
@@ -147,9 +164,10 @@ export async function submitResult({ taskPda, result }) {
  * @param {Object} params
  * @param {string} params.taskPda - The task PDA
  * @param {string} params.result - The result text
+ * @param {number} [params.vkVersion] - Version of the registered task_verify_v2 key
  * @returns {Object} - Submission result
  */
-export async function submitResultZk({ taskPda, result }) {
+export async function submitResultZk({ taskPda, result, vkVersion = 1 }) {
   const { program, keypair, connection } = getProgram();
 
   // Check if ZK circuit files exist
@@ -207,15 +225,29 @@ export async function submitResultZk({ taskPda, result }) {
   try {
     // Dynamic import for snarkjs (it's a CommonJS module)
     const snarkjs = await import("snarkjs");
+    const { buildPoseidon } = requireFromCircuits("circomlibjs");
 
     // Compute Poseidon hash of result for the circuit
     // For simplicity, we use the first 31 bytes of SHA256 as the preimage
     const sha256Hash = createHash("sha256").update(result).digest();
     const preimage = BigInt(`0x${sha256Hash.subarray(0, 31).toString("hex")}`);
+    const poseidon = await buildPoseidon();
+    const expectedHash = poseidon.F.toString(poseidon([preimage]));
+
+    // The proof is bound to this task and provider, so it cannot be replayed elsewhere
+    const [taskHi, taskLo] = keyToFieldElements(taskPubkey);
+    const [providerHi, providerLo] = keyToFieldElements(keypair.publicKey);
 
     // Generate ZK proof
     const { proof, publicSignals } = await snarkjs.groth16.fullProve(
-      { preimage },
+      {
+        result: preimage.toString(),
+        expectedHash,
+        taskHi: taskHi.toString(),
+        taskLo: taskLo.toString(),
+        providerHi: providerHi.toString(),
+        providerLo: providerLo.toString(),
+      },
       WASM_PATH,
       ZKEY_PATH,
     );
@@ -223,16 +255,18 @@ export async function submitResultZk({ taskPda, result }) {
     // Format proof for Solana
     const { a, b, c } = formatProofForSolana(proof);
 
-    // The public signal is the Poseidon hash (result hash for on-chain)
+    // The first public signal is the Poseidon hash (result hash for on-chain); the
+    // program rebuilds the task and provider inputs itself
     const resultHash = Buffer.alloc(32);
     const hashBigInt = BigInt(publicSignals[0]);
     bigIntToBytes32BE(hashBigInt).copy(resultHash);
 
     const tx = await program.methods
-      .submitResultZk(Array.from(a), Array.from(b), Array.from(c), Array.from(resultHash))
+      .submitResultZk(Array.from(a), Array.from(b), Array.from(c), Array.from(resultHash), 1)
       .accounts({
         provider: keypair.publicKey,
         taskRequest: taskPubkey,
+        verifyingKey: deriveVerifyingKeyPda(0, vkVersion),
       })
       .signers([keypair])
       .rpc();
@@ -295,6 +329,10 @@ export const providerTools = [
           type: "string",
           description: "The result text or data (will be ZK-verified on-chain)",
         },
+        vkVersion: {
+          type: "number",
+          description: "Version of the registered task_verify_v2 verifying key (default 1)",
+        },
       },
       required: ["taskPda", "result"],
     },
@@ -5,7 +5,7 @@
  */
 
 import { PublicKey } from "@solana/web3.js";
-import { getProgram, lamportsToSol } from "./program.mjs";
+import { deriveConfigPda, deriveProfilePda, getProgram, lamportsToSol } from "./program.mjs";
 
 /**
  * Accept a submitted result and release payment to provider
@@ -51,13 +51,24 @@ export async function acceptResult({ taskPda }) {
   }
 
   try {
+    const configPda = deriveConfigPda();
+    const config = await program.account.config.fetch(configPda);
     const tx = await program.methods
       .acceptResult()
       .accounts({
         requester: keypair.publicKey,
         taskRequest: taskPubkey,
         provider: task.provider,
-        serviceListing: task.serviceListing,
+        // Bounties have no listing
+        serviceListing: task.serviceListing.equals(PublicKey.default) ? null : task.serviceListing,
+        providerProfile: deriveProfilePda(task.provider),
+        config: configPda,
+        treasury: config.treasury,
+        mint: null,
+        escrowTokenAccount: null,
+        providerTokenAccount: null,
+        treasuryTokenAccount: null,
+        tokenProgram: null,
       })
       .signers([keypair])
       .rpc();
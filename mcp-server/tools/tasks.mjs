@@ -12,6 +12,8 @@ import { PublicKey, SystemProgram } from "@solana/web3.js";
 const { BN } = anchor;
 
 import {
+  deriveConfigPda,
+  deriveProfilePda,
   getConnection,
   getProgram,
   lamportsToSol,
@@ -73,6 +75,12 @@ export async function createTask({ servicePda, description, deadlineMinutes = 60
         requester: keypair.publicKey,
         serviceListing: servicePubkey,
         taskRequest: taskPda,
+        providerProfile: deriveProfilePda(service.provider),
+        config: deriveConfigPda(),
+        mint: null,
+        requesterTokenAccount: null,
+        escrowTokenAccount: null,
+        tokenProgram: null,
         systemProgram: SystemProgram.programId,
       })
       .signers([keypair])
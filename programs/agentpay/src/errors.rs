@@ -26,4 +26,76 @@ pub enum AgentPayError {
     ZkProofVerificationFailed,
     #[msg("Provider reputation is too low for this service")]
     ReputationTooLow,
+    #[msg("Token mint does not match the service listing")]
+    MintMismatch,
+    #[msg("Token escrow accounts are required for token-denominated tasks")]
+    TokenAccountsRequired,
+    #[msg("Arbiter must be set and cannot be the service provider")]
+    InvalidArbiter,
+    #[msg("Only the task arbiter can perform this action")]
+    UnauthorizedArbiter,
+    #[msg("Basis points must be between 0 and 10000")]
+    InvalidBasisPoints,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Review window must be positive and at most 30 days")]
+    InvalidReviewWindow,
+    #[msg("Review window has not ended yet")]
+    ReviewWindowActive,
+    #[msg("Review window has ended")]
+    ReviewWindowClosed,
+    #[msg("Milestone is not in the expected status")]
+    InvalidMilestoneStatus,
+    #[msg("Milestones must be added in order")]
+    InvalidMilestoneIndex,
+    #[msg("Milestone amount must be positive and fit within the task escrow")]
+    InvalidMilestoneAmount,
+    #[msg("Milestones must cover the full escrow before work starts")]
+    MilestonesNotFunded,
+    #[msg("Task is split into milestones; use the milestone instructions")]
+    TaskHasMilestones,
+    #[msg("A milestone is still awaiting review")]
+    MilestonesInReview,
+    #[msg("Only the protocol admin can perform this action")]
+    UnauthorizedAdmin,
+    #[msg("Protocol fee exceeds the maximum")]
+    ProtocolFeeTooHigh,
+    #[msg("Treasury does not match the protocol config")]
+    InvalidTreasury,
+    #[msg("Program is paused")]
+    ProgramPaused,
+    #[msg("Verifying key is incomplete or revoked")]
+    ZkVerifyingKeyMissing,
+    #[msg("Verifying key does not match the circuit")]
+    InvalidVerifyingKey,
+    #[msg("Result hash does not match the hash the requester is paying for")]
+    DeliveryHashMismatch,
+    #[msg("Result chunk count is out of range for the circuit")]
+    InvalidChunkCount,
+    #[msg("Task account holds less than its rent and remaining escrow")]
+    EscrowUnderfunded,
+    #[msg("Payout exceeds the escrow remaining on the task")]
+    EscrowOverdrawn,
+    #[msg("Review score must be between 1 and 5")]
+    InvalidReviewScore,
+    #[msg("Service listing must be the task's own, and omitted for bounties")]
+    ServiceListingMismatch,
+    #[msg("Task is not a bounty")]
+    NotABounty,
+    #[msg("Bounty is claimed and the claim has not timed out")]
+    BountyAlreadyClaimed,
+    #[msg("Claim timeout must be positive and at most 30 days")]
+    InvalidClaimTimeout,
+    #[msg("Close the task's milestones before closing the task")]
+    MilestonesNotClosed,
+    #[msg("Close the task's encrypted delivery before closing the task")]
+    DeliveryNotClosed,
+    #[msg("Provider profile is required once the task has a provider")]
+    ProviderProfileRequired,
+    #[msg("Arbiter can still rule on the dispute")]
+    ArbitrationWindowActive,
+    #[msg("Only the task's requester or provider can expire its dispute")]
+    UnauthorizedDisputeParty,
+    #[msg("Pass the task's review so it is closed with the task")]
+    ReviewRequired,
 }
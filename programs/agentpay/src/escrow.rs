@@ -0,0 +1,323 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    transfer_fee::TransferFeeAmount, BaseStateWithExtensions, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::Account as SplTokenAccount;
+use anchor_spl::token_interface::{
+    self, CloseAccount, HarvestWithheldTokensToMint, Mint, TokenAccount, TokenInterface,
+    TransferChecked,
+};
+
+use crate::errors::AgentPayError;
+use crate::state::{TaskRequest, BASIS_POINTS};
+
+// Native SOL escrow sits on the task PDA next to the account's rent, and anyone can
+// transfer more lamports to a PDA. Payouts are checked against the escrow still owed,
+// never the raw balance, so rent is never paid out and stray lamports stay on the PDA
+// until `close_task` returns them to the requester along with the rent.
+
+// ============================================================================
+// Escrow accounting
+// ============================================================================
+
+/// Lamports held by a native SOL task PDA, split by purpose.
+pub struct NativeBalance {
+    /// Minimum balance keeping the account rent-exempt
+    pub rent: u64,
+    /// Escrow still owed to the requester or provider
+    pub escrow: u64,
+    /// Anything else sent to the PDA
+    pub surplus: u64,
+}
+
+impl NativeBalance {
+    pub fn of(task: &Account<'_, TaskRequest>) -> Result<Self> {
+        let info = task.to_account_info();
+        let rent = Rent::get()?.minimum_balance(info.data_len());
+        let escrow = task.escrow_remaining()?;
+        let surplus = info
+            .lamports()
+            .checked_sub(rent)
+            .and_then(|held| held.checked_sub(escrow))
+            .ok_or(AgentPayError::EscrowUnderfunded)?;
+        Ok(Self {
+            rent,
+            escrow,
+            surplus,
+        })
+    }
+}
+
+/// Token accounts needed to move SPL / Token-2022 escrow out of a task.
+pub struct TokenEscrow<'a, 'info> {
+    pub mint: &'a InterfaceAccount<'info, Mint>,
+    pub escrow: &'a InterfaceAccount<'info, TokenAccount>,
+    pub token_program: &'a Interface<'info, TokenInterface>,
+}
+
+/// Escrow paid out by one settlement instruction, reported in its settlement event.
+#[derive(Clone, Copy, Default)]
+pub struct Payouts {
+    /// Paid to the provider, net of the protocol fee
+    pub provider: u64,
+    /// Refunded to the requester
+    pub requester: u64,
+    /// Withheld for the treasury
+    pub protocol_fee: u64,
+}
+
+/// The escrow of one task, resolved once per settlement instruction.
+/// Every release is booked against `remaining`, so an instruction can never pay
+/// out more than the task still owes, whatever the account balances say.
+pub struct Escrow<'a, 'info> {
+    task: &'a Account<'info, TaskRequest>,
+    token: Option<TokenEscrow<'a, 'info>>,
+    /// Escrow not yet released by this instruction (lamports or token base units)
+    remaining: u64,
+    /// Rent the task PDA must keep; unused for token escrow
+    rent: u64,
+    payouts: Payouts,
+}
+
+impl<'a, 'info> Escrow<'a, 'info> {
+    /// Resolve the optional token accounts of a settlement instruction and check
+    /// the escrow is fully funded. Token accounts are ignored for native SOL tasks.
+    pub fn resolve(
+        task: &'a Account<'info, TaskRequest>,
+        mint: &'a Option<InterfaceAccount<'info, Mint>>,
+        escrow: &'a Option<InterfaceAccount<'info, TokenAccount>>,
+        token_program: &'a Option<Interface<'info, TokenInterface>>,
+    ) -> Result<Self> {
+        if task.is_native() {
+            let balance = NativeBalance::of(task)?;
+            return Ok(Self {
+                task,
+                token: None,
+                remaining: balance.escrow,
+                rent: balance.rent,
+                payouts: Payouts::default(),
+            });
+        }
+
+        let (Some(mint), Some(escrow), Some(token_program)) = (mint, escrow, token_program) else {
+            return err!(AgentPayError::TokenAccountsRequired);
+        };
+        let remaining = task.escrow_remaining()?;
+        require!(escrow.amount >= remaining, AgentPayError::EscrowUnderfunded);
+        Ok(Self {
+            task,
+            token: Some(TokenEscrow {
+                mint,
+                escrow,
+                token_program,
+            }),
+            remaining,
+            rent: 0,
+            payouts: Payouts::default(),
+        })
+    }
+
+    /// Pay `amount` of the escrow to the provider, withholding the task's
+    /// protocol fee for the treasury.
+    pub fn pay_provider(
+        &mut self,
+        provider: &AccountInfo<'info>,
+        provider_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+        treasury: &AccountInfo<'info>,
+        treasury_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+        amount: u64,
+    ) -> Result<()> {
+        let fee = bps_share(amount, self.task.protocol_fee_bps)?;
+        let payout = amount.checked_sub(fee).ok_or(AgentPayError::MathOverflow)?;
+
+        if fee > 0 {
+            self.release(treasury, treasury_token_account, fee)?;
+        }
+        self.release(provider, provider_token_account, payout)?;
+        self.payouts.provider = self.payouts.provider.saturating_add(payout);
+        self.payouts.protocol_fee = self.payouts.protocol_fee.saturating_add(fee);
+        Ok(())
+    }
+
+    /// Refund `amount` of the escrow to the requester.
+    pub fn refund(
+        &mut self,
+        requester: &AccountInfo<'info>,
+        requester_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+        amount: u64,
+    ) -> Result<()> {
+        self.release(requester, requester_token_account, amount)?;
+        self.payouts.requester = self.payouts.requester.saturating_add(amount);
+        Ok(())
+    }
+
+    /// Everything paid out so far by this instruction.
+    pub fn payouts(&self) -> Payouts {
+        self.payouts
+    }
+
+    /// Release `amount` of the escrow to `recipient`.
+    /// Native SOL is moved straight off the task PDA; tokens are sent from the
+    /// escrow token account to `recipient_token_account`, signed by the task PDA.
+    fn release(
+        &mut self,
+        recipient: &AccountInfo<'info>,
+        recipient_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+        amount: u64,
+    ) -> Result<()> {
+        self.remaining = self
+            .remaining
+            .checked_sub(amount)
+            .ok_or(AgentPayError::EscrowOverdrawn)?;
+
+        let task = self.task;
+        let Some(token) = &self.token else {
+            let info = task.to_account_info();
+            let balance = info
+                .lamports()
+                .checked_sub(amount)
+                .filter(|balance| *balance >= self.rent)
+                .ok_or(AgentPayError::EscrowUnderfunded)?;
+            let credited = recipient
+                .lamports()
+                .checked_add(amount)
+                .ok_or(AgentPayError::MathOverflow)?;
+            **info.try_borrow_mut_lamports()? = balance;
+            **recipient.try_borrow_mut_lamports()? = credited;
+            return Ok(());
+        };
+
+        let destination = recipient_token_account.ok_or(AgentPayError::TokenAccountsRequired)?;
+        let bump = [task.bump];
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"task",
+            task.requester.as_ref(),
+            task.task_id.as_ref(),
+            &bump,
+        ]];
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                token.token_program.to_account_info(),
+                TransferChecked {
+                    from: token.escrow.to_account_info(),
+                    mint: token.mint.to_account_info(),
+                    to: destination.to_account_info(),
+                    authority: task.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            token.mint.decimals,
+        )
+    }
+}
+
+/// Close a settled task's escrow token account, returning its rent to `destination`.
+/// Token-2022 withholds transfer fees on the receiving account and refuses to close
+/// an account still holding any, so those are harvested to the mint first.
+pub fn close_token_escrow<'info>(
+    task: &Account<'info, TaskRequest>,
+    token: &TokenEscrow<'_, 'info>,
+    destination: &AccountInfo<'info>,
+) -> Result<()> {
+    if withheld_transfer_fees(token.escrow)? > 0 {
+        token_interface::harvest_withheld_tokens_to_mint(
+            CpiContext::new(
+                token.token_program.to_account_info(),
+                HarvestWithheldTokensToMint {
+                    token_program_id: token.token_program.to_account_info(),
+                    mint: token.mint.to_account_info(),
+                },
+            ),
+            vec![token.escrow.to_account_info()],
+        )?;
+    }
+
+    let bump = [task.bump];
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"task",
+        task.requester.as_ref(),
+        task.task_id.as_ref(),
+        &bump,
+    ]];
+
+    token_interface::close_account(CpiContext::new_with_signer(
+        token.token_program.to_account_info(),
+        CloseAccount {
+            account: token.escrow.to_account_info(),
+            destination: destination.clone(),
+            authority: task.to_account_info(),
+        },
+        signer_seeds,
+    ))
+}
+
+/// Transfer fees withheld on a token account; always zero for SPL Token.
+fn withheld_transfer_fees(account: &InterfaceAccount<'_, TokenAccount>) -> Result<u64> {
+    let info = account.to_account_info();
+    let data = info.try_borrow_data()?;
+    let state = StateWithExtensions::<SplTokenAccount>::unpack(&data)?;
+    Ok(state
+        .get_extension::<TransferFeeAmount>()
+        .map_or(0, |fee| fee.withheld_amount.into()))
+}
+
+/// Token accounts a requester funds a new task's escrow from.
+pub struct TokenDeposit<'a, 'info> {
+    pub mint: &'a InterfaceAccount<'info, Mint>,
+    pub from: &'a InterfaceAccount<'info, TokenAccount>,
+    pub escrow: &'a mut InterfaceAccount<'info, TokenAccount>,
+    pub token_program: &'a Interface<'info, TokenInterface>,
+}
+
+/// Lock `amount` from `requester` in the escrow of a new task: native SOL goes onto
+/// the task PDA itself, tokens into the task's escrow token account. Returns what was
+/// escrowed, which for Token-2022 mints with a transfer fee is less than `amount`.
+pub fn deposit<'info>(
+    requester: &AccountInfo<'info>,
+    task: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    token: Option<TokenDeposit<'_, 'info>>,
+    amount: u64,
+) -> Result<u64> {
+    let Some(token) = token else {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: requester.clone(),
+                    to: task.clone(),
+                },
+            ),
+            amount,
+        )?;
+        return Ok(amount);
+    };
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            token.token_program.to_account_info(),
+            TransferChecked {
+                from: token.from.to_account_info(),
+                mint: token.mint.to_account_info(),
+                to: token.escrow.to_account_info(),
+                authority: requester.clone(),
+            },
+        ),
+        amount,
+        token.mint.decimals,
+    )?;
+
+    // Token-2022 transfer fees are withheld from the destination,
+    // so escrow exactly what arrived rather than the amount sent.
+    token.escrow.reload()?;
+    Ok(token.escrow.amount)
+}
+
+/// `bps` basis points of `amount`, rounded down.
+pub fn bps_share(amount: u64, bps: u16) -> Result<u64> {
+    u64::try_from(u128::from(amount) * u128::from(bps) / u128::from(BASIS_POINTS))
+        .map_err(|_| error!(AgentPayError::MathOverflow))
+}
@@ -0,0 +1,343 @@
+use anchor_lang::prelude::*;
+
+use crate::escrow::Payouts;
+use crate::state::{
+    Config, Milestone, MilestoneStatus, ServiceListing, TaskRequest, TaskStatus,
+    VerifyingKeyAccount, ZkCircuit,
+};
+
+// Events are sent with `emit_cpi!`: the program invokes itself with the event as
+// instruction data, so indexers read them from the transaction's inner instructions
+// and they survive log truncation. Every config, verifying key, service, task,
+// milestone and review state change emits one, as does every account closure;
+// the `msg!` lines stay for humans reading logs.
+
+// ============================================================================
+// Config and verifying key events
+// ============================================================================
+
+/// Emitted by `initialize_config`, `set_protocol_fee` and `set_paused` with the
+/// settings after the change.
+#[event]
+pub struct ConfigUpdated {
+    pub config: Pubkey,
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub fee_bps: u16,
+    pub paused: bool,
+}
+
+impl ConfigUpdated {
+    pub fn of(config: &Account<'_, Config>) -> Self {
+        Self {
+            config: config.key(),
+            admin: config.admin,
+            treasury: config.treasury,
+            fee_bps: config.fee_bps,
+            paused: config.paused,
+        }
+    }
+}
+
+#[event]
+pub struct AdminTransferred {
+    pub config: Pubkey,
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+/// Emitted by `init_verifying_key`, `append_verifying_key_ic` and `revoke_verifying_key`
+/// with the key after the change. It verifies proofs once `ic_written` reaches
+/// `nr_pubinputs + 1`, until it is revoked.
+#[event]
+pub struct VerifyingKeyUpdated {
+    pub verifying_key: Pubkey,
+    pub circuit: ZkCircuit,
+    pub version: u16,
+    pub nr_pubinputs: u8,
+    pub ic_written: u16,
+    pub revoked: bool,
+}
+
+impl VerifyingKeyUpdated {
+    pub fn of(vk: &Account<'_, VerifyingKeyAccount>) -> Self {
+        Self {
+            verifying_key: vk.key(),
+            circuit: vk.circuit,
+            version: vk.version,
+            nr_pubinputs: vk.nr_pubinputs,
+            ic_written: vk.ic.len() as u16,
+            revoked: vk.revoked,
+        }
+    }
+}
+
+// ============================================================================
+// Service events
+// ============================================================================
+
+#[event]
+pub struct ServiceRegistered {
+    pub service_listing: Pubkey,
+    pub provider: Pubkey,
+    pub service_id: [u8; 16],
+    /// Price in lamports, or base units of `mint`
+    pub price: u64,
+    /// Default = native SOL
+    pub mint: Pubkey,
+    pub min_reputation: u64,
+    pub arbiter: Pubkey,
+    pub review_window: i64,
+    pub cancellation_fee_bps: u16,
+    pub created_at: i64,
+}
+
+/// Emitted by `update_service`, `deactivate_service` and `reactivate_service` with
+/// the listing's terms after the change.
+#[event]
+pub struct ServiceUpdated {
+    pub service_listing: Pubkey,
+    pub provider: Pubkey,
+    pub description: [u8; 128],
+    pub price: u64,
+    pub min_reputation: u64,
+    pub cancellation_fee_bps: u16,
+    pub is_active: bool,
+}
+
+impl ServiceUpdated {
+    pub fn of(listing: &Account<'_, ServiceListing>) -> Self {
+        Self {
+            service_listing: listing.key(),
+            provider: listing.provider,
+            description: listing.description,
+            price: listing.price_lamports,
+            min_reputation: listing.min_reputation,
+            cancellation_fee_bps: listing.cancellation_fee_bps,
+            is_active: listing.is_active,
+        }
+    }
+}
+
+// ============================================================================
+// Task events
+// ============================================================================
+
+#[event]
+pub struct TaskCreated {
+    pub task: Pubkey,
+    pub requester: Pubkey,
+    pub provider: Pubkey,
+    pub service_listing: Pubkey,
+    pub task_id: [u8; 16],
+    /// Escrowed amount in lamports, or base units of `mint`
+    pub amount: u64,
+    /// Default = native SOL
+    pub mint: Pubkey,
+    pub arbiter: Pubkey,
+    pub deadline: i64,
+    pub review_window: i64,
+    pub cancellation_fee_bps: u16,
+    pub protocol_fee_bps: u16,
+    pub created_at: i64,
+}
+
+/// A provider claimed a bounty, or took over one whose previous claim timed out.
+#[event]
+pub struct BountyClaimed {
+    pub task: Pubkey,
+    pub provider: Pubkey,
+    /// Default = the bounty was unclaimed
+    pub previous_provider: Pubkey,
+    pub claim_expires_at: i64,
+}
+
+#[event]
+pub struct TaskStarted {
+    pub task: Pubkey,
+    pub provider: Pubkey,
+}
+
+/// Emitted by `submit_result`, `submit_result_zk` and `submit_result_encrypted`.
+#[event]
+pub struct ResultSubmitted {
+    pub task: Pubkey,
+    pub provider: Pubkey,
+    pub result_hash: [u8; 32],
+    pub zk_verified: bool,
+    /// Verifying key version the proof was checked against (valid if zk_verified)
+    pub zk_vk_version: u16,
+    /// Chunks committed under `result_hash` by `submit_result_zk` (0 otherwise)
+    pub chunk_count: u32,
+    /// Whether the result was posted encrypted to the requester's key
+    pub encrypted: bool,
+    pub submitted_at: i64,
+}
+
+impl ResultSubmitted {
+    pub fn of(task: &Account<'_, TaskRequest>, encrypted: bool) -> Self {
+        Self {
+            task: task.key(),
+            provider: task.provider,
+            result_hash: task.result_hash,
+            zk_verified: task.zk_verified,
+            zk_vk_version: task.zk_vk_version,
+            chunk_count: task.result_chunk_count,
+            encrypted,
+            submitted_at: task.submitted_at,
+        }
+    }
+}
+
+#[event]
+pub struct TaskDisputed {
+    pub task: Pubkey,
+    pub requester: Pubkey,
+    pub arbiter: Pubkey,
+    pub disputed_at: i64,
+}
+
+/// Emitted once a task reaches a terminal status: accepted (by the requester, the
+/// review-window crank or a fair-exchange delivery), resolved, cancelled or expired.
+/// Amounts cover the escrow paid out by this instruction only; milestones released
+/// earlier were reported by their own `MilestoneSettled` events.
+#[event]
+pub struct TaskSettled {
+    pub task: Pubkey,
+    pub requester: Pubkey,
+    pub provider: Pubkey,
+    /// Default = native SOL
+    pub mint: Pubkey,
+    pub status: TaskStatus,
+    /// Paid to the provider, net of the protocol fee
+    pub provider_amount: u64,
+    /// Refunded to the requester
+    pub requester_amount: u64,
+    /// Withheld for the treasury
+    pub protocol_fee: u64,
+    pub settled_at: i64,
+}
+
+impl TaskSettled {
+    pub fn of(task: &Account<'_, TaskRequest>, payouts: Payouts) -> Result<Self> {
+        Ok(Self {
+            task: task.key(),
+            requester: task.requester,
+            provider: task.provider,
+            mint: task.mint,
+            status: task.status,
+            provider_amount: payouts.provider,
+            requester_amount: payouts.requester,
+            protocol_fee: payouts.protocol_fee,
+            settled_at: Clock::get()?.unix_timestamp,
+        })
+    }
+}
+
+/// The task account was closed and its rent returned to the requester.
+#[event]
+pub struct TaskClosed {
+    pub task: Pubkey,
+    pub requester: Pubkey,
+}
+
+// ============================================================================
+// Milestone events
+// ============================================================================
+
+#[event]
+pub struct MilestoneAdded {
+    pub task: Pubkey,
+    pub milestone: Pubkey,
+    pub index: u8,
+    pub amount: u64,
+}
+
+#[event]
+pub struct MilestoneSubmitted {
+    pub task: Pubkey,
+    pub milestone: Pubkey,
+    pub index: u8,
+    pub result_hash: [u8; 32],
+    pub submitted_at: i64,
+}
+
+#[event]
+pub struct MilestoneDisputed {
+    pub task: Pubkey,
+    pub milestone: Pubkey,
+    pub index: u8,
+    pub arbiter: Pubkey,
+}
+
+/// Emitted when a milestone is released or its dispute resolved. Settling the last
+/// milestone also settles the task, reported by `task_status`.
+#[event]
+pub struct MilestoneSettled {
+    pub task: Pubkey,
+    pub milestone: Pubkey,
+    pub index: u8,
+    pub status: MilestoneStatus,
+    /// Paid to the provider, net of the protocol fee
+    pub provider_amount: u64,
+    /// Refunded to the requester
+    pub requester_amount: u64,
+    /// Withheld for the treasury
+    pub protocol_fee: u64,
+    pub task_status: TaskStatus,
+}
+
+impl MilestoneSettled {
+    pub fn of(
+        task: &Account<'_, TaskRequest>,
+        milestone: &Account<'_, Milestone>,
+        payouts: Payouts,
+    ) -> Self {
+        Self {
+            task: task.key(),
+            milestone: milestone.key(),
+            index: milestone.index,
+            status: milestone.status,
+            provider_amount: payouts.provider,
+            requester_amount: payouts.requester,
+            protocol_fee: payouts.protocol_fee,
+            task_status: task.status,
+        }
+    }
+}
+
+#[event]
+pub struct MilestoneClosed {
+    pub task: Pubkey,
+    pub milestone: Pubkey,
+    pub index: u8,
+}
+
+// ============================================================================
+// Delivery and review events
+// ============================================================================
+
+#[event]
+pub struct EncryptedDeliveryRequested {
+    pub task: Pubkey,
+    pub requester: Pubkey,
+    pub encryption_key: [u8; 64],
+    /// Zero = no fair exchange; the result goes to review
+    pub expected_hash: [u8; 32],
+}
+
+#[event]
+pub struct DeliveryClosed {
+    pub task: Pubkey,
+    pub delivery: Pubkey,
+}
+
+#[event]
+pub struct ReviewSubmitted {
+    pub task: Pubkey,
+    pub reviewer: Pubkey,
+    pub provider: Pubkey,
+    pub service_listing: Pubkey,
+    pub score: u8,
+    pub review_hash: [u8; 32],
+}
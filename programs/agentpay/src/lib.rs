@@ -1,11 +1,15 @@
 use anchor_lang::prelude::*;
-use anchor_lang::system_program;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
 pub mod errors;
+pub mod escrow;
+pub mod events;
 pub mod state;
 pub mod zk;
 
 use errors::AgentPayError;
+use escrow::{bps_share, close_token_escrow, Escrow, Payouts, TokenDeposit, TokenEscrow};
+use events::*;
 use state::*;
 
 declare_id!("2rfRD9jhyK4nwiWiDuixARYsmU3Euw2QMPjmSLHxxYpw");
@@ -14,15 +18,234 @@ declare_id!("2rfRD9jhyK4nwiWiDuixARYsmU3Euw2QMPjmSLHxxYpw");
 pub mod agentpay {
     use super::*;
 
+    /// Create the global protocol config.
+    /// Only the program's upgrade authority can call this, and only once.
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        fee_bps: u16,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        require!(
+            fee_bps <= MAX_PROTOCOL_FEE_BPS,
+            AgentPayError::ProtocolFeeTooHigh
+        );
+        require!(
+            treasury != Pubkey::default(),
+            AgentPayError::InvalidTreasury
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.treasury = treasury;
+        config.fee_bps = fee_bps;
+        config.bump = ctx.bumps.config;
+        config.paused = false;
+
+        msg!(
+            "Config initialized: admin={}, treasury={}, fee={} bps",
+            config.admin,
+            config.treasury,
+            config.fee_bps
+        );
+        emit_cpi!(ConfigUpdated::of(config));
+
+        Ok(())
+    }
+
+    /// Change the protocol fee. Applies to tasks created afterwards.
+    pub fn set_protocol_fee(ctx: Context<UpdateConfig>, fee_bps: u16) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        require!(
+            config.admin == ctx.accounts.admin.key(),
+            AgentPayError::UnauthorizedAdmin
+        );
+        require!(
+            fee_bps <= MAX_PROTOCOL_FEE_BPS,
+            AgentPayError::ProtocolFeeTooHigh
+        );
+
+        config.fee_bps = fee_bps;
+
+        msg!("Protocol fee set to {} bps", fee_bps);
+        emit_cpi!(ConfigUpdated::of(config));
+
+        Ok(())
+    }
+
+    /// Pause or unpause the program.
+    /// While paused no listings or escrow can be created; settlement, refunds
+    /// and disputes keep working so locked funds can still leave.
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        require!(
+            config.admin == ctx.accounts.admin.key(),
+            AgentPayError::UnauthorizedAdmin
+        );
+
+        config.paused = paused;
+
+        msg!("Program paused: {}", paused);
+        emit_cpi!(ConfigUpdated::of(config));
+
+        Ok(())
+    }
+
+    /// Register a verifying key for one version of a circuit. Admin only.
+    /// The IC points are written afterwards with `append_verifying_key_ic`; the key
+    /// cannot verify anything until all `nr_pubinputs + 1` of them are present.
+    /// Both reject points at infinity, see `zk::is_identity`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn init_verifying_key(
+        ctx: Context<InitVerifyingKey>,
+        circuit: ZkCircuit,
+        version: u16,
+        nr_pubinputs: u8,
+        alpha_g1: [u8; 64],
+        beta_g2: [u8; 128],
+        gamma_g2: [u8; 128],
+        delta_g2: [u8; 128],
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.config.admin == ctx.accounts.admin.key(),
+            AgentPayError::UnauthorizedAdmin
+        );
+        require!(
+            usize::from(nr_pubinputs) == circuit.public_inputs(),
+            AgentPayError::InvalidVerifyingKey
+        );
+        require!(
+            ![&alpha_g1[..], &beta_g2, &gamma_g2, &delta_g2]
+                .into_iter()
+                .any(zk::is_identity),
+            AgentPayError::InvalidVerifyingKey
+        );
+
+        let vk = &mut ctx.accounts.verifying_key;
+        vk.circuit = circuit;
+        vk.version = version;
+        vk.nr_pubinputs = nr_pubinputs;
+        vk.alpha_g1 = alpha_g1;
+        vk.beta_g2 = beta_g2;
+        vk.gamma_g2 = gamma_g2;
+        vk.delta_g2 = delta_g2;
+        vk.ic = Vec::new();
+        vk.revoked = false;
+        vk.created_at = Clock::get()?.unix_timestamp;
+        vk.bump = ctx.bumps.verifying_key;
+
+        msg!("Verifying key v{} registered", version);
+        emit_cpi!(VerifyingKeyUpdated::of(vk));
+
+        Ok(())
+    }
+
+    /// Append IC points to a verifying key that is still being written. Admin only.
+    pub fn append_verifying_key_ic(
+        ctx: Context<UpdateVerifyingKey>,
+        ic: Vec<[u8; 64]>,
+    ) -> Result<()> {
+        let vk = &mut ctx.accounts.verifying_key;
+
+        require!(
+            ctx.accounts.config.admin == ctx.accounts.admin.key(),
+            AgentPayError::UnauthorizedAdmin
+        );
+        require!(
+            vk.ic.len() + ic.len() <= usize::from(vk.nr_pubinputs) + 1,
+            AgentPayError::InvalidVerifyingKey
+        );
+        require!(
+            !ic.iter().any(|point| zk::is_identity(point)),
+            AgentPayError::InvalidVerifyingKey
+        );
+
+        vk.ic.extend(ic);
+
+        msg!(
+            "Verifying key v{}: {} of {} IC points written",
+            vk.version,
+            vk.ic.len(),
+            usize::from(vk.nr_pubinputs) + 1
+        );
+        emit_cpi!(VerifyingKeyUpdated::of(vk));
+
+        Ok(())
+    }
+
+    /// Withdraw a verifying key so no further proofs verify against it. Admin only.
+    /// Tasks and attestations already verified keep their recorded version.
+    pub fn revoke_verifying_key(ctx: Context<UpdateVerifyingKey>) -> Result<()> {
+        let vk = &mut ctx.accounts.verifying_key;
+
+        require!(
+            ctx.accounts.config.admin == ctx.accounts.admin.key(),
+            AgentPayError::UnauthorizedAdmin
+        );
+
+        vk.revoked = true;
+
+        msg!("Verifying key v{} revoked", vk.version);
+        emit_cpi!(VerifyingKeyUpdated::of(vk));
+
+        Ok(())
+    }
+
+    /// Hand the admin role to a new key. The new admin must co-sign so the
+    /// role cannot be moved to a key nobody controls.
+    pub fn transfer_admin(ctx: Context<TransferAdmin>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        require!(
+            config.admin == ctx.accounts.admin.key(),
+            AgentPayError::UnauthorizedAdmin
+        );
+
+        let previous_admin = config.admin;
+        config.admin = ctx.accounts.new_admin.key();
+
+        msg!("Admin transferred to {}", config.admin);
+        emit_cpi!(AdminTransferred {
+            config: config.key(),
+            previous_admin,
+            new_admin: config.admin,
+        });
+
+        Ok(())
+    }
+
     /// Register a new service listing on-chain.
     /// The provider advertises what they offer and at what price.
+    /// Passing a `mint` account denominates the price in that SPL / Token-2022 token
+    /// instead of native SOL. The `arbiter` rules on disputes for this listing's tasks,
+    /// `review_window` is how long requesters have to review a result before it auto-accepts,
+    /// and `cancellation_fee_bps` is paid to the provider if a started task is cancelled.
+    #[allow(clippy::too_many_arguments)]
     pub fn register_service(
         ctx: Context<RegisterService>,
         service_id: [u8; 16],
         description: [u8; 128],
         price_lamports: u64,
         min_reputation: u64,
+        arbiter: Pubkey,
+        review_window: i64,
+        cancellation_fee_bps: u16,
     ) -> Result<()> {
+        require!(!ctx.accounts.config.paused, AgentPayError::ProgramPaused);
+        require!(
+            arbiter != Pubkey::default() && arbiter != ctx.accounts.provider.key(),
+            AgentPayError::InvalidArbiter
+        );
+        require!(
+            (1..=MAX_REVIEW_WINDOW).contains(&review_window),
+            AgentPayError::InvalidReviewWindow
+        );
+        require!(
+            u64::from(cancellation_fee_bps) <= BASIS_POINTS,
+            AgentPayError::InvalidBasisPoints
+        );
+
         let listing = &mut ctx.accounts.service_listing;
         listing.provider = ctx.accounts.provider.key();
         listing.service_id = service_id;
@@ -33,6 +256,32 @@ pub mod agentpay {
         listing.created_at = Clock::get()?.unix_timestamp;
         listing.min_reputation = min_reputation;
         listing.bump = ctx.bumps.service_listing;
+        listing.mint = ctx
+            .accounts
+            .mint
+            .as_ref()
+            .map(|mint| mint.key())
+            .unwrap_or_default();
+        listing.arbiter = arbiter;
+        listing.review_window = review_window;
+        listing.cancellation_fee_bps = cancellation_fee_bps;
+
+        ctx.accounts
+            .provider_profile
+            .init_if_new(listing.provider, ctx.bumps.provider_profile)?;
+
+        emit_cpi!(ServiceRegistered {
+            service_listing: listing.key(),
+            provider: listing.provider,
+            service_id,
+            price: price_lamports,
+            mint: listing.mint,
+            min_reputation,
+            arbiter,
+            review_window,
+            cancellation_fee_bps,
+            created_at: listing.created_at,
+        });
         Ok(())
     }
 
@@ -44,6 +293,57 @@ pub mod agentpay {
             AgentPayError::UnauthorizedServiceOwner
         );
         listing.is_active = false;
+
+        emit_cpi!(ServiceUpdated::of(listing));
+        Ok(())
+    }
+
+    /// Update a service listing's price, description, reputation threshold and/or
+    /// cancellation fee. Only the provider can do this. Fields passed as `None` are left
+    /// unchanged. Tasks already in escrow keep the terms they were created with.
+    pub fn update_service(
+        ctx: Context<UpdateService>,
+        price_lamports: Option<u64>,
+        description: Option<[u8; 128]>,
+        min_reputation: Option<u64>,
+        cancellation_fee_bps: Option<u16>,
+    ) -> Result<()> {
+        let listing = &mut ctx.accounts.service_listing;
+        require!(
+            listing.provider == ctx.accounts.provider.key(),
+            AgentPayError::UnauthorizedServiceOwner
+        );
+        if let Some(price_lamports) = price_lamports {
+            listing.price_lamports = price_lamports;
+        }
+        if let Some(description) = description {
+            listing.description = description;
+        }
+        if let Some(min_reputation) = min_reputation {
+            listing.min_reputation = min_reputation;
+        }
+        if let Some(cancellation_fee_bps) = cancellation_fee_bps {
+            require!(
+                u64::from(cancellation_fee_bps) <= BASIS_POINTS,
+                AgentPayError::InvalidBasisPoints
+            );
+            listing.cancellation_fee_bps = cancellation_fee_bps;
+        }
+
+        emit_cpi!(ServiceUpdated::of(listing));
+        Ok(())
+    }
+
+    /// Reactivate a previously deactivated service listing. Only the provider can do this.
+    pub fn reactivate_service(ctx: Context<UpdateService>) -> Result<()> {
+        let listing = &mut ctx.accounts.service_listing;
+        require!(
+            listing.provider == ctx.accounts.provider.key(),
+            AgentPayError::UnauthorizedServiceOwner
+        );
+        listing.is_active = true;
+
+        emit_cpi!(ServiceUpdated::of(listing));
         Ok(())
     }
 
@@ -58,21 +358,45 @@ pub mod agentpay {
         let listing = &ctx.accounts.service_listing;
         let now = Clock::get()?.unix_timestamp;
 
+        require!(!ctx.accounts.config.paused, AgentPayError::ProgramPaused);
         require!(listing.is_active, AgentPayError::ServiceNotActive);
         require!(deadline > now, AgentPayError::DeadlineInPast);
+        require!(
+            ctx.accounts.provider_profile.tasks_completed >= listing.min_reputation,
+            AgentPayError::ReputationTooLow
+        );
 
-        let amount = listing.price_lamports;
-
-        // Transfer SOL from requester to task_request PDA (acts as escrow)
-        system_program::transfer(
-            CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                system_program::Transfer {
-                    from: ctx.accounts.requester.to_account_info(),
-                    to: ctx.accounts.task_request.to_account_info(),
-                },
-            ),
-            amount,
+        let mint = listing.mint;
+        let token = if mint == Pubkey::default() {
+            None
+        } else {
+            let (
+                Some(token_mint),
+                Some(requester_token_account),
+                Some(escrow),
+                Some(token_program),
+            ) = (
+                &ctx.accounts.mint,
+                &ctx.accounts.requester_token_account,
+                &mut ctx.accounts.escrow_token_account,
+                &ctx.accounts.token_program,
+            )
+            else {
+                return err!(AgentPayError::TokenAccountsRequired);
+            };
+            Some(TokenDeposit {
+                mint: token_mint,
+                from: requester_token_account,
+                escrow,
+                token_program,
+            })
+        };
+        let amount = escrow::deposit(
+            &ctx.accounts.requester.to_account_info(),
+            &ctx.accounts.task_request.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            token,
+            listing.price_lamports,
         )?;
 
         let task = &mut ctx.accounts.task_request;
@@ -88,6 +412,30 @@ pub mod agentpay {
         task.created_at = now;
         task.zk_verified = false;
         task.bump = ctx.bumps.task_request;
+        task.mint = mint;
+        task.arbiter = listing.arbiter;
+        task.disputed_at = 0;
+        task.review_window = listing.review_window;
+        task.submitted_at = 0;
+        task.cancellation_fee_bps = listing.cancellation_fee_bps;
+        task.milestone_count = 0;
+        task.milestones_settled = 0;
+        task.milestones_in_review = 0;
+        task.milestone_allocated = 0;
+        task.released_amount = 0;
+        task.protocol_fee_bps = ctx.accounts.config.fee_bps;
+        task.zk_vk_version = 0;
+        task.result_chunk_count = 0;
+        task.min_reputation = 0;
+        task.claim_timeout = 0;
+        task.claimed_at = 0;
+        task.milestones_open = 0;
+        task.delivery_open = false;
+        task.review_open = false;
+
+        ctx.accounts
+            .provider_profile
+            .init_if_new(task.provider, ctx.bumps.provider_profile)?;
 
         msg!(
             "Task created: requester={}, provider={}, amount={}",
@@ -95,149 +443,340 @@ pub mod agentpay {
             task.provider,
             task.amount_lamports
         );
+        emit_cpi!(TaskCreated {
+            task: task.key(),
+            requester: task.requester,
+            provider: task.provider,
+            service_listing: task.service_listing,
+            task_id,
+            amount,
+            mint,
+            arbiter: task.arbiter,
+            deadline,
+            review_window: task.review_window,
+            cancellation_fee_bps: task.cancellation_fee_bps,
+            protocol_fee_bps: task.protocol_fee_bps,
+            created_at: now,
+        });
 
         Ok(())
     }
 
-    /// Provider submits a result hash for a task.
-    /// The actual result data is exchanged off-chain; the hash provides verifiability.
-    pub fn submit_result(
-        ctx: Context<SubmitResult>,
-        result_hash: [u8; 32],
+    /// Post an open bounty: escrow `amount` with no provider, for any provider who has
+    /// completed at least `min_reputation` tasks to claim with `claim_bounty`. Passing a
+    /// `mint` account escrows that SPL / Token-2022 token instead of native SOL. The
+    /// requester picks the `arbiter` and `review_window` a listing would otherwise set;
+    /// bounties carry no cancellation fee.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_bounty(
+        ctx: Context<CreateBounty>,
+        task_id: [u8; 16],
+        description: [u8; 256],
+        amount: u64,
+        deadline: i64,
+        min_reputation: u64,
+        claim_timeout: i64,
+        arbiter: Pubkey,
+        review_window: i64,
     ) -> Result<()> {
-        let task = &mut ctx.accounts.task_request;
         let now = Clock::get()?.unix_timestamp;
 
+        require!(!ctx.accounts.config.paused, AgentPayError::ProgramPaused);
+        require!(deadline > now, AgentPayError::DeadlineInPast);
         require!(
-            task.provider == ctx.accounts.provider.key(),
-            AgentPayError::UnauthorizedProvider
+            arbiter != Pubkey::default() && arbiter != ctx.accounts.requester.key(),
+            AgentPayError::InvalidArbiter
         );
         require!(
-            task.status == TaskStatus::Open,
-            AgentPayError::InvalidTaskStatus
+            (1..=MAX_REVIEW_WINDOW).contains(&review_window),
+            AgentPayError::InvalidReviewWindow
+        );
+        require!(
+            (1..=MAX_CLAIM_TIMEOUT).contains(&claim_timeout),
+            AgentPayError::InvalidClaimTimeout
         );
-        require!(now <= task.deadline, AgentPayError::DeadlinePassed);
 
-        task.result_hash = result_hash;
-        task.status = TaskStatus::Submitted;
+        let token = match &ctx.accounts.mint {
+            None => None,
+            Some(token_mint) => {
+                let (Some(requester_token_account), Some(escrow), Some(token_program)) = (
+                    &ctx.accounts.requester_token_account,
+                    &mut ctx.accounts.escrow_token_account,
+                    &ctx.accounts.token_program,
+                ) else {
+                    return err!(AgentPayError::TokenAccountsRequired);
+                };
+                Some(TokenDeposit {
+                    mint: token_mint,
+                    from: requester_token_account,
+                    escrow,
+                    token_program,
+                })
+            }
+        };
+        let mint = token
+            .as_ref()
+            .map(|token| token.mint.key())
+            .unwrap_or_default();
+        let amount = escrow::deposit(
+            &ctx.accounts.requester.to_account_info(),
+            &ctx.accounts.task_request.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            token,
+            amount,
+        )?;
 
-        msg!("Result submitted for task by provider {}", task.provider);
+        let task = &mut ctx.accounts.task_request;
+        task.requester = ctx.accounts.requester.key();
+        task.provider = Pubkey::default();
+        task.service_listing = Pubkey::default();
+        task.task_id = task_id;
+        task.description = description;
+        task.amount_lamports = amount;
+        task.status = TaskStatus::Open;
+        task.result_hash = [0u8; 32];
+        task.deadline = deadline;
+        task.created_at = now;
+        task.zk_verified = false;
+        task.bump = ctx.bumps.task_request;
+        task.mint = mint;
+        task.arbiter = arbiter;
+        task.disputed_at = 0;
+        task.review_window = review_window;
+        task.submitted_at = 0;
+        task.cancellation_fee_bps = 0;
+        task.milestone_count = 0;
+        task.milestones_settled = 0;
+        task.milestones_in_review = 0;
+        task.milestone_allocated = 0;
+        task.released_amount = 0;
+        task.protocol_fee_bps = ctx.accounts.config.fee_bps;
+        task.zk_vk_version = 0;
+        task.result_chunk_count = 0;
+        task.min_reputation = min_reputation;
+        task.claim_timeout = claim_timeout;
+        task.claimed_at = 0;
+        task.milestones_open = 0;
+        task.delivery_open = false;
+        task.review_open = false;
+
+        msg!(
+            "Bounty created: requester={}, amount={}, min reputation {}",
+            task.requester,
+            task.amount_lamports,
+            min_reputation
+        );
+        emit_cpi!(TaskCreated {
+            task: task.key(),
+            requester: task.requester,
+            provider: task.provider,
+            service_listing: task.service_listing,
+            task_id,
+            amount,
+            mint,
+            arbiter,
+            deadline,
+            review_window,
+            cancellation_fee_bps: 0,
+            protocol_fee_bps: task.protocol_fee_bps,
+            created_at: now,
+        });
 
         Ok(())
     }
 
-    /// Requester accepts the submitted result.
-    /// Escrow funds are released to the provider.
-    pub fn accept_result(ctx: Context<AcceptResult>) -> Result<()> {
+    /// Claim an open bounty and start working on it; the claimant becomes the task's
+    /// provider. The provider's profile must show at least the bounty's `min_reputation`
+    /// completed tasks. A claim that has produced no result within the bounty's
+    /// `claim_timeout` reopens the bounty, and another provider can claim it over.
+    pub fn claim_bounty(ctx: Context<ClaimBounty>) -> Result<()> {
+        let provider = ctx.accounts.provider.key();
+        let profile = &mut ctx.accounts.provider_profile;
+        profile.init_if_new(provider, ctx.bumps.provider_profile)?;
+
         let task = &mut ctx.accounts.task_request;
+        let now = Clock::get()?.unix_timestamp;
 
+        require!(task.is_bounty(), AgentPayError::NotABounty);
+        match task.status {
+            TaskStatus::Open => {}
+            // Only a lapsed claim with no milestone under way can be taken over
+            TaskStatus::InProgress => require!(
+                now > task.claim_expires_at()?
+                    && task.milestones_settled == 0
+                    && task.milestones_in_review == 0,
+                AgentPayError::BountyAlreadyClaimed
+            ),
+            _ => return err!(AgentPayError::InvalidTaskStatus),
+        }
+        require!(now <= task.deadline, AgentPayError::DeadlinePassed);
+        require!(task.milestones_funded(), AgentPayError::MilestonesNotFunded);
+        require!(provider != task.arbiter, AgentPayError::InvalidArbiter);
         require!(
-            task.requester == ctx.accounts.requester.key(),
-            AgentPayError::UnauthorizedRequester
-        );
-        require!(
-            task.status == TaskStatus::Submitted,
-            AgentPayError::InvalidTaskStatus
+            profile.tasks_completed >= task.min_reputation,
+            AgentPayError::ReputationTooLow
         );
 
-        let amount = task.amount_lamports;
-        task.status = TaskStatus::Completed;
-
-        // Transfer lamports from task PDA to provider
-        let task_account_info = task.to_account_info();
-        let provider_account_info = ctx.accounts.provider.to_account_info();
+        let previous_provider = task.provider;
+        task.provider = provider;
+        task.claimed_at = now;
+        task.status = TaskStatus::InProgress;
 
-        **task_account_info.try_borrow_mut_lamports()? -= amount;
-        **provider_account_info.try_borrow_mut_lamports()? += amount;
-
-        // Increment the provider's completed task counter
-        let listing = &mut ctx.accounts.service_listing;
-        listing.tasks_completed = listing.tasks_completed.checked_add(1).unwrap();
-
-        msg!(
-            "Task completed: {} lamports released to provider {}",
-            amount,
-            task.provider
-        );
+        msg!("Bounty claimed by provider {}", provider);
+        emit_cpi!(BountyClaimed {
+            task: task.key(),
+            provider,
+            previous_provider,
+            claim_expires_at: task.claim_expires_at()?,
+        });
 
         Ok(())
     }
 
-    /// Requester disputes the task. Funds are returned to the requester.
-    /// Simple dispute model: requester gets an immediate refund.
-    pub fn dispute_task(ctx: Context<DisputeTask>) -> Result<()> {
-        let task = &mut ctx.accounts.task_request;
+    /// Requester withdraws a bounty no provider has claimed, refunding the escrow in full.
+    /// A claimed bounty is cancelled with `cancel_task` like any other task.
+    pub fn cancel_bounty(ctx: Context<CancelBounty>) -> Result<()> {
+        let task = &ctx.accounts.task_request;
 
         require!(
             task.requester == ctx.accounts.requester.key(),
             AgentPayError::UnauthorizedRequester
         );
+        require!(task.is_bounty(), AgentPayError::NotABounty);
         require!(
-            task.status == TaskStatus::Submitted,
+            task.status == TaskStatus::Open,
             AgentPayError::InvalidTaskStatus
         );
 
-        let amount = task.amount_lamports;
-        task.status = TaskStatus::Disputed;
-
-        // Refund lamports from task PDA back to requester
-        let task_account_info = task.to_account_info();
-        let requester_account_info = ctx.accounts.requester.to_account_info();
+        let mut escrow = Escrow::resolve(
+            task,
+            &ctx.accounts.mint,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        escrow.refund(
+            &ctx.accounts.requester.to_account_info(),
+            ctx.accounts.requester_token_account.as_ref(),
+            task.escrow_remaining()?,
+        )?;
+        let payouts = escrow.payouts();
 
-        **task_account_info.try_borrow_mut_lamports()? -= amount;
-        **requester_account_info.try_borrow_mut_lamports()? += amount;
+        let task = &mut ctx.accounts.task_request;
+        task.status = TaskStatus::Cancelled;
 
         msg!(
-            "Task disputed: {} lamports refunded to requester {}",
-            amount,
+            "Bounty cancelled: {} refunded to requester {}",
+            payouts.requester,
             task.requester
         );
+        emit_cpi!(TaskSettled::of(task, payouts)?);
 
         Ok(())
     }
 
-    /// Expire a task that has passed its deadline without a result submission.
-    /// Anyone can call this (permissionless crank). Funds return to the requester.
-    pub fn expire_task(ctx: Context<ExpireTask>) -> Result<()> {
+    /// Provider acknowledges an open task and starts working on it.
+    /// From here on, a requester cancellation pays the provider the listing's cancellation fee.
+    pub fn start_task(ctx: Context<StartTask>) -> Result<()> {
         let task = &mut ctx.accounts.task_request;
         let now = Clock::get()?.unix_timestamp;
 
+        require!(
+            task.provider == ctx.accounts.provider.key(),
+            AgentPayError::UnauthorizedProvider
+        );
         require!(
             task.status == TaskStatus::Open,
             AgentPayError::InvalidTaskStatus
         );
-        require!(now > task.deadline, AgentPayError::DeadlineNotReached);
+        require!(now <= task.deadline, AgentPayError::DeadlinePassed);
+        require!(task.milestones_funded(), AgentPayError::MilestonesNotFunded);
 
-        let amount = task.amount_lamports;
-        task.status = TaskStatus::Expired;
+        task.status = TaskStatus::InProgress;
+
+        msg!("Task started by provider {}", task.provider);
+        emit_cpi!(TaskStarted {
+            task: task.key(),
+            provider: task.provider,
+        });
+
+        Ok(())
+    }
+
+    /// Requester cancels a task before a result is submitted.
+    /// Open tasks are fully refunded; once the provider has started, the provider
+    /// keeps the task's cancellation fee and the rest is refunded.
+    pub fn cancel_task(ctx: Context<CancelTask>) -> Result<()> {
+        let task = &ctx.accounts.task_request;
+
+        require!(
+            task.requester == ctx.accounts.requester.key(),
+            AgentPayError::UnauthorizedRequester
+        );
+        require!(
+            task.status.is_awaiting_result(),
+            AgentPayError::InvalidTaskStatus
+        );
+        require!(
+            task.milestones_in_review == 0,
+            AgentPayError::MilestonesInReview
+        );
 
-        // Refund lamports from task PDA back to requester
-        let task_account_info = task.to_account_info();
-        let requester_account_info = ctx.accounts.requester.to_account_info();
+        // Milestones already released stay with the provider
+        let amount = task.escrow_remaining()?;
+        let provider_fee = if task.status == TaskStatus::InProgress {
+            bps_share(amount, task.cancellation_fee_bps)?
+        } else {
+            0
+        };
+        let refund = amount
+            .checked_sub(provider_fee)
+            .ok_or(AgentPayError::MathOverflow)?;
+
+        let mut escrow = Escrow::resolve(
+            task,
+            &ctx.accounts.mint,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        if provider_fee > 0 {
+            escrow.pay_provider(
+                &ctx.accounts.provider.to_account_info(),
+                ctx.accounts.provider_token_account.as_ref(),
+                &ctx.accounts.treasury.to_account_info(),
+                ctx.accounts.treasury_token_account.as_ref(),
+                provider_fee,
+            )?;
+        }
+        if refund > 0 {
+            escrow.refund(
+                &ctx.accounts.requester.to_account_info(),
+                ctx.accounts.requester_token_account.as_ref(),
+                refund,
+            )?;
+        }
+
+        let payouts = escrow.payouts();
 
-        **task_account_info.try_borrow_mut_lamports()? -= amount;
-        **requester_account_info.try_borrow_mut_lamports()? += amount;
+        let task = &mut ctx.accounts.task_request;
+        task.status = TaskStatus::Cancelled;
+        task.record_on_profile(ctx.accounts.provider_profile.as_mut(), payouts.provider)?;
 
         msg!(
-            "Task expired: {} lamports refunded to requester {}",
-            amount,
-            task.requester
+            "Task cancelled: {} refunded to requester {}, {} fee to provider {}",
+            refund,
+            task.requester,
+            provider_fee,
+            task.provider
         );
+        emit_cpi!(TaskSettled::of(task, payouts)?);
 
         Ok(())
     }
 
-    /// Submit a result with ZK proof verification.
-    /// The provider proves knowledge of the result pre-image via a Groth16 proof.
-    /// proof_a: 64 bytes (G1, negated, big-endian)
-    /// proof_b: 128 bytes (G2, big-endian)
-    /// proof_c: 64 bytes (G1, big-endian)
-    /// result_hash: 32 bytes (the Poseidon hash, used as public input)
-    pub fn submit_result_zk(
+    /// Provider submits a result hash for a task.
+    /// The actual result data is exchanged off-chain; the hash provides verifiability.
+    pub fn submit_result(
         ctx: Context<SubmitResult>,
-        proof_a: [u8; 64],
-        proof_b: [u8; 128],
-        proof_c: [u8; 64],
         result_hash: [u8; 32],
     ) -> Result<()> {
         let task = &mut ctx.accounts.task_request;
@@ -248,140 +787,2225 @@ pub mod agentpay {
             AgentPayError::UnauthorizedProvider
         );
         require!(
-            task.status == TaskStatus::Open,
+            task.status.is_awaiting_result(),
             AgentPayError::InvalidTaskStatus
         );
         require!(now <= task.deadline, AgentPayError::DeadlinePassed);
-
-        // Verify the Groth16 proof on-chain
-        // Public input: the Poseidon hash of the result
-        zk::verify_task_proof(&proof_a, &proof_b, &proof_c, &[result_hash])?;
+        require!(task.milestone_count == 0, AgentPayError::TaskHasMilestones);
 
         task.result_hash = result_hash;
         task.status = TaskStatus::Submitted;
-        task.zk_verified = true;
+        task.submitted_at = now;
 
-        msg!(
-            "ZK-verified result submitted for task by provider {}",
-            task.provider
-        );
+        msg!("Result submitted for task by provider {}", task.provider);
+        emit_cpi!(ResultSubmitted::of(task, false));
 
         Ok(())
     }
 
-    /// Verify a provider's reputation meets the service minimum requirement.
-    /// Uses a ZK proof to prove reputation >= threshold without revealing exact score.
-    /// This is called by the requester before creating a task, to verify the provider qualifies.
-    pub fn verify_reputation(
-        ctx: Context<VerifyReputation>,
-        proof_a: [u8; 64],
-        proof_b: [u8; 128],
-        proof_c: [u8; 64],
-        threshold: [u8; 32],
-        provider_commitment: [u8; 32],
-    ) -> Result<()> {
-        let listing = &ctx.accounts.service_listing;
-
-        require!(listing.is_active, AgentPayError::ServiceNotActive);
-
-        // Verify the Groth16 reputation proof
-        // Public inputs: [threshold, providerCommitment]
-        zk::verify_reputation_proof(&proof_a, &proof_b, &proof_c, &[threshold, provider_commitment])?;
-
-        msg!(
-            "Reputation verified for service {} (threshold met)",
-            ctx.accounts.service_listing.key()
-        );
+    /// Requester accepts the submitted result.
+    /// Escrow funds are released to the provider.
+    pub fn accept_result(ctx: Context<AcceptResult>) -> Result<()> {
+        let task = &ctx.accounts.task_request;
+
+        require!(
+            task.requester == ctx.accounts.requester.key(),
+            AgentPayError::UnauthorizedRequester
+        );
+        require!(
+            task.status == TaskStatus::Submitted,
+            AgentPayError::InvalidTaskStatus
+        );
+
+        let amount = task.amount_lamports;
+
+        // Release escrow from task PDA to provider
+        let mut escrow = Escrow::resolve(
+            task,
+            &ctx.accounts.mint,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        escrow.pay_provider(
+            &ctx.accounts.provider.to_account_info(),
+            ctx.accounts.provider_token_account.as_ref(),
+            &ctx.accounts.treasury.to_account_info(),
+            ctx.accounts.treasury_token_account.as_ref(),
+            amount,
+        )?;
+
+        let payouts = escrow.payouts();
+
+        let task = &mut ctx.accounts.task_request;
+        task.status = TaskStatus::Completed;
+
+        // Increment the provider's completed task counter
+        task.count_listing_completion(ctx.accounts.service_listing.as_mut())?;
+        ctx.accounts
+            .provider_profile
+            .record(task, payouts.provider)?;
+
+        msg!(
+            "Task completed: {} lamports released to provider {}",
+            amount,
+            task.provider
+        );
+        emit_cpi!(TaskSettled::of(task, payouts)?);
 
         Ok(())
     }
-}
 
-// ============================================================================
-// Account validation structs
-// ============================================================================
+    /// Requester disputes the submitted result.
+    /// Escrow stays locked in the task PDA until the task's arbiter calls `resolve_dispute`,
+    /// or either party calls `expire_dispute` once the arbitration window has passed.
+    pub fn dispute_task(ctx: Context<DisputeTask>) -> Result<()> {
+        let task = &mut ctx.accounts.task_request;
+
+        require!(
+            task.requester == ctx.accounts.requester.key(),
+            AgentPayError::UnauthorizedRequester
+        );
+        require!(
+            task.status == TaskStatus::Submitted,
+            AgentPayError::InvalidTaskStatus
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now <= task.review_deadline()?,
+            AgentPayError::ReviewWindowClosed
+        );
+
+        task.status = TaskStatus::DisputePending;
+        task.disputed_at = now;
+
+        msg!(
+            "Task disputed by requester {}, awaiting arbiter {}",
+            task.requester,
+            task.arbiter
+        );
+        emit_cpi!(TaskDisputed {
+            task: task.key(),
+            requester: task.requester,
+            arbiter: task.arbiter,
+            disputed_at: now,
+        });
+
+        Ok(())
+    }
+
+    /// Auto-accept a submitted result once the review window has passed.
+    /// Anyone can call this (permissionless crank). Funds are released to the provider.
+    pub fn auto_accept(ctx: Context<AutoAccept>) -> Result<()> {
+        let task = &ctx.accounts.task_request;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(
+            task.status == TaskStatus::Submitted,
+            AgentPayError::InvalidTaskStatus
+        );
+        require!(
+            now > task.review_deadline()?,
+            AgentPayError::ReviewWindowActive
+        );
+
+        let amount = task.amount_lamports;
+
+        // Release escrow from task PDA to provider
+        let mut escrow = Escrow::resolve(
+            task,
+            &ctx.accounts.mint,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        escrow.pay_provider(
+            &ctx.accounts.provider.to_account_info(),
+            ctx.accounts.provider_token_account.as_ref(),
+            &ctx.accounts.treasury.to_account_info(),
+            ctx.accounts.treasury_token_account.as_ref(),
+            amount,
+        )?;
+
+        let payouts = escrow.payouts();
+
+        let task = &mut ctx.accounts.task_request;
+        task.status = TaskStatus::Completed;
+
+        task.count_listing_completion(ctx.accounts.service_listing.as_mut())?;
+        ctx.accounts
+            .provider_profile
+            .record(task, payouts.provider)?;
+
+        msg!(
+            "Task auto-accepted: {} released to provider {}",
+            amount,
+            task.provider
+        );
+        emit_cpi!(TaskSettled::of(task, payouts)?);
+
+        Ok(())
+    }
+
+    /// Arbiter resolves a pending dispute by splitting escrow.
+    /// `provider_bps` of the escrow goes to the provider, the remainder to the requester.
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, provider_bps: u16) -> Result<()> {
+        let task = &ctx.accounts.task_request;
+
+        require!(
+            task.arbiter == ctx.accounts.arbiter.key(),
+            AgentPayError::UnauthorizedArbiter
+        );
+        require!(
+            task.status == TaskStatus::DisputePending,
+            AgentPayError::InvalidTaskStatus
+        );
+        require!(
+            u64::from(provider_bps) <= BASIS_POINTS,
+            AgentPayError::InvalidBasisPoints
+        );
+
+        let amount = task.amount_lamports;
+        let provider_share = bps_share(amount, provider_bps)?;
+        let requester_share = amount
+            .checked_sub(provider_share)
+            .ok_or(AgentPayError::MathOverflow)?;
+
+        let mut escrow = Escrow::resolve(
+            task,
+            &ctx.accounts.mint,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        if provider_share > 0 {
+            escrow.pay_provider(
+                &ctx.accounts.provider.to_account_info(),
+                ctx.accounts.provider_token_account.as_ref(),
+                &ctx.accounts.treasury.to_account_info(),
+                ctx.accounts.treasury_token_account.as_ref(),
+                provider_share,
+            )?;
+        }
+        if requester_share > 0 {
+            escrow.refund(
+                &ctx.accounts.requester.to_account_info(),
+                ctx.accounts.requester_token_account.as_ref(),
+                requester_share,
+            )?;
+        }
+
+        let payouts = escrow.payouts();
+
+        let task = &mut ctx.accounts.task_request;
+        task.status = TaskStatus::Disputed;
+        ctx.accounts
+            .provider_profile
+            .record(task, payouts.provider)?;
+
+        msg!(
+            "Dispute resolved: {} to provider {}, {} to requester {}",
+            provider_share,
+            task.provider,
+            requester_share,
+            task.requester
+        );
+        emit_cpi!(TaskSettled::of(task, payouts)?);
+
+        Ok(())
+    }
+
+    /// Refund a dispute the arbiter has not ruled on within `ARBITRATION_WINDOW`.
+    /// Either party can call it, so an absent arbiter cannot lock the escrow for good.
+    pub fn expire_dispute(ctx: Context<ExpireDispute>) -> Result<()> {
+        let task = &ctx.accounts.task_request;
+        let party = ctx.accounts.party.key();
+
+        require!(
+            party == task.requester || party == task.provider,
+            AgentPayError::UnauthorizedDisputeParty
+        );
+        require!(
+            task.status == TaskStatus::DisputePending,
+            AgentPayError::InvalidTaskStatus
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now > task.arbitration_deadline()?,
+            AgentPayError::ArbitrationWindowActive
+        );
+
+        let amount = task.escrow_remaining()?;
+
+        let mut escrow = Escrow::resolve(
+            task,
+            &ctx.accounts.mint,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        escrow.refund(
+            &ctx.accounts.requester.to_account_info(),
+            ctx.accounts.requester_token_account.as_ref(),
+            amount,
+        )?;
+
+        let payouts = escrow.payouts();
+
+        let task = &mut ctx.accounts.task_request;
+        task.status = TaskStatus::Disputed;
+        ctx.accounts
+            .provider_profile
+            .record(task, payouts.provider)?;
+
+        msg!(
+            "Dispute expired unresolved: {} refunded to requester {}",
+            amount,
+            task.requester
+        );
+        emit_cpi!(TaskSettled::of(task, payouts)?);
+
+        Ok(())
+    }
+
+    /// Requester splits an open task's escrow into milestones.
+    /// Milestones are added in order and must cover the full escrow before the provider
+    /// can start; each one is then submitted, accepted or disputed on its own.
+    pub fn add_milestone(
+        ctx: Context<AddMilestone>,
+        index: u8,
+        amount: u64,
+        description: [u8; 128],
+    ) -> Result<()> {
+        let task = &mut ctx.accounts.task_request;
+
+        require!(
+            task.requester == ctx.accounts.requester.key(),
+            AgentPayError::UnauthorizedRequester
+        );
+        require!(
+            task.status == TaskStatus::Open,
+            AgentPayError::InvalidTaskStatus
+        );
+        require!(
+            index == task.milestone_count,
+            AgentPayError::InvalidMilestoneIndex
+        );
+
+        let allocated = task
+            .milestone_allocated
+            .checked_add(amount)
+            .ok_or(AgentPayError::MathOverflow)?;
+        require!(
+            amount > 0 && allocated <= task.amount_lamports,
+            AgentPayError::InvalidMilestoneAmount
+        );
+
+        task.milestone_allocated = allocated;
+        task.milestone_count = task
+            .milestone_count
+            .checked_add(1)
+            .ok_or(AgentPayError::MathOverflow)?;
+        task.milestones_open = task
+            .milestones_open
+            .checked_add(1)
+            .ok_or(AgentPayError::MathOverflow)?;
+
+        let milestone = &mut ctx.accounts.milestone;
+        milestone.task = task.key();
+        milestone.index = index;
+        milestone.amount = amount;
+        milestone.description = description;
+        milestone.status = MilestoneStatus::Pending;
+        milestone.result_hash = [0u8; 32];
+        milestone.submitted_at = 0;
+        milestone.bump = ctx.bumps.milestone;
+
+        msg!("Milestone {} added: {} of task escrow", index, amount);
+        emit_cpi!(MilestoneAdded {
+            task: milestone.task,
+            milestone: milestone.key(),
+            index,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Provider submits a result hash for one milestone.
+    pub fn submit_milestone(ctx: Context<SubmitMilestone>, result_hash: [u8; 32]) -> Result<()> {
+        let task = &mut ctx.accounts.task_request;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(
+            task.provider == ctx.accounts.provider.key(),
+            AgentPayError::UnauthorizedProvider
+        );
+        require!(
+            task.status.is_awaiting_result(),
+            AgentPayError::InvalidTaskStatus
+        );
+        require!(now <= task.deadline, AgentPayError::DeadlinePassed);
+        require!(task.milestones_funded(), AgentPayError::MilestonesNotFunded);
+
+        let milestone = &mut ctx.accounts.milestone;
+        require!(
+            milestone.status == MilestoneStatus::Pending,
+            AgentPayError::InvalidMilestoneStatus
+        );
+
+        milestone.result_hash = result_hash;
+        milestone.status = MilestoneStatus::Submitted;
+        milestone.submitted_at = now;
+        task.milestones_in_review = task
+            .milestones_in_review
+            .checked_add(1)
+            .ok_or(AgentPayError::MathOverflow)?;
+
+        msg!(
+            "Milestone {} submitted by provider {}",
+            milestone.index,
+            task.provider
+        );
+        emit_cpi!(MilestoneSubmitted {
+            task: task.key(),
+            milestone: milestone.key(),
+            index: milestone.index,
+            result_hash,
+            submitted_at: now,
+        });
+
+        Ok(())
+    }
+
+    /// Requester accepts a submitted milestone and its amount is released to the provider.
+    /// Accepting the last milestone completes the task.
+    pub fn accept_milestone(ctx: Context<AcceptMilestone>) -> Result<()> {
+        let task = &ctx.accounts.task_request;
+        let milestone = &ctx.accounts.milestone;
+
+        require!(
+            task.requester == ctx.accounts.requester.key(),
+            AgentPayError::UnauthorizedRequester
+        );
+        require!(
+            milestone.status == MilestoneStatus::Submitted,
+            AgentPayError::InvalidMilestoneStatus
+        );
+
+        let amount = milestone.amount;
+
+        let mut escrow = Escrow::resolve(
+            task,
+            &ctx.accounts.mint,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        escrow.pay_provider(
+            &ctx.accounts.provider.to_account_info(),
+            ctx.accounts.provider_token_account.as_ref(),
+            &ctx.accounts.treasury.to_account_info(),
+            ctx.accounts.treasury_token_account.as_ref(),
+            amount,
+        )?;
+
+        let payouts = escrow.payouts();
+
+        let milestone = &mut ctx.accounts.milestone;
+        milestone.status = MilestoneStatus::Released;
+
+        let task = &mut ctx.accounts.task_request;
+        if task.settle_milestone(amount)? {
+            task.status = TaskStatus::Completed;
+            task.count_listing_completion(ctx.accounts.service_listing.as_mut())?;
+        }
+        ctx.accounts
+            .provider_profile
+            .record(task, payouts.provider)?;
+
+        msg!(
+            "Milestone {} accepted: {} released to provider {}",
+            milestone.index,
+            amount,
+            task.provider
+        );
+        emit_cpi!(MilestoneSettled::of(task, milestone, payouts));
+
+        Ok(())
+    }
+
+    /// Auto-accept a submitted milestone once the task's review window has passed.
+    /// Anyone can call this (permissionless crank).
+    pub fn auto_accept_milestone(ctx: Context<AutoAcceptMilestone>) -> Result<()> {
+        let task = &ctx.accounts.task_request;
+        let milestone = &ctx.accounts.milestone;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(
+            milestone.status == MilestoneStatus::Submitted,
+            AgentPayError::InvalidMilestoneStatus
+        );
+        let review_deadline = milestone
+            .submitted_at
+            .checked_add(task.review_window)
+            .ok_or(AgentPayError::MathOverflow)?;
+        require!(now > review_deadline, AgentPayError::ReviewWindowActive);
+
+        let amount = milestone.amount;
+
+        let mut escrow = Escrow::resolve(
+            task,
+            &ctx.accounts.mint,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        escrow.pay_provider(
+            &ctx.accounts.provider.to_account_info(),
+            ctx.accounts.provider_token_account.as_ref(),
+            &ctx.accounts.treasury.to_account_info(),
+            ctx.accounts.treasury_token_account.as_ref(),
+            amount,
+        )?;
+
+        let payouts = escrow.payouts();
+
+        let milestone = &mut ctx.accounts.milestone;
+        milestone.status = MilestoneStatus::Released;
+
+        let task = &mut ctx.accounts.task_request;
+        if task.settle_milestone(amount)? {
+            task.status = TaskStatus::Completed;
+            task.count_listing_completion(ctx.accounts.service_listing.as_mut())?;
+        }
+        ctx.accounts
+            .provider_profile
+            .record(task, payouts.provider)?;
+
+        msg!(
+            "Milestone {} auto-accepted: {} released to provider {}",
+            milestone.index,
+            amount,
+            task.provider
+        );
+        emit_cpi!(MilestoneSettled::of(task, milestone, payouts));
+
+        Ok(())
+    }
+
+    /// Requester disputes a submitted milestone within the review window.
+    /// The milestone amount stays locked until the task's arbiter rules.
+    pub fn dispute_milestone(ctx: Context<DisputeMilestone>) -> Result<()> {
+        let task = &ctx.accounts.task_request;
+        let milestone = &mut ctx.accounts.milestone;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(
+            task.requester == ctx.accounts.requester.key(),
+            AgentPayError::UnauthorizedRequester
+        );
+        require!(
+            milestone.status == MilestoneStatus::Submitted,
+            AgentPayError::InvalidMilestoneStatus
+        );
+        let review_deadline = milestone
+            .submitted_at
+            .checked_add(task.review_window)
+            .ok_or(AgentPayError::MathOverflow)?;
+        require!(now <= review_deadline, AgentPayError::ReviewWindowClosed);
+
+        milestone.status = MilestoneStatus::DisputePending;
+
+        msg!(
+            "Milestone {} disputed by requester {}, awaiting arbiter {}",
+            milestone.index,
+            task.requester,
+            task.arbiter
+        );
+        emit_cpi!(MilestoneDisputed {
+            task: task.key(),
+            milestone: milestone.key(),
+            index: milestone.index,
+            arbiter: task.arbiter,
+        });
+
+        Ok(())
+    }
+
+    /// Arbiter resolves a disputed milestone by splitting its amount.
+    /// `provider_bps` of the milestone goes to the provider, the remainder to the requester.
+    /// Settling the last milestone through a dispute leaves the task `Disputed`.
+    pub fn resolve_milestone_dispute(
+        ctx: Context<ResolveMilestoneDispute>,
+        provider_bps: u16,
+    ) -> Result<()> {
+        let task = &ctx.accounts.task_request;
+        let milestone = &ctx.accounts.milestone;
+
+        require!(
+            task.arbiter == ctx.accounts.arbiter.key(),
+            AgentPayError::UnauthorizedArbiter
+        );
+        require!(
+            milestone.status == MilestoneStatus::DisputePending,
+            AgentPayError::InvalidMilestoneStatus
+        );
+        require!(
+            u64::from(provider_bps) <= BASIS_POINTS,
+            AgentPayError::InvalidBasisPoints
+        );
+
+        let amount = milestone.amount;
+        let provider_share = bps_share(amount, provider_bps)?;
+        let requester_share = amount
+            .checked_sub(provider_share)
+            .ok_or(AgentPayError::MathOverflow)?;
+
+        let mut escrow = Escrow::resolve(
+            task,
+            &ctx.accounts.mint,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        if provider_share > 0 {
+            escrow.pay_provider(
+                &ctx.accounts.provider.to_account_info(),
+                ctx.accounts.provider_token_account.as_ref(),
+                &ctx.accounts.treasury.to_account_info(),
+                ctx.accounts.treasury_token_account.as_ref(),
+                provider_share,
+            )?;
+        }
+        if requester_share > 0 {
+            escrow.refund(
+                &ctx.accounts.requester.to_account_info(),
+                ctx.accounts.requester_token_account.as_ref(),
+                requester_share,
+            )?;
+        }
+
+        let payouts = escrow.payouts();
+
+        let milestone = &mut ctx.accounts.milestone;
+        milestone.status = MilestoneStatus::Resolved;
+
+        let task = &mut ctx.accounts.task_request;
+        if task.settle_milestone(amount)? {
+            task.status = TaskStatus::Disputed;
+        }
+        ctx.accounts
+            .provider_profile
+            .record(task, payouts.provider)?;
+
+        msg!(
+            "Milestone {} dispute resolved: {} to provider, {} to requester",
+            milestone.index,
+            provider_share,
+            requester_share
+        );
+        emit_cpi!(MilestoneSettled::of(task, milestone, payouts));
+
+        Ok(())
+    }
+
+    /// Close a milestone of a settled task and return its rent to the requester.
+    pub fn close_milestone(ctx: Context<CloseMilestone>) -> Result<()> {
+        let task = &ctx.accounts.task_request;
+
+        require!(
+            task.requester == ctx.accounts.requester.key(),
+            AgentPayError::UnauthorizedRequester
+        );
+        require!(task.status.is_terminal(), AgentPayError::InvalidTaskStatus);
+
+        let task = &mut ctx.accounts.task_request;
+        task.milestones_open = task
+            .milestones_open
+            .checked_sub(1)
+            .ok_or(AgentPayError::MathOverflow)?;
+
+        let milestone = &ctx.accounts.milestone;
+        msg!(
+            "Milestone {} closed: rent returned to requester {}",
+            milestone.index,
+            task.requester
+        );
+        emit_cpi!(MilestoneClosed {
+            task: task.key(),
+            milestone: milestone.key(),
+            index: milestone.index,
+        });
+
+        Ok(())
+    }
+
+    /// Expire a task that has passed its deadline without a result submission.
+    /// Anyone can call this (permissionless crank). Funds return to the requester.
+    pub fn expire_task(ctx: Context<ExpireTask>) -> Result<()> {
+        let task = &ctx.accounts.task_request;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(
+            task.status.is_awaiting_result(),
+            AgentPayError::InvalidTaskStatus
+        );
+        require!(now > task.deadline, AgentPayError::DeadlineNotReached);
+        require!(
+            task.milestones_in_review == 0,
+            AgentPayError::MilestonesInReview
+        );
+
+        // Milestones already released stay with the provider
+        let amount = task.escrow_remaining()?;
+
+        // Refund escrow from task PDA back to requester
+        let mut escrow = Escrow::resolve(
+            task,
+            &ctx.accounts.mint,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        escrow.refund(
+            &ctx.accounts.requester.to_account_info(),
+            ctx.accounts.requester_token_account.as_ref(),
+            amount,
+        )?;
+
+        let payouts = escrow.payouts();
+
+        let task = &mut ctx.accounts.task_request;
+        task.status = TaskStatus::Expired;
+        task.record_on_profile(ctx.accounts.provider_profile.as_mut(), payouts.provider)?;
+
+        msg!(
+            "Task expired: {} lamports refunded to requester {}",
+            amount,
+            task.requester
+        );
+        emit_cpi!(TaskSettled::of(task, payouts)?);
+
+        Ok(())
+    }
+
+    /// Close a settled task and return its rent to the requester who paid it.
+    /// For token tasks the empty escrow token account is closed as well.
+    /// Its milestones and encrypted delivery must be closed first: they are keyed by the
+    /// task PDA, so they would otherwise outlive it and attach to a new task reusing the
+    /// same task id. For the same reason its review, if any, is closed along with it.
+    pub fn close_task(ctx: Context<CloseTask>) -> Result<()> {
+        let task = &ctx.accounts.task_request;
+
+        require!(
+            task.requester == ctx.accounts.requester.key(),
+            AgentPayError::UnauthorizedRequester
+        );
+        require!(task.status.is_terminal(), AgentPayError::InvalidTaskStatus);
+        require!(task.milestones_open == 0, AgentPayError::MilestonesNotClosed);
+        require!(!task.delivery_open, AgentPayError::DeliveryNotClosed);
+        require!(
+            !task.review_open || ctx.accounts.review.is_some(),
+            AgentPayError::ReviewRequired
+        );
+
+        if !task.is_native() {
+            let (Some(mint), Some(escrow), Some(token_program)) = (
+                &ctx.accounts.mint,
+                &ctx.accounts.escrow_token_account,
+                &ctx.accounts.token_program,
+            ) else {
+                return err!(AgentPayError::TokenAccountsRequired);
+            };
+            close_token_escrow(
+                task,
+                &TokenEscrow {
+                    mint,
+                    escrow,
+                    token_program,
+                },
+                &ctx.accounts.requester.to_account_info(),
+            )?;
+        }
+
+        msg!("Task closed: rent returned to requester {}", task.requester);
+        emit_cpi!(TaskClosed {
+            task: task.key(),
+            requester: task.requester,
+        });
+
+        Ok(())
+    }
+
+    /// Review a settled task: a score from 1 to 5 and the hash of the review text.
+    /// Only the task's requester can review, only once the task is completed or
+    /// disputed, and only once per task. The score is folded into the provider's
+    /// rolling average on their `ProviderProfile`.
+    pub fn submit_review(
+        ctx: Context<SubmitReview>,
+        score: u8,
+        review_hash: [u8; 32],
+    ) -> Result<()> {
+        let task = &ctx.accounts.task_request;
+
+        require!(
+            task.requester == ctx.accounts.requester.key(),
+            AgentPayError::UnauthorizedRequester
+        );
+        require!(
+            matches!(task.status, TaskStatus::Completed | TaskStatus::Disputed),
+            AgentPayError::InvalidTaskStatus
+        );
+        require!(
+            (1..=MAX_REVIEW_SCORE).contains(&score),
+            AgentPayError::InvalidReviewScore
+        );
+
+        ctx.accounts.task_request.review_open = true;
+
+        let task = &ctx.accounts.task_request;
+        let review = &mut ctx.accounts.review;
+        review.task = task.key();
+        review.reviewer = task.requester;
+        review.provider = task.provider;
+        review.service_listing = task.service_listing;
+        review.score = score;
+        review.review_hash = review_hash;
+        review.created_at = Clock::get()?.unix_timestamp;
+        review.bump = ctx.bumps.review;
+
+        ctx.accounts.provider_profile.record_review(score)?;
+
+        msg!(
+            "Task {} reviewed: score {} for provider {}",
+            review.task,
+            score,
+            review.provider
+        );
+        emit_cpi!(ReviewSubmitted {
+            task: review.task,
+            reviewer: review.reviewer,
+            provider: review.provider,
+            service_listing: review.service_listing,
+            score,
+            review_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Submit a result with ZK proof verification.
+    /// The provider proves knowledge of the result pre-image via a Groth16 proof.
+    /// The proof is bound to this task PDA and provider key, so it cannot be replayed
+    /// on another task with the same result hash. The verifying key picks the circuit:
+    /// task_verify_v2 for a single field element (`chunk_count` = 1), or
+    /// task_verify_chunked for a Merkle root over `chunk_count` chunks, which lets the
+    /// provider later reveal individual chunks with inclusion proofs.
+    /// proof_a: 64 bytes (G1, negated, big-endian)
+    /// proof_b: 128 bytes (G2, big-endian)
+    /// proof_c: 64 bytes (G1, big-endian)
+    /// result_hash: 32 bytes (Poseidon hash or Merkle root, used as public input)
+    pub fn submit_result_zk(
+        ctx: Context<SubmitResultZk>,
+        proof_a: [u8; 64],
+        proof_b: [u8; 128],
+        proof_c: [u8; 64],
+        result_hash: [u8; 32],
+        chunk_count: u32,
+    ) -> Result<()> {
+        let task = &mut ctx.accounts.task_request;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(
+            task.provider == ctx.accounts.provider.key(),
+            AgentPayError::UnauthorizedProvider
+        );
+        require!(
+            task.status.is_awaiting_result(),
+            AgentPayError::InvalidTaskStatus
+        );
+        require!(now <= task.deadline, AgentPayError::DeadlinePassed);
+        require!(task.milestone_count == 0, AgentPayError::TaskHasMilestones);
+
+        // Verify the Groth16 proof on-chain
+        // Public inputs: the result hash or root, bound to this task and provider
+        let vk = &ctx.accounts.verifying_key;
+        match vk.circuit {
+            ZkCircuit::TaskVerify => {
+                require!(chunk_count == 1, AgentPayError::InvalidChunkCount);
+                let public_inputs = zk::task_proof_inputs(result_hash, &task.key(), &task.provider);
+                zk::verify_task_proof(vk, &proof_a, &proof_b, &proof_c, &public_inputs)?;
+            }
+            ZkCircuit::TaskVerifyChunked => {
+                require!(
+                    (1..=MAX_RESULT_CHUNKS).contains(&chunk_count),
+                    AgentPayError::InvalidChunkCount
+                );
+                let public_inputs = zk::chunked_task_proof_inputs(
+                    result_hash,
+                    chunk_count,
+                    &task.key(),
+                    &task.provider,
+                );
+                zk::verify_chunked_task_proof(vk, &proof_a, &proof_b, &proof_c, &public_inputs)?;
+            }
+            _ => return err!(AgentPayError::InvalidVerifyingKey),
+        }
+
+        task.zk_vk_version = vk.version;
+        task.result_chunk_count = chunk_count;
+        task.result_hash = result_hash;
+        task.status = TaskStatus::Submitted;
+        task.submitted_at = now;
+        task.zk_verified = true;
+
+        msg!(
+            "ZK-verified result submitted for task by provider {}",
+            task.provider
+        );
+        emit_cpi!(ResultSubmitted::of(task, false));
+
+        Ok(())
+    }
+
+    /// Requester asks for the result to be delivered encrypted to `encryption_key`
+    /// (a BabyJubJub public key). If `expected_hash` is non-zero, the provider is paid
+    /// the moment a matching encrypted result is proven, with no review window.
+    pub fn request_encrypted_delivery(
+        ctx: Context<RequestEncryptedDelivery>,
+        encryption_key: [u8; 64],
+        expected_hash: [u8; 32],
+    ) -> Result<()> {
+        let task = &mut ctx.accounts.task_request;
+
+        require!(
+            task.requester == ctx.accounts.requester.key(),
+            AgentPayError::UnauthorizedRequester
+        );
+        require!(
+            task.status.is_awaiting_result(),
+            AgentPayError::InvalidTaskStatus
+        );
+        require!(task.milestone_count == 0, AgentPayError::TaskHasMilestones);
+        task.delivery_open = true;
+
+        let delivery = &mut ctx.accounts.delivery;
+        delivery.task = task.key();
+        delivery.encryption_key = encryption_key;
+        delivery.expected_hash = expected_hash;
+        delivery.ephemeral_key = [0u8; 64];
+        delivery.ciphertext = [0u8; 32];
+        delivery.delivered_at = 0;
+        delivery.bump = ctx.bumps.delivery;
+
+        msg!(
+            "Encrypted delivery requested for task by requester {}",
+            task.requester
+        );
+        emit_cpi!(EncryptedDeliveryRequested {
+            task: task.key(),
+            requester: task.requester,
+            encryption_key,
+            expected_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Provider posts the result encrypted to the requester's key, with a Groth16 proof
+    /// that the ciphertext decrypts to the pre-image of `result_hash`.
+    /// When the requester fixed the expected hash, escrow is released in the same
+    /// instruction (fair exchange); otherwise the task moves to `Submitted` for review.
+    pub fn submit_result_encrypted(
+        ctx: Context<SubmitResultEncrypted>,
+        proof_a: [u8; 64],
+        proof_b: [u8; 128],
+        proof_c: [u8; 64],
+        result_hash: [u8; 32],
+        ephemeral_key: [u8; 64],
+        ciphertext: [u8; 32],
+    ) -> Result<()> {
+        let task = &ctx.accounts.task_request;
+        let delivery = &ctx.accounts.delivery;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(
+            task.provider == ctx.accounts.provider.key(),
+            AgentPayError::UnauthorizedProvider
+        );
+        require!(
+            task.status.is_awaiting_result(),
+            AgentPayError::InvalidTaskStatus
+        );
+        require!(now <= task.deadline, AgentPayError::DeadlinePassed);
+        require!(task.milestone_count == 0, AgentPayError::TaskHasMilestones);
+        require!(
+            !delivery.has_expected_hash() || delivery.expected_hash == result_hash,
+            AgentPayError::DeliveryHashMismatch
+        );
+
+        // Verify the ciphertext decrypts to the result under the requester's key
+        let vk = &ctx.accounts.verifying_key;
+        let public_inputs = zk::delivery_proof_inputs(
+            result_hash,
+            ciphertext,
+            &delivery.encryption_key,
+            &ephemeral_key,
+            &task.key(),
+            &task.provider,
+        );
+        zk::verify_delivery_proof(vk, &proof_a, &proof_b, &proof_c, &public_inputs)?;
+
+        let fair_exchange = delivery.has_expected_hash();
+        let amount = task.amount_lamports;
+        let payouts = if fair_exchange {
+            let mut escrow = Escrow::resolve(
+                task,
+                &ctx.accounts.mint,
+                &ctx.accounts.escrow_token_account,
+                &ctx.accounts.token_program,
+            )?;
+            escrow.pay_provider(
+                &ctx.accounts.provider.to_account_info(),
+                ctx.accounts.provider_token_account.as_ref(),
+                &ctx.accounts.treasury.to_account_info(),
+                ctx.accounts.treasury_token_account.as_ref(),
+                amount,
+            )?;
+            escrow.payouts()
+        } else {
+            Payouts::default()
+        };
+
+        let delivery = &mut ctx.accounts.delivery;
+        delivery.ephemeral_key = ephemeral_key;
+        delivery.ciphertext = ciphertext;
+        delivery.delivered_at = now;
+
+        let task = &mut ctx.accounts.task_request;
+        task.result_hash = result_hash;
+        task.submitted_at = now;
+        task.zk_verified = true;
+        task.zk_vk_version = vk.version;
+
+        if fair_exchange {
+            task.status = TaskStatus::Completed;
+            task.count_listing_completion(ctx.accounts.service_listing.as_mut())?;
+            ctx.accounts
+                .provider_profile
+                .record(task, payouts.provider)?;
+
+            msg!(
+                "Encrypted result delivered: {} released to provider {}",
+                amount,
+                task.provider
+            );
+            emit_cpi!(ResultSubmitted::of(task, true));
+            emit_cpi!(TaskSettled::of(task, payouts)?);
+        } else {
+            task.status = TaskStatus::Submitted;
+
+            msg!(
+                "Encrypted result submitted for task by provider {}",
+                task.provider
+            );
+            emit_cpi!(ResultSubmitted::of(task, true));
+        }
+
+        Ok(())
+    }
+
+    /// Close the encrypted delivery of a settled task and return its rent to the requester.
+    /// Copy the ciphertext off-chain first; it is gone once the account is closed.
+    pub fn close_delivery(ctx: Context<CloseDelivery>) -> Result<()> {
+        let task = &ctx.accounts.task_request;
+
+        require!(
+            task.requester == ctx.accounts.requester.key(),
+            AgentPayError::UnauthorizedRequester
+        );
+        require!(task.status.is_terminal(), AgentPayError::InvalidTaskStatus);
+
+        let task = &mut ctx.accounts.task_request;
+        task.delivery_open = false;
+
+        msg!(
+            "Delivery closed: rent returned to requester {}",
+            task.requester
+        );
+        emit_cpi!(DeliveryClosed {
+            task: task.key(),
+            delivery: ctx.accounts.delivery.key(),
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account validation structs
+// ============================================================================
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Config::SIZE,
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    // This program's ProgramData, derived here because `#[event_cpi]` already
+    // brings the `program` account.
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ AgentPayError::UnauthorizedAdmin,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ AgentPayError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(circuit: ZkCircuit, version: u16)]
+pub struct InitVerifyingKey<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ AgentPayError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = VerifyingKeyAccount::SIZE,
+        seeds = [b"vk", circuit.seed().as_ref(), version.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub verifying_key: Account<'info, VerifyingKeyAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateVerifyingKey<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ AgentPayError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"vk", verifying_key.circuit.seed().as_ref(), verifying_key.version.to_le_bytes().as_ref()],
+        bump = verifying_key.bump,
+    )]
+    pub verifying_key: Account<'info, VerifyingKeyAccount>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TransferAdmin<'info> {
+    pub admin: Signer<'info>,
+
+    pub new_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ AgentPayError::UnauthorizedAdmin,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(service_id: [u8; 16])]
+pub struct RegisterService<'info> {
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    #[account(
+        init,
+        payer = provider,
+        space = ServiceListing::SIZE,
+        seeds = [b"service", provider.key().as_ref(), service_id.as_ref()],
+        bump,
+    )]
+    pub service_listing: Account<'info, ServiceListing>,
+
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = ProviderProfile::SIZE,
+        seeds = [b"provider", provider.key().as_ref()],
+        bump,
+    )]
+    pub provider_profile: Account<'info, ProviderProfile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Token the service is priced in. Omit for native SOL listings.
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DeactivateService<'info> {
+    pub provider: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"service", provider.key().as_ref(), service_listing.service_id.as_ref()],
+        bump = service_listing.bump,
+        has_one = provider @ AgentPayError::UnauthorizedServiceOwner,
+    )]
+    pub service_listing: Account<'info, ServiceListing>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateService<'info> {
+    pub provider: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"service", provider.key().as_ref(), service_listing.service_id.as_ref()],
+        bump = service_listing.bump,
+        has_one = provider @ AgentPayError::UnauthorizedServiceOwner,
+    )]
+    pub service_listing: Account<'info, ServiceListing>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(task_id: [u8; 16])]
+pub struct CreateTask<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    #[account(
+        seeds = [b"service", service_listing.provider.as_ref(), service_listing.service_id.as_ref()],
+        bump = service_listing.bump,
+    )]
+    pub service_listing: Account<'info, ServiceListing>,
+
+    #[account(
+        init,
+        payer = requester,
+        space = TaskRequest::SIZE,
+        seeds = [b"task", requester.key().as_ref(), task_id.as_ref()],
+        bump,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+
+    /// Profile of the listing's provider, created here if the provider has none yet
+    /// (listings registered before profiles existed).
+    #[account(
+        init_if_needed,
+        payer = requester,
+        space = ProviderProfile::SIZE,
+        seeds = [b"provider", service_listing.provider.as_ref()],
+        bump,
+    )]
+    pub provider_profile: Account<'info, ProviderProfile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    // Token escrow accounts, required only when the listing is token-denominated.
+    #[account(address = service_listing.mint @ AgentPayError::MintMismatch)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = requester,
+        token::token_program = token_program,
+    )]
+    pub requester_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = requester,
+        seeds = [b"escrow", task_request.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = task_request,
+        token::token_program = token_program,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(task_id: [u8; 16])]
+pub struct CreateBounty<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    #[account(
+        init,
+        payer = requester,
+        space = TaskRequest::SIZE,
+        seeds = [b"task", requester.key().as_ref(), task_id.as_ref()],
+        bump,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    // Token escrow accounts. Omit them all for a native SOL bounty.
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = requester,
+        token::token_program = token_program,
+    )]
+    pub requester_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = requester,
+        seeds = [b"escrow", task_request.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = task_request,
+        token::token_program = token_program,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimBounty<'info> {
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", task_request.requester.as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+
+    /// Profile of the claimant, created here if they have never listed a service
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = ProviderProfile::SIZE,
+        seeds = [b"provider", provider.key().as_ref()],
+        bump,
+    )]
+    pub provider_profile: Account<'info, ProviderProfile>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelBounty<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", requester.key().as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = requester @ AgentPayError::UnauthorizedRequester,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+
+    // Token escrow accounts, required only when the bounty is token-denominated.
+    #[account(address = task_request.mint @ AgentPayError::MintMismatch)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", task_request.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = task_request,
+        token::token_program = token_program,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = requester,
+        token::token_program = token_program,
+    )]
+    pub requester_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct StartTask<'info> {
+    pub provider: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", task_request.requester.as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = provider @ AgentPayError::UnauthorizedProvider,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelTask<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", requester.key().as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = requester @ AgentPayError::UnauthorizedRequester,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+
+    /// CHECK: The provider to receive any cancellation fee. Validated via task_request.provider.
+    #[account(
+        mut,
+        constraint = provider.key() == task_request.provider,
+    )]
+    pub provider: UncheckedAccount<'info>,
+
+    /// Required unless the task is a bounty nobody has claimed.
+    #[account(
+        mut,
+        seeds = [b"provider", task_request.provider.as_ref()],
+        bump = provider_profile.bump,
+    )]
+    pub provider_profile: Option<Account<'info, ProviderProfile>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Receives the protocol fee. Validated via config.treasury.
+    #[account(
+        mut,
+        address = config.treasury @ AgentPayError::InvalidTreasury,
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    // Token escrow accounts, required only when the task is token-denominated.
+    #[account(address = task_request.mint @ AgentPayError::MintMismatch)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", task_request.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = task_request,
+        token::token_program = token_program,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = requester,
+        token::token_program = token_program,
+    )]
+    pub requester_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = provider,
+        token::token_program = token_program,
+    )]
+    pub provider_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = treasury,
+        token::token_program = token_program,
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SubmitResult<'info> {
+    pub provider: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", task_request.requester.as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = provider @ AgentPayError::UnauthorizedProvider,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SubmitResultZk<'info> {
+    pub provider: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", task_request.requester.as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = provider @ AgentPayError::UnauthorizedProvider,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+
+    #[account(
+        seeds = [b"vk", verifying_key.circuit.seed().as_ref(), verifying_key.version.to_le_bytes().as_ref()],
+        bump = verifying_key.bump,
+    )]
+    pub verifying_key: Account<'info, VerifyingKeyAccount>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RequestEncryptedDelivery<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", requester.key().as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = requester @ AgentPayError::UnauthorizedRequester,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+
+    #[account(
+        init,
+        payer = requester,
+        space = EncryptedDelivery::SIZE,
+        seeds = [b"delivery", task_request.key().as_ref()],
+        bump,
+    )]
+    pub delivery: Account<'info, EncryptedDelivery>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SubmitResultEncrypted<'info> {
+    pub provider: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", task_request.requester.as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = provider @ AgentPayError::UnauthorizedProvider,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+
+    #[account(
+        mut,
+        seeds = [b"delivery", task_request.key().as_ref()],
+        bump = delivery.bump,
+    )]
+    pub delivery: Account<'info, EncryptedDelivery>,
+
+    #[account(
+        seeds = [b"vk", ZkCircuit::ResultDelivery.seed().as_ref(), verifying_key.version.to_le_bytes().as_ref()],
+        bump = verifying_key.bump,
+    )]
+    pub verifying_key: Account<'info, VerifyingKeyAccount>,
+
+    /// The task's listing, whose completed counter a completion bumps. Omit for bounties.
+    #[account(
+        mut,
+        address = task_request.service_listing,
+    )]
+    pub service_listing: Option<Account<'info, ServiceListing>>,
+
+    #[account(
+        mut,
+        seeds = [b"provider", task_request.provider.as_ref()],
+        bump = provider_profile.bump,
+    )]
+    pub provider_profile: Account<'info, ProviderProfile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Receives the protocol fee. Validated via config.treasury.
+    #[account(
+        mut,
+        address = config.treasury @ AgentPayError::InvalidTreasury,
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    // Token escrow accounts, required only when the task is token-denominated.
+    #[account(address = task_request.mint @ AgentPayError::MintMismatch)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", task_request.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = task_request,
+        token::token_program = token_program,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = provider,
+        token::token_program = token_program,
+    )]
+    pub provider_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = treasury,
+        token::token_program = token_program,
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseDelivery<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", requester.key().as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = requester @ AgentPayError::UnauthorizedRequester,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+
+    #[account(
+        mut,
+        close = requester,
+        seeds = [b"delivery", task_request.key().as_ref()],
+        bump = delivery.bump,
+    )]
+    pub delivery: Account<'info, EncryptedDelivery>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptResult<'info> {
+    pub requester: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", requester.key().as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = requester @ AgentPayError::UnauthorizedRequester,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+
+    /// CHECK: The provider wallet to receive payment. Validated via task_request.provider.
+    #[account(
+        mut,
+        constraint = provider.key() == task_request.provider,
+    )]
+    pub provider: UncheckedAccount<'info>,
+
+    /// The task's listing, whose completed counter a completion bumps. Omit for bounties.
+    #[account(
+        mut,
+        seeds = [b"service", task_request.provider.as_ref(), service_listing.service_id.as_ref()],
+        bump = service_listing.bump,
+    )]
+    pub service_listing: Option<Account<'info, ServiceListing>>,
+
+    #[account(
+        mut,
+        seeds = [b"provider", task_request.provider.as_ref()],
+        bump = provider_profile.bump,
+    )]
+    pub provider_profile: Account<'info, ProviderProfile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Receives the protocol fee. Validated via config.treasury.
+    #[account(
+        mut,
+        address = config.treasury @ AgentPayError::InvalidTreasury,
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    // Token escrow accounts, required only when the task is token-denominated.
+    #[account(address = task_request.mint @ AgentPayError::MintMismatch)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", task_request.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = task_request,
+        token::token_program = token_program,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = provider,
+        token::token_program = token_program,
+    )]
+    pub provider_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = treasury,
+        token::token_program = token_program,
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DisputeTask<'info> {
+    pub requester: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", requester.key().as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = requester @ AgentPayError::UnauthorizedRequester,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AutoAccept<'info> {
+    #[account(
+        mut,
+        seeds = [b"task", task_request.requester.as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+
+    /// CHECK: The provider wallet to receive payment. Validated via task_request.provider.
+    #[account(
+        mut,
+        constraint = provider.key() == task_request.provider,
+    )]
+    pub provider: UncheckedAccount<'info>,
+
+    /// The task's listing, whose completed counter a completion bumps. Omit for bounties.
+    #[account(
+        mut,
+        address = task_request.service_listing,
+    )]
+    pub service_listing: Option<Account<'info, ServiceListing>>,
+
+    #[account(
+        mut,
+        seeds = [b"provider", task_request.provider.as_ref()],
+        bump = provider_profile.bump,
+    )]
+    pub provider_profile: Account<'info, ProviderProfile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Receives the protocol fee. Validated via config.treasury.
+    #[account(
+        mut,
+        address = config.treasury @ AgentPayError::InvalidTreasury,
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    // Token escrow accounts, required only when the task is token-denominated.
+    #[account(address = task_request.mint @ AgentPayError::MintMismatch)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", task_request.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = task_request,
+        token::token_program = token_program,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = provider,
+        token::token_program = token_program,
+    )]
+    pub provider_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = treasury,
+        token::token_program = token_program,
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    pub arbiter: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", task_request.requester.as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = arbiter @ AgentPayError::UnauthorizedArbiter,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+
+    /// CHECK: The requester to receive their share. Validated via task_request.requester.
+    #[account(
+        mut,
+        constraint = requester.key() == task_request.requester,
+    )]
+    pub requester: UncheckedAccount<'info>,
+
+    /// CHECK: The provider to receive their share. Validated via task_request.provider.
+    #[account(
+        mut,
+        constraint = provider.key() == task_request.provider,
+    )]
+    pub provider: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"provider", task_request.provider.as_ref()],
+        bump = provider_profile.bump,
+    )]
+    pub provider_profile: Account<'info, ProviderProfile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Receives the protocol fee. Validated via config.treasury.
+    #[account(
+        mut,
+        address = config.treasury @ AgentPayError::InvalidTreasury,
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    // Token escrow accounts, required only when the task is token-denominated.
+    #[account(address = task_request.mint @ AgentPayError::MintMismatch)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", task_request.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = task_request,
+        token::token_program = token_program,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = requester,
+        token::token_program = token_program,
+    )]
+    pub requester_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = provider,
+        token::token_program = token_program,
+    )]
+    pub provider_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = treasury,
+        token::token_program = token_program,
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExpireDispute<'info> {
+    /// Requester or provider of the task.
+    pub party: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", task_request.requester.as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+
+    /// CHECK: The requester to receive the refund. Validated via task_request.requester.
+    #[account(
+        mut,
+        constraint = requester.key() == task_request.requester,
+    )]
+    pub requester: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"provider", task_request.provider.as_ref()],
+        bump = provider_profile.bump,
+    )]
+    pub provider_profile: Account<'info, ProviderProfile>,
+
+    // Token escrow accounts, required only when the task is token-denominated.
+    #[account(address = task_request.mint @ AgentPayError::MintMismatch)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", task_request.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = task_request,
+        token::token_program = token_program,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = requester,
+        token::token_program = token_program,
+    )]
+    pub requester_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
 
+#[event_cpi]
 #[derive(Accounts)]
-#[instruction(service_id: [u8; 16])]
-pub struct RegisterService<'info> {
+#[instruction(index: u8)]
+pub struct AddMilestone<'info> {
     #[account(mut)]
-    pub provider: Signer<'info>,
+    pub requester: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", requester.key().as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = requester @ AgentPayError::UnauthorizedRequester,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
 
     #[account(
         init,
-        payer = provider,
-        space = ServiceListing::SIZE,
-        seeds = [b"service", provider.key().as_ref(), service_id.as_ref()],
+        payer = requester,
+        space = Milestone::SIZE,
+        seeds = [b"milestone", task_request.key().as_ref(), &[index]],
         bump,
     )]
-    pub service_listing: Account<'info, ServiceListing>,
+    pub milestone: Account<'info, Milestone>,
 
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct DeactivateService<'info> {
+pub struct SubmitMilestone<'info> {
     pub provider: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [b"service", provider.key().as_ref(), service_listing.service_id.as_ref()],
-        bump = service_listing.bump,
-        has_one = provider @ AgentPayError::UnauthorizedServiceOwner,
+        seeds = [b"task", task_request.requester.as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = provider @ AgentPayError::UnauthorizedProvider,
     )]
-    pub service_listing: Account<'info, ServiceListing>,
+    pub task_request: Account<'info, TaskRequest>,
+
+    #[account(
+        mut,
+        seeds = [b"milestone", task_request.key().as_ref(), &[milestone.index]],
+        bump = milestone.bump,
+    )]
+    pub milestone: Account<'info, Milestone>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-#[instruction(task_id: [u8; 16])]
-pub struct CreateTask<'info> {
-    #[account(mut)]
+pub struct AcceptMilestone<'info> {
     pub requester: Signer<'info>,
 
     #[account(
-        seeds = [b"service", service_listing.provider.as_ref(), service_listing.service_id.as_ref()],
-        bump = service_listing.bump,
+        mut,
+        seeds = [b"task", requester.key().as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = requester @ AgentPayError::UnauthorizedRequester,
     )]
-    pub service_listing: Account<'info, ServiceListing>,
+    pub task_request: Account<'info, TaskRequest>,
 
     #[account(
-        init,
-        payer = requester,
-        space = TaskRequest::SIZE,
-        seeds = [b"task", requester.key().as_ref(), task_id.as_ref()],
+        mut,
+        seeds = [b"milestone", task_request.key().as_ref(), &[milestone.index]],
+        bump = milestone.bump,
+    )]
+    pub milestone: Account<'info, Milestone>,
+
+    /// CHECK: The provider wallet to receive payment. Validated via task_request.provider.
+    #[account(
+        mut,
+        constraint = provider.key() == task_request.provider,
+    )]
+    pub provider: UncheckedAccount<'info>,
+
+    /// The task's listing, whose completed counter a completion bumps. Omit for bounties.
+    #[account(
+        mut,
+        address = task_request.service_listing,
+    )]
+    pub service_listing: Option<Account<'info, ServiceListing>>,
+
+    #[account(
+        mut,
+        seeds = [b"provider", task_request.provider.as_ref()],
+        bump = provider_profile.bump,
+    )]
+    pub provider_profile: Account<'info, ProviderProfile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Receives the protocol fee. Validated via config.treasury.
+    #[account(
+        mut,
+        address = config.treasury @ AgentPayError::InvalidTreasury,
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    // Token escrow accounts, required only when the task is token-denominated.
+    #[account(address = task_request.mint @ AgentPayError::MintMismatch)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", task_request.key().as_ref()],
         bump,
+        token::mint = mint,
+        token::authority = task_request,
+        token::token_program = token_program,
     )]
-    pub task_request: Account<'info, TaskRequest>,
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
 
-    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = provider,
+        token::token_program = token_program,
+    )]
+    pub provider_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = treasury,
+        token::token_program = token_program,
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct SubmitResult<'info> {
-    pub provider: Signer<'info>,
-
+pub struct AutoAcceptMilestone<'info> {
     #[account(
         mut,
         seeds = [b"task", task_request.requester.as_ref(), task_request.task_id.as_ref()],
         bump = task_request.bump,
-        has_one = provider @ AgentPayError::UnauthorizedProvider,
     )]
     pub task_request: Account<'info, TaskRequest>,
+
+    #[account(
+        mut,
+        seeds = [b"milestone", task_request.key().as_ref(), &[milestone.index]],
+        bump = milestone.bump,
+    )]
+    pub milestone: Account<'info, Milestone>,
+
+    /// CHECK: The provider wallet to receive payment. Validated via task_request.provider.
+    #[account(
+        mut,
+        constraint = provider.key() == task_request.provider,
+    )]
+    pub provider: UncheckedAccount<'info>,
+
+    /// The task's listing, whose completed counter a completion bumps. Omit for bounties.
+    #[account(
+        mut,
+        address = task_request.service_listing,
+    )]
+    pub service_listing: Option<Account<'info, ServiceListing>>,
+
+    #[account(
+        mut,
+        seeds = [b"provider", task_request.provider.as_ref()],
+        bump = provider_profile.bump,
+    )]
+    pub provider_profile: Account<'info, ProviderProfile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Receives the protocol fee. Validated via config.treasury.
+    #[account(
+        mut,
+        address = config.treasury @ AgentPayError::InvalidTreasury,
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    // Token escrow accounts, required only when the task is token-denominated.
+    #[account(address = task_request.mint @ AgentPayError::MintMismatch)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", task_request.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = task_request,
+        token::token_program = token_program,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = provider,
+        token::token_program = token_program,
+    )]
+    pub provider_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = treasury,
+        token::token_program = token_program,
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct AcceptResult<'info> {
+pub struct DisputeMilestone<'info> {
     pub requester: Signer<'info>,
 
     #[account(
-        mut,
         seeds = [b"task", requester.key().as_ref(), task_request.task_id.as_ref()],
         bump = task_request.bump,
         has_one = requester @ AgentPayError::UnauthorizedRequester,
     )]
     pub task_request: Account<'info, TaskRequest>,
 
-    /// CHECK: The provider wallet to receive payment. Validated via task_request.provider.
+    #[account(
+        mut,
+        seeds = [b"milestone", task_request.key().as_ref(), &[milestone.index]],
+        bump = milestone.bump,
+    )]
+    pub milestone: Account<'info, Milestone>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ResolveMilestoneDispute<'info> {
+    pub arbiter: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", task_request.requester.as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = arbiter @ AgentPayError::UnauthorizedArbiter,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+
+    #[account(
+        mut,
+        seeds = [b"milestone", task_request.key().as_ref(), &[milestone.index]],
+        bump = milestone.bump,
+    )]
+    pub milestone: Account<'info, Milestone>,
+
+    /// CHECK: The requester to receive their share. Validated via task_request.requester.
+    #[account(
+        mut,
+        constraint = requester.key() == task_request.requester,
+    )]
+    pub requester: UncheckedAccount<'info>,
+
+    /// CHECK: The provider to receive their share. Validated via task_request.provider.
     #[account(
         mut,
         constraint = provider.key() == task_request.provider,
@@ -390,14 +3014,65 @@ pub struct AcceptResult<'info> {
 
     #[account(
         mut,
-        seeds = [b"service", task_request.provider.as_ref(), service_listing.service_id.as_ref()],
-        bump = service_listing.bump,
+        seeds = [b"provider", task_request.provider.as_ref()],
+        bump = provider_profile.bump,
     )]
-    pub service_listing: Account<'info, ServiceListing>,
+    pub provider_profile: Account<'info, ProviderProfile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Receives the protocol fee. Validated via config.treasury.
+    #[account(
+        mut,
+        address = config.treasury @ AgentPayError::InvalidTreasury,
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    // Token escrow accounts, required only when the task is token-denominated.
+    #[account(address = task_request.mint @ AgentPayError::MintMismatch)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", task_request.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = task_request,
+        token::token_program = token_program,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = requester,
+        token::token_program = token_program,
+    )]
+    pub requester_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = provider,
+        token::token_program = token_program,
+    )]
+    pub provider_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = treasury,
+        token::token_program = token_program,
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct DisputeTask<'info> {
+pub struct CloseMilestone<'info> {
     #[account(mut)]
     pub requester: Signer<'info>,
 
@@ -408,8 +3083,17 @@ pub struct DisputeTask<'info> {
         has_one = requester @ AgentPayError::UnauthorizedRequester,
     )]
     pub task_request: Account<'info, TaskRequest>,
+
+    #[account(
+        mut,
+        close = requester,
+        seeds = [b"milestone", task_request.key().as_ref(), &[milestone.index]],
+        bump = milestone.bump,
+    )]
+    pub milestone: Account<'info, Milestone>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ExpireTask<'info> {
     /// CHECK: The requester to receive the refund. Validated via task_request.requester.
@@ -425,15 +3109,113 @@ pub struct ExpireTask<'info> {
         bump = task_request.bump,
     )]
     pub task_request: Account<'info, TaskRequest>,
+
+    /// Required unless the task is a bounty nobody has claimed.
+    #[account(
+        mut,
+        seeds = [b"provider", task_request.provider.as_ref()],
+        bump = provider_profile.bump,
+    )]
+    pub provider_profile: Option<Account<'info, ProviderProfile>>,
+
+    // Token escrow accounts, required only when the task is token-denominated.
+    #[account(address = task_request.mint @ AgentPayError::MintMismatch)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", task_request.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = task_request,
+        token::token_program = token_program,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = requester,
+        token::token_program = token_program,
+    )]
+    pub requester_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct VerifyReputation<'info> {
-    pub signer: Signer<'info>,
+pub struct CloseTask<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
 
     #[account(
-        seeds = [b"service", service_listing.provider.as_ref(), service_listing.service_id.as_ref()],
-        bump = service_listing.bump,
+        mut,
+        close = requester,
+        seeds = [b"task", requester.key().as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = requester @ AgentPayError::UnauthorizedRequester,
     )]
-    pub service_listing: Account<'info, ServiceListing>,
+    pub task_request: Account<'info, TaskRequest>,
+
+    /// Required once the task has been reviewed.
+    #[account(
+        mut,
+        close = requester,
+        seeds = [b"review", task_request.key().as_ref()],
+        bump = review.bump,
+    )]
+    pub review: Option<Account<'info, Review>>,
+
+    // Token escrow accounts, required only when the task is token-denominated.
+    // The mint is writable so Token-2022 transfer fees withheld on the escrow can be
+    // harvested to it before the escrow is closed.
+    #[account(mut, address = task_request.mint @ AgentPayError::MintMismatch)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", task_request.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = task_request,
+        token::token_program = token_program,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SubmitReview<'info> {
+    #[account(mut)]
+    pub requester: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"task", requester.key().as_ref(), task_request.task_id.as_ref()],
+        bump = task_request.bump,
+        has_one = requester @ AgentPayError::UnauthorizedRequester,
+    )]
+    pub task_request: Account<'info, TaskRequest>,
+
+    #[account(
+        mut,
+        seeds = [b"provider", task_request.provider.as_ref()],
+        bump = provider_profile.bump,
+    )]
+    pub provider_profile: Account<'info, ProviderProfile>,
+
+    // `init` rather than `init_if_needed`: a second review of the same task fails here
+    #[account(
+        init,
+        payer = requester,
+        space = Review::SIZE,
+        seeds = [b"review", task_request.key().as_ref()],
+        bump,
+    )]
+    pub review: Account<'info, Review>,
+
+    pub system_program: Program<'info, System>,
 }
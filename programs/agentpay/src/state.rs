@@ -1,5 +1,49 @@
 use anchor_lang::prelude::*;
 
+use crate::errors::AgentPayError;
+
+/// Denominator for basis-point splits (10_000 bps = 100%)
+pub const BASIS_POINTS: u64 = 10_000;
+
+/// Upper bound on the protocol fee the admin can set (10%)
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 1_000;
+
+/// Highest score a review can give; the lowest is 1
+pub const MAX_REVIEW_SCORE: u8 = 5;
+
+/// Longest review window a listing or bounty can give its requester (30 days)
+pub const MAX_REVIEW_WINDOW: i64 = 30 * 24 * 3600;
+
+/// Longest a bounty claim can hold without a result before it lapses (30 days)
+pub const MAX_CLAIM_TIMEOUT: i64 = 30 * 24 * 3600;
+
+/// How long the arbiter has to rule on a dispute before either party can expire it (14 days)
+pub const ARBITRATION_WINDOW: i64 = 14 * 24 * 3600;
+
+/// Global protocol settings, a singleton PDA at `["config"]`.
+#[account]
+pub struct Config {
+    /// Key allowed to change the protocol settings
+    pub admin: Pubkey,
+    /// Account that receives protocol fees
+    pub treasury: Pubkey,
+    /// Share of every provider payout taken as protocol fee (bps)
+    pub fee_bps: u16,
+    /// PDA bump seed
+    pub bump: u8,
+    /// Emergency switch: blocks new listings and new escrow while set
+    pub paused: bool,
+}
+
+impl Config {
+    pub const SIZE: usize = 8  // discriminator
+        + 32   // admin
+        + 32   // treasury
+        + 2    // fee_bps
+        + 1    // bump
+        + 1;   // paused
+}
+
 #[account]
 pub struct ServiceListing {
     /// The agent's wallet that provides this service
@@ -8,7 +52,7 @@ pub struct ServiceListing {
     pub service_id: [u8; 16],
     /// Human-readable description of the service
     pub description: [u8; 128],
-    /// Cost per task in lamports
+    /// Cost per task in lamports, or in base units of `mint` for token listings
     pub price_lamports: u64,
     /// Whether this service is currently accepting work
     pub is_active: bool,
@@ -16,10 +60,19 @@ pub struct ServiceListing {
     pub tasks_completed: u64,
     /// Timestamp of creation
     pub created_at: i64,
-    /// Minimum reputation score required (0 = no minimum)
+    /// Tasks the provider's profile must show completed before a task can be created
+    /// (0 = no minimum)
     pub min_reputation: u64,
     /// PDA bump seed
     pub bump: u8,
+    /// SPL / Token-2022 mint the price is denominated in (default = native SOL)
+    pub mint: Pubkey,
+    /// Account that rules on disputes for tasks created against this listing
+    pub arbiter: Pubkey,
+    /// Seconds the requester has to accept or dispute a submitted result
+    pub review_window: i64,
+    /// Share of escrow paid to the provider if the requester cancels after work started (bps)
+    pub cancellation_fee_bps: u16,
 }
 
 impl ServiceListing {
@@ -32,22 +85,166 @@ impl ServiceListing {
         + 8    // tasks_completed
         + 8    // created_at
         + 8    // min_reputation
+        + 1    // bump
+        + 32   // mint
+        + 32   // arbiter
+        + 8    // review_window
+        + 2;   // cancellation_fee_bps
+}
+
+/// Reputation of a provider across all of their listings, a PDA at `["provider", provider]`.
+/// Created with the provider's first listing (or first task) and updated by every
+/// settlement, so it survives new listings and cannot be reset by registering a fresh
+/// `service_id`.
+#[account]
+pub struct ProviderProfile {
+    /// The provider's wallet
+    pub provider: Pubkey,
+    /// Tasks completed by acceptance, auto-accept or fair-exchange delivery
+    pub tasks_completed: u64,
+    /// Tasks settled by an arbiter ruling
+    pub tasks_disputed: u64,
+    /// Tasks that passed their deadline without a result
+    pub tasks_expired: u64,
+    /// Lamports paid out to the provider from native SOL escrow, net of protocol fees.
+    /// Token payouts are not included; amounts in different mints don't add up.
+    pub total_earned: u64,
+    /// Timestamp the profile was created
+    pub created_at: i64,
+    /// PDA bump seed
+    pub bump: u8,
+    /// Reviews requesters have left on the provider's settled tasks
+    pub review_count: u64,
+    /// Sum of those reviews' scores; the average is `review_score_total / review_count`
+    pub review_score_total: u64,
+}
+
+impl ProviderProfile {
+    pub const SIZE: usize = 8  // discriminator
+        + 32   // provider
+        + 8    // tasks_completed
+        + 8    // tasks_disputed
+        + 8    // tasks_expired
+        + 8    // total_earned
+        + 8    // created_at
+        + 1    // bump
+        + 8    // review_count
+        + 8;   // review_score_total
+
+    /// Set up a profile the first time it is created; a no-op for an existing one.
+    pub fn init_if_new(&mut self, provider: Pubkey, bump: u8) -> Result<()> {
+        if self.provider == Pubkey::default() {
+            self.provider = provider;
+            self.created_at = Clock::get()?.unix_timestamp;
+            self.bump = bump;
+        }
+        Ok(())
+    }
+
+    /// Book one settlement of `task`, called after its status has been updated:
+    /// `provider_payout` paid to the provider, and the outcome once the task is settled.
+    pub fn record(&mut self, task: &TaskRequest, provider_payout: u64) -> Result<()> {
+        if task.is_native() {
+            self.total_earned = self
+                .total_earned
+                .checked_add(provider_payout)
+                .ok_or(AgentPayError::MathOverflow)?;
+        }
+        let counter = match task.status {
+            TaskStatus::Completed => &mut self.tasks_completed,
+            TaskStatus::Disputed => &mut self.tasks_disputed,
+            TaskStatus::Expired => &mut self.tasks_expired,
+            _ => return Ok(()),
+        };
+        *counter = counter.checked_add(1).ok_or(AgentPayError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Fold one review `score` into the rolling average.
+    pub fn record_review(&mut self, score: u8) -> Result<()> {
+        self.review_count = self
+            .review_count
+            .checked_add(1)
+            .ok_or(AgentPayError::MathOverflow)?;
+        self.review_score_total = self
+            .review_score_total
+            .checked_add(u64::from(score))
+            .ok_or(AgentPayError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// A requester's rating of one of their settled tasks, a PDA at `["review", task]`,
+/// so each task can be reviewed at most once. It is closed with the task.
+#[account]
+pub struct Review {
+    /// The reviewed task
+    pub task: Pubkey,
+    /// The task's requester, the only key allowed to review it
+    pub reviewer: Pubkey,
+    /// The provider being reviewed
+    pub provider: Pubkey,
+    /// Listing the task was created from
+    pub service_listing: Pubkey,
+    /// Score from 1 to `MAX_REVIEW_SCORE`
+    pub score: u8,
+    /// Hash of the review text, stored off-chain
+    pub review_hash: [u8; 32],
+    /// Timestamp the review was submitted
+    pub created_at: i64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl Review {
+    pub const SIZE: usize = 8  // discriminator
+        + 32   // task
+        + 32   // reviewer
+        + 32   // provider
+        + 32   // service_listing
+        + 1    // score
+        + 32   // review_hash
+        + 8    // created_at
         + 1;   // bump
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum TaskStatus {
-    /// Task created, escrow locked, waiting for provider to do work
+    /// Task created, escrow locked, waiting for provider to pick it up
     Open = 0,
     /// Provider submitted result, waiting for requester to accept
     Submitted = 1,
     /// Requester accepted, escrow released to provider
     Completed = 2,
-    /// Requester disputed, escrow returned to requester
+    /// Arbiter resolved the dispute, escrow split between requester and provider
     Disputed = 3,
     /// Deadline passed with no result, escrow returned to requester
     Expired = 4,
+    /// Requester disputed the result, escrow locked until the arbiter rules
+    DisputePending = 5,
+    /// Provider acknowledged the task and is working on it
+    InProgress = 6,
+    /// Requester cancelled before a result was submitted
+    Cancelled = 7,
+}
+
+impl TaskStatus {
+    /// Whether escrow has been fully paid out and the task can no longer change.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            TaskStatus::Completed
+                | TaskStatus::Disputed
+                | TaskStatus::Expired
+                | TaskStatus::Cancelled
+        )
+    }
+
+    /// Whether the provider can still submit a result.
+    pub fn is_awaiting_result(&self) -> bool {
+        matches!(self, TaskStatus::Open | TaskStatus::InProgress)
+    }
 }
 
 #[account]
@@ -62,7 +259,7 @@ pub struct TaskRequest {
     pub task_id: [u8; 16],
     /// Description of what needs to be done
     pub description: [u8; 256],
-    /// Payment amount locked in escrow (lamports)
+    /// Payment amount locked in escrow (lamports, or base units of `mint`)
     pub amount_lamports: u64,
     /// Current status of the task
     pub status: TaskStatus,
@@ -76,6 +273,46 @@ pub struct TaskRequest {
     pub zk_verified: bool,
     /// PDA bump seed
     pub bump: u8,
+    /// Mint of the escrowed tokens (default = native SOL held by this PDA)
+    pub mint: Pubkey,
+    /// Arbiter copied from the listing at creation; rules on disputes
+    pub arbiter: Pubkey,
+    /// Timestamp the requester opened a dispute (0 = never disputed)
+    pub disputed_at: i64,
+    /// Review window copied from the listing at creation (seconds)
+    pub review_window: i64,
+    /// Timestamp the provider submitted a result (0 = not submitted)
+    pub submitted_at: i64,
+    /// Cancellation fee copied from the listing at creation (bps)
+    pub cancellation_fee_bps: u16,
+    /// Number of milestones the escrow is split into (0 = single release)
+    pub milestone_count: u8,
+    /// Number of milestones released or resolved
+    pub milestones_settled: u8,
+    /// Number of milestones submitted or disputed and awaiting a decision
+    pub milestones_in_review: u8,
+    /// Sum of all milestone amounts
+    pub milestone_allocated: u64,
+    /// Escrow already paid out through settled milestones
+    pub released_amount: u64,
+    /// Protocol fee copied from the config at creation (bps)
+    pub protocol_fee_bps: u16,
+    /// Version of the task_verify key the ZK result was checked against (valid if zk_verified)
+    pub zk_vk_version: u16,
+    /// Number of chunks committed under `result_hash` (1 = single field element)
+    pub result_chunk_count: u32,
+    /// Bounty only: tasks a provider must have completed to claim it
+    pub min_reputation: u64,
+    /// Bounty only: how long a claim holds without a result before the bounty reopens (seconds)
+    pub claim_timeout: i64,
+    /// Bounty only: timestamp of the current claim (0 = unclaimed)
+    pub claimed_at: i64,
+    /// Milestone accounts added and not yet closed; the task cannot be closed until 0
+    pub milestones_open: u8,
+    /// Whether an `EncryptedDelivery` exists for this task; the task cannot be closed until false
+    pub delivery_open: bool,
+    /// Whether a `Review` exists for this task; `close_task` must close it along with the task
+    pub review_open: bool,
 }
 
 impl TaskRequest {
@@ -91,5 +328,302 @@ impl TaskRequest {
         + 8    // deadline
         + 8    // created_at
         + 1    // zk_verified
+        + 1    // bump
+        + 32   // mint
+        + 32   // arbiter
+        + 8    // disputed_at
+        + 8    // review_window
+        + 8    // submitted_at
+        + 2    // cancellation_fee_bps
+        + 1    // milestone_count
+        + 1    // milestones_settled
+        + 1    // milestones_in_review
+        + 8    // milestone_allocated
+        + 8    // released_amount
+        + 2    // protocol_fee_bps
+        + 2    // zk_vk_version
+        + 4    // result_chunk_count
+        + 8    // min_reputation
+        + 8    // claim_timeout
+        + 8    // claimed_at
+        + 1    // milestones_open
+        + 1    // delivery_open
+        + 1;   // review_open
+
+    /// Whether the escrow is native SOL held directly by this PDA.
+    pub fn is_native(&self) -> bool {
+        self.mint == Pubkey::default()
+    }
+
+    /// Whether this is an open bounty, created without a listing and claimed by its provider.
+    pub fn is_bounty(&self) -> bool {
+        self.service_listing == Pubkey::default()
+    }
+
+    /// Timestamp after which a bounty claim with no result lapses and the bounty
+    /// can be claimed again.
+    pub fn claim_expires_at(&self) -> Result<i64> {
+        self.claimed_at
+            .checked_add(self.claim_timeout)
+            .ok_or_else(|| error!(AgentPayError::MathOverflow))
+    }
+
+    /// Whether the task has a provider: every listing task does, a bounty once claimed.
+    pub fn has_provider(&self) -> bool {
+        !self.is_bounty() || self.provider != Pubkey::default()
+    }
+
+    /// Book this settlement on the provider's profile, after the status has been updated.
+    /// Only a task without a provider, an unclaimed bounty, settles without one.
+    pub fn record_on_profile(
+        &self,
+        profile: Option<&mut Account<'_, ProviderProfile>>,
+        provider_payout: u64,
+    ) -> Result<()> {
+        let Some(profile) = profile else {
+            require!(!self.has_provider(), AgentPayError::ProviderProfileRequired);
+            return Ok(());
+        };
+        profile.record(self, provider_payout)
+    }
+
+    /// Count a completion on the task's listing. Bounties have none; every other
+    /// task must pass its own, so the listing's counter cannot be skipped.
+    pub fn count_listing_completion(
+        &self,
+        listing: Option<&mut Account<'_, ServiceListing>>,
+    ) -> Result<()> {
+        let Some(listing) = listing else {
+            require!(self.is_bounty(), AgentPayError::ServiceListingMismatch);
+            return Ok(());
+        };
+        require_keys_eq!(
+            listing.key(),
+            self.service_listing,
+            AgentPayError::ServiceListingMismatch
+        );
+        listing.tasks_completed = listing
+            .tasks_completed
+            .checked_add(1)
+            .ok_or(AgentPayError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Timestamp after which a submitted result can be auto-accepted.
+    pub fn review_deadline(&self) -> Result<i64> {
+        self.submitted_at
+            .checked_add(self.review_window)
+            .ok_or_else(|| error!(AgentPayError::MathOverflow))
+    }
+
+    /// Timestamp after which an unresolved dispute can be expired with a refund.
+    pub fn arbitration_deadline(&self) -> Result<i64> {
+        self.disputed_at
+            .checked_add(ARBITRATION_WINDOW)
+            .ok_or_else(|| error!(AgentPayError::MathOverflow))
+    }
+
+    /// Escrow not yet paid out through milestones.
+    pub fn escrow_remaining(&self) -> Result<u64> {
+        self.amount_lamports
+            .checked_sub(self.released_amount)
+            .ok_or_else(|| error!(AgentPayError::MathOverflow))
+    }
+
+    /// Whether work can begin: either no milestones, or milestones covering the full escrow.
+    pub fn milestones_funded(&self) -> bool {
+        self.milestone_count == 0 || self.milestone_allocated == self.amount_lamports
+    }
+
+    /// Book a milestone that was released or resolved.
+    /// Returns true once every milestone of the task has been settled.
+    pub fn settle_milestone(&mut self, amount: u64) -> Result<bool> {
+        self.released_amount = self
+            .released_amount
+            .checked_add(amount)
+            .ok_or(AgentPayError::MathOverflow)?;
+        self.milestones_settled = self
+            .milestones_settled
+            .checked_add(1)
+            .ok_or(AgentPayError::MathOverflow)?;
+        self.milestones_in_review = self
+            .milestones_in_review
+            .checked_sub(1)
+            .ok_or(AgentPayError::MathOverflow)?;
+        Ok(self.milestones_settled == self.milestone_count)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MilestoneStatus {
+    /// Waiting for the provider to deliver this milestone
+    Pending = 0,
+    /// Provider submitted a result, waiting for requester to accept
+    Submitted = 1,
+    /// Requester accepted, milestone amount released to provider
+    Released = 2,
+    /// Requester disputed the result, amount locked until the arbiter rules
+    DisputePending = 3,
+    /// Arbiter split the milestone amount between requester and provider
+    Resolved = 4,
+}
+
+#[account]
+pub struct Milestone {
+    /// The task this milestone belongs to
+    pub task: Pubkey,
+    /// Position of the milestone within the task (0-based)
+    pub index: u8,
+    /// Portion of the task escrow released by this milestone
+    pub amount: u64,
+    /// Description of the deliverable
+    pub description: [u8; 128],
+    /// Current status of the milestone
+    pub status: MilestoneStatus,
+    /// SHA256 hash of the delivered result (set by provider)
+    pub result_hash: [u8; 32],
+    /// Timestamp the provider submitted a result (0 = not submitted)
+    pub submitted_at: i64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl Milestone {
+    pub const SIZE: usize = 8  // discriminator
+        + 32   // task
+        + 1    // index
+        + 8    // amount
+        + 128  // description
+        + 1    // status
+        + 32   // result_hash
+        + 8    // submitted_at
         + 1;   // bump
 }
+
+/// Most chunks a result can be split into for the task_verify_chunked circuit (2^10)
+pub const MAX_RESULT_CHUNKS: u32 = 1024;
+
+/// Most public inputs a registered circuit may declare
+pub const MAX_PUBLIC_INPUTS: usize = 16;
+
+/// Circuits the program verifies proofs for. Each fixes its public input layout.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ZkCircuit {
+    /// task_verify_v2: [expectedHash, taskHi, taskLo, providerHi, providerLo]
+    TaskVerify = 0,
+    /// result_delivery: [expectedHash, ciphertext, encKeyX, encKeyY, ephemeralX, ephemeralY,
+    /// taskHi, taskLo, providerHi, providerLo]
+    ResultDelivery = 1,
+    /// task_verify_chunked: [resultRoot, chunkCount, taskHi, taskLo, providerHi, providerLo]
+    TaskVerifyChunked = 2,
+}
+
+impl ZkCircuit {
+    /// Number of public inputs the circuit exposes.
+    pub fn public_inputs(&self) -> usize {
+        match self {
+            ZkCircuit::TaskVerify => 5,
+            ZkCircuit::ResultDelivery => 10,
+            ZkCircuit::TaskVerifyChunked => 6,
+        }
+    }
+
+    /// The circuit's byte in its verifying key PDA seeds, written as a method so the
+    /// IDL can resolve the seed from the instruction argument.
+    pub fn seed(&self) -> [u8; 1] {
+        [*self as u8]
+    }
+}
+
+/// Groth16 verifying key for one version of a circuit, a PDA at
+/// `["vk", circuit, version]`. Written by the protocol admin; the IC points are
+/// appended in chunks because a full key does not fit in one transaction.
+#[account]
+pub struct VerifyingKeyAccount {
+    /// Circuit this key verifies
+    pub circuit: ZkCircuit,
+    /// Key version within the circuit
+    pub version: u16,
+    /// Number of public inputs (IC holds one more point than this)
+    pub nr_pubinputs: u8,
+    /// alpha (G1, big-endian)
+    pub alpha_g1: [u8; 64],
+    /// beta (G2, big-endian)
+    pub beta_g2: [u8; 128],
+    /// gamma (G2, big-endian)
+    pub gamma_g2: [u8; 128],
+    /// delta (G2, big-endian)
+    pub delta_g2: [u8; 128],
+    /// IC points (G1, big-endian), filled by `append_verifying_key_ic`
+    pub ic: Vec<[u8; 64]>,
+    /// Whether the admin withdrew this key; revoked keys verify nothing
+    pub revoked: bool,
+    /// Timestamp of creation
+    pub created_at: i64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl VerifyingKeyAccount {
+    pub const SIZE: usize = 8  // discriminator
+        + 1    // circuit
+        + 2    // version
+        + 1    // nr_pubinputs
+        + 64   // alpha_g1
+        + 128  // beta_g2
+        + 128  // gamma_g2
+        + 128  // delta_g2
+        + 4 + 64 * (MAX_PUBLIC_INPUTS + 1) // ic
+        + 1    // revoked
+        + 8    // created_at
+        + 1;   // bump
+
+    /// Whether every IC point has been written.
+    pub fn is_complete(&self) -> bool {
+        self.ic.len() == usize::from(self.nr_pubinputs) + 1
+    }
+
+    /// Whether proofs can be verified against this key.
+    pub fn is_usable(&self) -> bool {
+        self.is_complete() && !self.revoked
+    }
+}
+
+/// Encrypted delivery of a task result, a PDA at `["delivery", task]`.
+/// The requester publishes a BabyJubJub encryption key; the provider posts the result
+/// encrypted to it together with a proof that it decrypts to the hashed pre-image.
+#[account]
+pub struct EncryptedDelivery {
+    /// The task this delivery belongs to
+    pub task: Pubkey,
+    /// Requester's BabyJubJub public key (x || y, big-endian field elements)
+    pub encryption_key: [u8; 64],
+    /// Result hash the requester is paying for (zero = any result, reviewed as usual)
+    pub expected_hash: [u8; 32],
+    /// Provider's ephemeral BabyJubJub public key (x || y)
+    pub ephemeral_key: [u8; 64],
+    /// Encrypted result: result + Poseidon(shared point)
+    pub ciphertext: [u8; 32],
+    /// Timestamp the ciphertext was posted (0 = not delivered)
+    pub delivered_at: i64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl EncryptedDelivery {
+    pub const SIZE: usize = 8  // discriminator
+        + 32   // task
+        + 64   // encryption_key
+        + 32   // expected_hash
+        + 64   // ephemeral_key
+        + 32   // ciphertext
+        + 8    // delivered_at
+        + 1;   // bump
+
+    /// Whether the requester fixed the result hash up front, making delivery a fair exchange.
+    pub fn has_expected_hash(&self) -> bool {
+        self.expected_hash != [0u8; 32]
+    }
+}
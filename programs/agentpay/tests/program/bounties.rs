@@ -0,0 +1,309 @@
+use agentpay::errors::AgentPayError;
+use agentpay::events::BountyClaimed;
+use agentpay::state::{
+    ProviderProfile, ServiceListing, TaskStatus, MAX_CLAIM_TIMEOUT, MAX_REVIEW_WINDOW,
+};
+use anchor_lang::ToAccountMetas;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+use crate::harness::*;
+
+/// Give `provider` a profile showing `completed` finished tasks.
+fn set_completed(env: &mut TestEnv, provider: &Pubkey, completed: u64) {
+    let profile = profile_pda(provider);
+    let mut state: ProviderProfile = env.svm.read(&profile);
+    state.tasks_completed = completed;
+    env.svm.write(&profile, &state);
+}
+
+#[test]
+fn a_claimed_bounty_settles_without_a_listing() {
+    let mut env = TestEnv::new();
+    let provider = env.provider.pubkey();
+    let requester_before = env.svm.lamports(&env.requester.pubkey());
+    let task = env.create_bounty(0);
+
+    let state = env.task(&task);
+    assert!(state.is_bounty() && state.status == TaskStatus::Open);
+    assert_eq!(state.provider, Pubkey::default());
+    assert_eq!(state.amount_lamports, PRICE);
+    assert_eq!((state.arbiter, state.claimed_at), (env.arbiter.pubkey(), 0));
+    assert!(requester_before - env.svm.lamports(&env.requester.pubkey()) >= PRICE);
+
+    // A provider who has never listed a service gets a profile on their first claim
+    assert!(!env.svm.exists(&profile_pda(&provider)));
+    let ix = env.claim_bounty_ix(&provider, &task);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    let claimed = event::<BountyClaimed>(&meta);
+    assert_eq!(
+        (claimed.provider, claimed.previous_provider),
+        (provider, Pubkey::default())
+    );
+    assert_eq!(claimed.claim_expires_at, env.svm.now() + CLAIM_TIMEOUT);
+
+    let state = env.task(&task);
+    assert!(state.status == TaskStatus::InProgress);
+    assert_eq!(
+        (state.provider, state.claimed_at),
+        (provider, env.svm.now())
+    );
+
+    let ix = env.submit_result_ix(&provider, &task, field(3));
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    let provider_before = env.svm.lamports(&provider);
+    let ix = env.accept_result_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    assert!(env.task(&task).status == TaskStatus::Completed);
+    assert_eq!(
+        env.svm.lamports(&provider) - provider_before,
+        PRICE - protocol_fee(PRICE)
+    );
+    let profile: ProviderProfile = env.svm.read(&profile_pda(&provider));
+    assert_eq!(profile.tasks_completed, 1);
+}
+
+#[test]
+fn create_bounty_validates_terms() {
+    let mut env = TestEnv::new();
+    let requester = env.requester.pubkey();
+    let deadline = env.svm.now() + TASK_DURATION;
+
+    for review_window in [0, MAX_REVIEW_WINDOW + 1, i64::MAX] {
+        let terms = BountyTerms {
+            review_window,
+            ..BountyTerms::default()
+        };
+        let task_id = env.next_id();
+        let ix = env.create_bounty_ix(&requester, task_id, deadline, &terms);
+        assert_error(
+            env.svm.process(&[ix], &[&env.requester]),
+            AgentPayError::InvalidReviewWindow,
+        );
+    }
+
+    for claim_timeout in [0, MAX_CLAIM_TIMEOUT + 1, i64::MAX] {
+        let terms = BountyTerms {
+            claim_timeout,
+            ..BountyTerms::default()
+        };
+        let task_id = env.next_id();
+        let ix = env.create_bounty_ix(&requester, task_id, deadline, &terms);
+        assert_error(
+            env.svm.process(&[ix], &[&env.requester]),
+            AgentPayError::InvalidClaimTimeout,
+        );
+    }
+
+    let terms = BountyTerms {
+        claim_timeout: MAX_CLAIM_TIMEOUT,
+        review_window: MAX_REVIEW_WINDOW,
+        ..BountyTerms::default()
+    };
+    let task_id = env.next_id();
+    let ix = env.create_bounty_ix(&requester, task_id, deadline, &terms);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let state = env.task(&task_pda(&requester, &task_id));
+    assert_eq!(state.claim_timeout, MAX_CLAIM_TIMEOUT);
+    assert_eq!(state.review_window, MAX_REVIEW_WINDOW);
+}
+
+#[test]
+fn claiming_needs_the_reputation_bar_and_a_bounty() {
+    let mut env = TestEnv::new();
+    let provider = env.provider.pubkey();
+    let listing = env.register_service();
+    let task = env.create_bounty(3);
+
+    let ix = env.claim_bounty_ix(&provider, &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.provider]),
+        AgentPayError::ReputationTooLow,
+    );
+
+    // The bounty's arbiter cannot also work on it
+    let arbiter = env.arbiter.pubkey();
+    let ix = env.claim_bounty_ix(&arbiter, &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.arbiter]),
+        AgentPayError::InvalidArbiter,
+    );
+
+    set_completed(&mut env, &provider, 3);
+    let ix = env.claim_bounty_ix(&provider, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+
+    let listed = env.create_task(&listing);
+    let ix = env.claim_bounty_ix(&provider, &listed);
+    assert_error(
+        env.svm.process(&[ix], &[&env.provider]),
+        AgentPayError::NotABounty,
+    );
+
+    let task = env.create_bounty(0);
+    env.svm.warp(TASK_DURATION + 1);
+    let ix = env.claim_bounty_ix(&provider, &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.provider]),
+        AgentPayError::DeadlinePassed,
+    );
+}
+
+#[test]
+fn a_lapsed_claim_reopens_the_bounty() {
+    let mut env = TestEnv::new();
+    let provider = env.provider.pubkey();
+    let stranger = env.stranger.pubkey();
+    let task = env.create_bounty(0);
+
+    let ix = env.claim_bounty_ix(&provider, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    let ix = env.claim_bounty_ix(&stranger, &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::BountyAlreadyClaimed,
+    );
+
+    env.svm.warp(CLAIM_TIMEOUT + 1);
+    let ix = env.claim_bounty_ix(&stranger, &task);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.stranger]));
+    assert_eq!(event::<BountyClaimed>(&meta).previous_provider, provider);
+    assert_eq!(env.task(&task).provider, stranger);
+
+    // The lapsed claimant can no longer deliver
+    let ix = env.submit_result_ix(&provider, &task, field(4));
+    assert_error(
+        env.svm.process(&[ix], &[&env.provider]),
+        AgentPayError::UnauthorizedProvider,
+    );
+
+    // Once a result is in, the claim no longer lapses
+    let ix = env.submit_result_ix(&stranger, &task, field(4));
+    assert_ok(env.svm.process(&[ix], &[&env.stranger]));
+    env.svm.warp(CLAIM_TIMEOUT + 1);
+    let ix = env.claim_bounty_ix(&provider, &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.provider]),
+        AgentPayError::InvalidTaskStatus,
+    );
+}
+
+#[test]
+fn only_an_unclaimed_bounty_is_withdrawn() {
+    let mut env = TestEnv::new();
+    let requester = env.requester.pubkey();
+    let task = env.create_bounty(0);
+    let task_balance = env.svm.lamports(&task);
+
+    let ix = env.cancel_bounty_ix(&requester, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert!(env.task(&task).status == TaskStatus::Cancelled);
+    assert_eq!(task_balance - env.svm.lamports(&task), PRICE);
+
+    let task = env.create_bounty(0);
+    let ix = env.claim_bounty_ix(&env.provider.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    let ix = env.cancel_bounty_ix(&requester, &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::InvalidTaskStatus,
+    );
+
+    let listing = env.register_service();
+    let listed = env.create_task(&listing);
+    let ix = env.cancel_bounty_ix(&requester, &listed);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::NotABounty,
+    );
+}
+
+#[test]
+fn listing_tasks_cannot_skip_their_listing_counter() {
+    let mut env = TestEnv::new();
+    let (listing, task) = env.submitted_task();
+
+    // Omitting the optional listing would let a settlement skip its completed counter
+    let mut ix = env.accept_result_ix(&env.requester.pubkey(), &task);
+    ix.accounts = agentpay::accounts::AcceptResult {
+        requester: env.requester.pubkey(),
+        task_request: task,
+        provider: env.provider.pubkey(),
+        service_listing: None,
+        provider_profile: profile_pda(&env.provider.pubkey()),
+        config: config_pda(),
+        treasury: env.treasury.pubkey(),
+        mint: None,
+        escrow_token_account: None,
+        provider_token_account: None,
+        treasury_token_account: None,
+        token_program: None,
+        event_authority: event_authority_pda(),
+        program: agentpay::ID,
+    }
+    .to_account_metas(None);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::ServiceListingMismatch,
+    );
+
+    let ix = env.accept_result_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert_eq!(env.svm.read::<ServiceListing>(&listing).tasks_completed, 1);
+}
+
+#[test]
+fn an_unclaimed_bounty_expires_without_a_provider_profile() {
+    let mut env = TestEnv::new();
+    let task = env.create_bounty(0);
+    let task_balance = env.svm.lamports(&task);
+
+    let ix = env.expire_task_ix(&task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::DeadlineNotReached,
+    );
+    env.svm.warp(TASK_DURATION + 1);
+    let ix = env.expire_task_ix(&task);
+    assert_ok(env.svm.process(&[ix], &[&env.stranger]));
+
+    assert!(env.task(&task).status == TaskStatus::Expired);
+    assert_eq!(task_balance - env.svm.lamports(&task), PRICE);
+}
+
+#[test]
+fn a_lapsed_claim_expires_on_the_claimant_profile() {
+    let mut env = TestEnv::new();
+    let provider = env.provider.pubkey();
+    let task = env.create_bounty(0);
+    let claim = env.claim_bounty_ix(&provider, &task);
+    assert_ok(env.svm.process(&[claim], &[&env.provider]));
+    env.svm.warp(TASK_DURATION + 1);
+
+    // Once claimed, the task has a provider whose profile has to be booked
+    let state = env.task(&task);
+    let expire = ix(
+        agentpay::accounts::ExpireTask {
+            requester: state.requester,
+            task_request: task,
+            provider_profile: None,
+            mint: None,
+            escrow_token_account: None,
+            requester_token_account: None,
+            token_program: None,
+            event_authority: event_authority_pda(),
+            program: agentpay::ID,
+        },
+        agentpay::instruction::ExpireTask {},
+    );
+    assert_error(
+        env.svm.process(&[expire], &[&env.stranger]),
+        AgentPayError::ProviderProfileRequired,
+    );
+
+    let ix = env.expire_task_ix(&task);
+    assert_ok(env.svm.process(&[ix], &[&env.stranger]));
+    assert!(env.task(&task).status == TaskStatus::Expired);
+    let profile: ProviderProfile = env.svm.read(&profile_pda(&provider));
+    assert_eq!(profile.tasks_expired, 1);
+}
@@ -0,0 +1,161 @@
+use agentpay::errors::AgentPayError;
+use agentpay::state::{Config, MAX_PROTOCOL_FEE_BPS};
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::harness::*;
+
+#[test]
+fn initialize_config_records_admin_treasury_and_fee() {
+    let env = TestEnv::new();
+
+    let config: Config = env.svm.read(&config_pda());
+    assert_eq!(config.admin, env.admin.pubkey());
+    assert_eq!(config.treasury, env.treasury.pubkey());
+    assert_eq!(config.fee_bps, PROTOCOL_FEE_BPS);
+    assert!(!config.paused);
+}
+
+#[test]
+fn initialize_config_requires_the_upgrade_authority() {
+    let mut env = TestEnv::without_config();
+
+    let ix = env.initialize_config_ix(
+        &env.stranger.pubkey(),
+        PROTOCOL_FEE_BPS,
+        env.treasury.pubkey(),
+    );
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::UnauthorizedAdmin,
+    );
+}
+
+#[test]
+fn initialize_config_validates_fee_and_treasury() {
+    let mut env = TestEnv::without_config();
+
+    let ix = env.initialize_config_ix(
+        &env.admin.pubkey(),
+        MAX_PROTOCOL_FEE_BPS + 1,
+        env.treasury.pubkey(),
+    );
+    assert_error(
+        env.svm.process(&[ix], &[&env.admin]),
+        AgentPayError::ProtocolFeeTooHigh,
+    );
+
+    let ix = env.initialize_config_ix(&env.admin.pubkey(), PROTOCOL_FEE_BPS, Default::default());
+    assert_error(
+        env.svm.process(&[ix], &[&env.admin]),
+        AgentPayError::InvalidTreasury,
+    );
+}
+
+#[test]
+fn protocol_fee_change_applies_to_new_tasks_only() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+    let before = env.create_task(&listing);
+
+    let ix = env.set_protocol_fee_ix(&env.admin.pubkey(), 500);
+    assert_ok(env.svm.process(&[ix], &[&env.admin]));
+    let after = env.create_task(&listing);
+
+    assert_eq!(env.task(&before).protocol_fee_bps, PROTOCOL_FEE_BPS);
+    assert_eq!(env.task(&after).protocol_fee_bps, 500);
+}
+
+#[test]
+fn set_protocol_fee_is_admin_only_and_capped() {
+    let mut env = TestEnv::new();
+
+    let ix = env.set_protocol_fee_ix(&env.stranger.pubkey(), 0);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::UnauthorizedAdmin,
+    );
+
+    let ix = env.set_protocol_fee_ix(&env.admin.pubkey(), MAX_PROTOCOL_FEE_BPS + 1);
+    assert_error(
+        env.svm.process(&[ix], &[&env.admin]),
+        AgentPayError::ProtocolFeeTooHigh,
+    );
+}
+
+#[test]
+fn pause_blocks_new_escrow_but_not_settlement() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+    let (_, submitted) = env.submitted_task();
+
+    let ix = env.set_paused_ix(&env.stranger.pubkey(), true);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::UnauthorizedAdmin,
+    );
+    let ix = env.set_paused_ix(&env.admin.pubkey(), true);
+    assert_ok(env.svm.process(&[ix], &[&env.admin]));
+
+    let terms = env.default_terms();
+    let service_id = env.next_id();
+    let ix = env.register_service_ix(&env.provider.pubkey(), service_id, &terms);
+    assert_error(
+        env.svm.process(&[ix], &[&env.provider]),
+        AgentPayError::ProgramPaused,
+    );
+
+    let task_id = env.next_id();
+    let deadline = env.svm.now() + TASK_DURATION;
+    let ix = env.create_task_ix(&env.requester.pubkey(), &listing, task_id, deadline);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::ProgramPaused,
+    );
+
+    // Funds already in escrow can still leave
+    let ix = env.accept_result_ix(&env.requester.pubkey(), &submitted);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    let ix = env.set_paused_ix(&env.admin.pubkey(), false);
+    assert_ok(env.svm.process(&[ix], &[&env.admin]));
+    env.create_task(&listing);
+}
+
+#[test]
+fn transfer_admin_hands_over_the_role() {
+    let mut env = TestEnv::new();
+    let new_admin = Keypair::new();
+    env.svm.airdrop(&new_admin.pubkey(), 1_000_000_000).unwrap();
+
+    let ix = env.transfer_admin_ix(&env.stranger.pubkey(), &new_admin.pubkey());
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger, &new_admin]),
+        AgentPayError::UnauthorizedAdmin,
+    );
+
+    let ix = env.transfer_admin_ix(&env.admin.pubkey(), &new_admin.pubkey());
+    assert_ok(env.svm.process(&[ix], &[&env.admin, &new_admin]));
+    let config: Config = env.svm.read(&config_pda());
+    assert_eq!(config.admin, new_admin.pubkey());
+
+    let ix = env.set_protocol_fee_ix(&env.admin.pubkey(), 0);
+    assert_error(
+        env.svm.process(&[ix], &[&env.admin]),
+        AgentPayError::UnauthorizedAdmin,
+    );
+    let ix = env.set_protocol_fee_ix(&new_admin.pubkey(), 0);
+    assert_ok(env.svm.process(&[ix], &[&new_admin]));
+}
+
+#[test]
+fn settlement_rejects_a_foreign_treasury() {
+    let mut env = TestEnv::new();
+    let (_, task) = env.submitted_task();
+
+    let ix =
+        env.accept_result_to_treasury_ix(&env.requester.pubkey(), &task, env.stranger.pubkey());
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::InvalidTreasury,
+    );
+}
@@ -0,0 +1,207 @@
+use agentpay::events::*;
+use agentpay::state::{MilestoneStatus, TaskStatus, ZkCircuit};
+use solana_sdk::signature::Signer;
+
+use crate::harness::*;
+
+#[test]
+fn admin_changes_emit_the_config_and_key_after_the_change() {
+    let mut env = TestEnv::without_config();
+    let admin = env.admin.pubkey();
+    let ix = env.initialize_config_ix(&admin, PROTOCOL_FEE_BPS, env.treasury.pubkey());
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.admin]));
+    let config: ConfigUpdated = event(&meta);
+    assert_eq!(config.config, config_pda());
+    assert_eq!(
+        (config.admin, config.treasury),
+        (admin, env.treasury.pubkey())
+    );
+    assert_eq!((config.fee_bps, config.paused), (PROTOCOL_FEE_BPS, false));
+
+    let ix = env.set_protocol_fee_ix(&admin, 500);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.admin]));
+    assert_eq!(event::<ConfigUpdated>(&meta).fee_bps, 500);
+    let ix = env.set_paused_ix(&admin, true);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.admin]));
+    let config: ConfigUpdated = event(&meta);
+    assert!(config.paused);
+    assert_eq!(config.fee_bps, 500);
+
+    let ix = env.init_verifying_key_ix(&admin, ZkCircuit::TaskVerify, 1, 5);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.admin]));
+    let vk = vk_pda(ZkCircuit::TaskVerify, 1);
+    let key: VerifyingKeyUpdated = event(&meta);
+    assert_eq!(key.verifying_key, vk);
+    assert!(key.circuit == ZkCircuit::TaskVerify);
+    assert_eq!((key.version, key.nr_pubinputs, key.ic_written), (1, 5, 0));
+    let ix = env.append_verifying_key_ic_ix(&admin, &vk, 6);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.admin]));
+    assert_eq!(event::<VerifyingKeyUpdated>(&meta).ic_written, 6);
+    let ix = env.revoke_verifying_key_ix(&admin, &vk);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.admin]));
+    assert!(event::<VerifyingKeyUpdated>(&meta).revoked);
+
+    let new_admin = env.stranger.pubkey();
+    let ix = env.transfer_admin_ix(&admin, &new_admin);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.admin, &env.stranger]));
+    let transferred: AdminTransferred = event(&meta);
+    assert_eq!(
+        (transferred.previous_admin, transferred.new_admin),
+        (admin, new_admin)
+    );
+}
+
+#[test]
+fn closing_accounts_emits_one_event_each() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+    let requester = env.requester.pubkey();
+
+    let task = env.create_task(&listing);
+    let ixs = [
+        env.add_milestone_ix(&requester, &task, 0, PRICE),
+        env.cancel_task_ix(&requester, &task),
+    ];
+    assert_ok(env.svm.process(&ixs, &[&env.requester]));
+    let ix = env.close_milestone_ix(&requester, &task, 0);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let closed: MilestoneClosed = event(&meta);
+    assert_eq!(closed.task, task);
+    assert_eq!(
+        (closed.milestone, closed.index),
+        (milestone_pda(&task, 0), 0)
+    );
+
+    let task = env.create_task(&listing);
+    let ixs = [
+        env.request_encrypted_delivery_ix(&requester, &task, [0u8; 64], [0u8; 32]),
+        env.cancel_task_ix(&requester, &task),
+    ];
+    assert_ok(env.svm.process(&ixs, &[&env.requester]));
+    let ix = env.close_delivery_ix(&requester, &task);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let closed: DeliveryClosed = event(&meta);
+    assert_eq!((closed.task, closed.delivery), (task, delivery_pda(&task)));
+
+    let ix = env.close_task_ix(&requester, &task);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let closed: TaskClosed = event(&meta);
+    assert_eq!((closed.task, closed.requester), (task, requester));
+}
+
+#[test]
+fn service_changes_emit_the_listing_terms() {
+    let mut env = TestEnv::new();
+    let service_id = env.next_id();
+    let terms = env.default_terms();
+    let ix = env.register_service_ix(&env.provider.pubkey(), service_id, &terms);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    let listing = listing_pda(&env.provider.pubkey(), &service_id);
+
+    let registered: ServiceRegistered = event(&meta);
+    assert_eq!(registered.service_listing, listing);
+    assert_eq!(registered.provider, env.provider.pubkey());
+    assert_eq!(registered.service_id, service_id);
+    assert_eq!(registered.price, PRICE);
+    assert_eq!(registered.arbiter, env.arbiter.pubkey());
+    assert_eq!(registered.review_window, REVIEW_WINDOW);
+    assert_eq!(registered.cancellation_fee_bps, CANCELLATION_FEE_BPS);
+
+    let ix = env.deactivate_service_ix(&env.provider.pubkey(), &listing);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    let updated: ServiceUpdated = event(&meta);
+    assert_eq!(updated.service_listing, listing);
+    assert_eq!(updated.description, padded("Summarize a document"));
+    assert!(!updated.is_active);
+}
+
+#[test]
+fn task_lifecycle_emits_created_submitted_and_settled() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+    let requester = env.requester.pubkey();
+    let task_id = env.next_id();
+    let deadline = env.svm.now() + TASK_DURATION;
+    let ix = env.create_task_ix(&requester, &listing, task_id, deadline);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let task = task_pda(&requester, &task_id);
+
+    let created: TaskCreated = event(&meta);
+    assert_eq!(created.task, task);
+    assert_eq!(created.requester, requester);
+    assert_eq!(created.provider, env.provider.pubkey());
+    assert_eq!(created.service_listing, listing);
+    assert_eq!(created.amount, PRICE);
+    assert_eq!(created.deadline, deadline);
+    assert_eq!(created.protocol_fee_bps, PROTOCOL_FEE_BPS);
+
+    let ix = env.submit_result_ix(&env.provider.pubkey(), &task, field(7));
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    let submitted: ResultSubmitted = event(&meta);
+    assert_eq!(submitted.task, task);
+    assert_eq!(submitted.result_hash, field(7));
+    assert!(!submitted.zk_verified && !submitted.encrypted);
+
+    let ix = env.accept_result_ix(&requester, &task);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let settled: TaskSettled = event(&meta);
+    assert_eq!(settled.task, task);
+    assert!(settled.status == TaskStatus::Completed);
+    assert_eq!(settled.provider_amount, PRICE - protocol_fee(PRICE));
+    assert_eq!(settled.requester_amount, 0);
+    assert_eq!(settled.protocol_fee, protocol_fee(PRICE));
+}
+
+#[test]
+fn dispute_resolution_reports_both_shares() {
+    let mut env = TestEnv::new();
+    let (_, task) = env.submitted_task();
+
+    let ix = env.dispute_task_ix(&env.requester.pubkey(), &task);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let disputed: TaskDisputed = event(&meta);
+    assert_eq!(disputed.arbiter, env.arbiter.pubkey());
+
+    let ix = env.resolve_dispute_ix(&env.arbiter.pubkey(), &task, 6_000);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.arbiter]));
+    let settled: TaskSettled = event(&meta);
+    let provider_share = PRICE * 6 / 10;
+    assert!(settled.status == TaskStatus::Disputed);
+    assert_eq!(
+        settled.provider_amount,
+        provider_share - protocol_fee(provider_share)
+    );
+    assert_eq!(settled.requester_amount, PRICE - provider_share);
+    assert_eq!(settled.protocol_fee, protocol_fee(provider_share));
+}
+
+#[test]
+fn the_last_milestone_reports_the_task_settled() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+    let task = env.create_task(&listing);
+    let requester = env.requester.pubkey();
+    let provider = env.provider.pubkey();
+
+    let ix = env.add_milestone_ix(&requester, &task, 0, PRICE);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let added: MilestoneAdded = event(&meta);
+    assert_eq!(added.milestone, milestone_pda(&task, 0));
+    assert_eq!(added.amount, PRICE);
+
+    let ixs = [
+        env.start_task_ix(&provider, &task),
+        env.submit_milestone_ix(&provider, &task, 0),
+    ];
+    let meta = assert_ok(env.svm.process(&ixs, &[&env.provider]));
+    assert_eq!(events::<TaskStarted>(&meta).len(), 1);
+    assert_eq!(events::<MilestoneSubmitted>(&meta).len(), 1);
+
+    let ix = env.accept_milestone_ix(&requester, &task, 0);
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let settled: MilestoneSettled = event(&meta);
+    assert_eq!(settled.index, 0);
+    assert!(settled.status == MilestoneStatus::Released);
+    assert_eq!(settled.provider_amount, PRICE - protocol_fee(PRICE));
+    assert!(settled.task_status == TaskStatus::Completed);
+}
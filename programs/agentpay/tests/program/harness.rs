@@ -0,0 +1,1320 @@
+//! Test environment: the compiled program loaded into an in-process LiteSVM, with
+//! funded keypairs for every role and builders for each instruction.
+
+use agentpay::state::{ServiceListing, TaskRequest, VerifyingKeyAccount, ZkCircuit};
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use anchor_lang::{
+    system_program, AccountDeserialize, AccountSerialize, Event, InstructionData, ToAccountMetas,
+};
+use litesvm::types::{TransactionMetadata, TransactionResult};
+use litesvm::LiteSVM;
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    compute_budget::ComputeBudgetInstruction,
+    instruction::{Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+/// Built by `anchor build`; the tests never touch a validator or the network.
+const PROGRAM_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../target/deploy/agentpay.so"
+);
+
+pub const PRICE: u64 = LAMPORTS_PER_SOL / 10;
+pub const REVIEW_WINDOW: i64 = 3600;
+pub const CANCELLATION_FEE_BPS: u16 = 1_000;
+pub const PROTOCOL_FEE_BPS: u16 = 100;
+pub const TASK_DURATION: i64 = 24 * 3600;
+pub const CLAIM_TIMEOUT: i64 = 2 * 3600;
+
+/// Proof points for the degenerate all-zero verifying keys written by
+/// `register_zero_vk`: every pairing involves the point at infinity, so the zero
+/// proof verifies and any proof with non-zero A and B does not. This reaches the
+/// success paths of the ZK instructions without a trusted setup.
+pub struct Proof {
+    pub a: [u8; 64],
+    pub b: [u8; 128],
+    pub c: [u8; 64],
+}
+
+pub const ZERO_PROOF: Proof = Proof {
+    a: [0u8; 64],
+    b: [0u8; 128],
+    c: [0u8; 64],
+};
+
+/// The BN254 G1 generator (1, 2), EIP-197 encoding.
+pub fn g1_generator() -> [u8; 64] {
+    let mut point = [0u8; 64];
+    point[31] = 1;
+    point[63] = 2;
+    point
+}
+
+/// The BN254 G2 generator, EIP-197 encoding.
+pub fn g2_generator() -> [u8; 128] {
+    hex32s([
+        "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2",
+        "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed",
+        "090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b",
+        "12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
+    ])
+}
+
+/// A = G1 generator, B = G2 generator: e(A, B) != 1.
+pub fn invalid_proof() -> Proof {
+    Proof {
+        a: g1_generator(),
+        b: g2_generator(),
+        c: [0u8; 64],
+    }
+}
+
+/// A complete key for `circuit` with every point a generator: accepted by
+/// `init_verifying_key`, though no proof anyone can compute verifies against it.
+pub fn generator_vk(circuit: ZkCircuit, version: u16) -> VerifyingKeyAccount {
+    VerifyingKeyAccount {
+        circuit,
+        version,
+        nr_pubinputs: circuit.public_inputs() as u8,
+        alpha_g1: g1_generator(),
+        beta_g2: g2_generator(),
+        gamma_g2: g2_generator(),
+        delta_g2: g2_generator(),
+        ic: vec![g1_generator(); circuit.public_inputs() + 1],
+        revoked: false,
+        created_at: 0,
+        bump: 0,
+    }
+}
+
+fn hex32s(words: [&str; 4]) -> [u8; 128] {
+    let mut out = [0u8; 128];
+    for (i, word) in words.iter().enumerate() {
+        for j in 0..32 {
+            out[i * 32 + j] = u8::from_str_radix(&word[2 * j..2 * j + 2], 16).unwrap();
+        }
+    }
+    out
+}
+
+/// A big-endian field element holding `value`, well below the BN254 modulus.
+pub fn field(value: u64) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[24..].copy_from_slice(&value.to_be_bytes());
+    out
+}
+
+pub fn padded<const N: usize>(text: &str) -> [u8; N] {
+    let mut out = [0u8; N];
+    out[..text.len()].copy_from_slice(text.as_bytes());
+    out
+}
+
+pub fn config_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &agentpay::ID).0
+}
+
+/// Signer of the self-CPI that carries the program's events.
+pub fn event_authority_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &agentpay::ID).0
+}
+
+pub fn listing_pda(provider: &Pubkey, service_id: &[u8; 16]) -> Pubkey {
+    Pubkey::find_program_address(&[b"service", provider.as_ref(), service_id], &agentpay::ID).0
+}
+
+pub fn task_pda(requester: &Pubkey, task_id: &[u8; 16]) -> Pubkey {
+    Pubkey::find_program_address(&[b"task", requester.as_ref(), task_id], &agentpay::ID).0
+}
+
+pub fn milestone_pda(task: &Pubkey, index: u8) -> Pubkey {
+    Pubkey::find_program_address(&[b"milestone", task.as_ref(), &[index]], &agentpay::ID).0
+}
+
+pub fn escrow_pda(task: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"escrow", task.as_ref()], &agentpay::ID).0
+}
+
+pub fn delivery_pda(task: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"delivery", task.as_ref()], &agentpay::ID).0
+}
+
+pub fn profile_pda(provider: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"provider", provider.as_ref()], &agentpay::ID).0
+}
+
+/// The profile a settlement of `state` books; an unclaimed bounty has none.
+pub fn profile_of(state: &TaskRequest) -> Option<Pubkey> {
+    state.has_provider().then(|| profile_pda(&state.provider))
+}
+
+/// The listing a settlement of `state` passes; bounties have none.
+pub fn listing_of(state: &TaskRequest) -> Option<Pubkey> {
+    (!state.is_bounty()).then_some(state.service_listing)
+}
+
+pub fn review_pda(task: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"review", task.as_ref()], &agentpay::ID).0
+}
+
+pub fn vk_pda(circuit: ZkCircuit, version: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"vk", &[circuit as u8], &version.to_le_bytes()],
+        &agentpay::ID,
+    )
+    .0
+}
+
+fn program_data_pda() -> Pubkey {
+    Pubkey::find_program_address(&[agentpay::ID.as_ref()], &bpf_loader_upgradeable::id()).0
+}
+
+pub fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: agentpay::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Transaction helpers on the SVM itself, so tests can borrow `env.svm` mutably
+/// while passing the environment's keypairs as signers.
+pub trait SvmExt {
+    /// Send `ixs` signed by `signers`; the first signer pays.
+    #[allow(clippy::result_large_err)]
+    fn process(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> TransactionResult;
+    fn now(&self) -> i64;
+    /// Move the clock forward by `seconds`.
+    fn warp(&mut self, seconds: i64);
+    fn read<T: AccountDeserialize>(&self, address: &Pubkey) -> T;
+    /// Overwrite an Anchor account in place, to set up states no instruction reaches.
+    fn write<T: AccountSerialize>(&mut self, address: &Pubkey, value: &T);
+    fn lamports(&self, address: &Pubkey) -> u64;
+    fn exists(&self, address: &Pubkey) -> bool;
+}
+
+impl SvmExt for LiteSVM {
+    fn process(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> TransactionResult {
+        let tx = Transaction::new_signed_with_payer(
+            ixs,
+            Some(&signers[0].pubkey()),
+            signers,
+            self.latest_blockhash(),
+        );
+        let result = self.send_transaction(tx);
+        // A fresh blockhash lets a test resend an identical instruction
+        self.expire_blockhash();
+        result
+    }
+
+    fn now(&self) -> i64 {
+        self.get_sysvar::<Clock>().unix_timestamp
+    }
+
+    fn warp(&mut self, seconds: i64) {
+        let mut clock = self.get_sysvar::<Clock>();
+        clock.unix_timestamp += seconds;
+        clock.slot += 1;
+        self.set_sysvar(&clock);
+    }
+
+    fn read<T: AccountDeserialize>(&self, address: &Pubkey) -> T {
+        let account = self
+            .get_account(address)
+            .unwrap_or_else(|| panic!("account {address} does not exist"));
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    fn write<T: AccountSerialize>(&mut self, address: &Pubkey, value: &T) {
+        let mut account = self.get_account(address).unwrap();
+        let mut data = Vec::new();
+        value.try_serialize(&mut data).unwrap();
+        account.data[..data.len()].copy_from_slice(&data);
+        self.set_account(*address, account).unwrap();
+    }
+
+    fn lamports(&self, address: &Pubkey) -> u64 {
+        self.get_balance(address).unwrap_or(0)
+    }
+
+    fn exists(&self, address: &Pubkey) -> bool {
+        self.get_account(address)
+            .is_some_and(|account| account.lamports > 0)
+    }
+}
+
+pub fn assert_ok(result: TransactionResult) -> TransactionMetadata {
+    result.unwrap_or_else(|failed| {
+        panic!(
+            "transaction failed: {:?}\n{}",
+            failed.err,
+            failed.meta.logs.join("\n")
+        )
+    })
+}
+
+/// Assert the transaction failed with `code`, either an `AgentPayError` or an
+/// Anchor `ErrorCode`.
+pub fn assert_error(result: TransactionResult, code: impl Into<u32>) {
+    let code = code.into();
+    match result {
+        Ok(meta) => panic!(
+            "expected error {code}, transaction succeeded\n{}",
+            meta.logs.join("\n")
+        ),
+        Err(failed) => match failed.err {
+            TransactionError::InstructionError(_, InstructionError::Custom(actual)) => {
+                assert_eq!(actual, code, "{}", failed.meta.logs.join("\n"))
+            }
+            err => panic!(
+                "expected error {code}, got {err:?}\n{}",
+                failed.meta.logs.join("\n")
+            ),
+        },
+    }
+}
+
+/// Decode every `E` the transaction emitted through the program's event self-CPI.
+pub fn events<E: Event>(meta: &TransactionMetadata) -> Vec<E> {
+    meta.inner_instructions
+        .iter()
+        .flatten()
+        .filter_map(|inner| {
+            let data = inner.instruction.data.strip_prefix(EVENT_IX_TAG_LE)?;
+            let mut data = data.strip_prefix(E::DISCRIMINATOR)?;
+            Some(E::deserialize(&mut data).unwrap())
+        })
+        .collect()
+}
+
+/// The one `E` the transaction emitted.
+pub fn event<E: Event>(meta: &TransactionMetadata) -> E {
+    let mut emitted = events::<E>(meta);
+    assert_eq!(emitted.len(), 1, "expected exactly one event");
+    emitted.remove(0)
+}
+
+/// Terms for `TestEnv::register_service_with`.
+pub struct ServiceTerms {
+    pub price: u64,
+    pub min_reputation: u64,
+    pub arbiter: Pubkey,
+    pub review_window: i64,
+    pub cancellation_fee_bps: u16,
+    pub mint: Option<Pubkey>,
+}
+
+/// Terms for `TestEnv::create_bounty_ix`.
+pub struct BountyTerms {
+    pub min_reputation: u64,
+    pub claim_timeout: i64,
+    pub review_window: i64,
+}
+
+impl Default for BountyTerms {
+    fn default() -> Self {
+        Self {
+            min_reputation: 0,
+            claim_timeout: CLAIM_TIMEOUT,
+            review_window: REVIEW_WINDOW,
+        }
+    }
+}
+
+pub struct TestEnv {
+    pub svm: LiteSVM,
+    /// Upgrade authority of the program and protocol admin
+    pub admin: Keypair,
+    pub treasury: Keypair,
+    pub provider: Keypair,
+    pub requester: Keypair,
+    pub arbiter: Keypair,
+    /// Unrelated signer for permissionless cranks and unauthorized calls
+    pub stranger: Keypair,
+    next_id: u8,
+}
+
+impl TestEnv {
+    /// Program deployed and the protocol config initialized with `PROTOCOL_FEE_BPS`.
+    pub fn new() -> Self {
+        let mut env = Self::without_config();
+        let ix =
+            env.initialize_config_ix(&env.admin.pubkey(), PROTOCOL_FEE_BPS, env.treasury.pubkey());
+        assert_ok(env.svm.process(&[ix], &[&env.admin]));
+        env
+    }
+
+    /// Program deployed behind the upgradeable loader with `admin` as upgrade authority,
+    /// so `initialize_config` can check it. No config yet.
+    pub fn without_config() -> Self {
+        let mut svm = LiteSVM::new();
+        let admin = Keypair::new();
+
+        let elf = std::fs::read(PROGRAM_PATH)
+            .unwrap_or_else(|_| panic!("{PROGRAM_PATH} not found; run `anchor build` first"));
+        let mut program_data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(admin.pubkey()),
+        })
+        .unwrap();
+        program_data.resize(UpgradeableLoaderState::size_of_programdata_metadata(), 0);
+        program_data.extend_from_slice(&elf);
+        let program_data_lamports = svm.minimum_balance_for_rent_exemption(program_data.len());
+        svm.set_account(
+            program_data_pda(),
+            Account {
+                lamports: program_data_lamports,
+                data: program_data,
+                owner: bpf_loader_upgradeable::id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        )
+        .unwrap();
+
+        let program = bincode::serialize(&UpgradeableLoaderState::Program {
+            programdata_address: program_data_pda(),
+        })
+        .unwrap();
+        let program_lamports = svm.minimum_balance_for_rent_exemption(program.len());
+        svm.set_account(
+            agentpay::ID,
+            Account {
+                lamports: program_lamports,
+                data: program,
+                owner: bpf_loader_upgradeable::id(),
+                executable: true,
+                rent_epoch: 0,
+            },
+        )
+        .unwrap();
+
+        let mut env = Self {
+            svm,
+            admin,
+            treasury: Keypair::new(),
+            provider: Keypair::new(),
+            requester: Keypair::new(),
+            arbiter: Keypair::new(),
+            stranger: Keypair::new(),
+            next_id: 0,
+        };
+        for key in [
+            env.admin.pubkey(),
+            env.treasury.pubkey(),
+            env.provider.pubkey(),
+            env.requester.pubkey(),
+            env.arbiter.pubkey(),
+            env.stranger.pubkey(),
+        ] {
+            env.svm.airdrop(&key, 100 * LAMPORTS_PER_SOL).unwrap();
+        }
+        env
+    }
+
+    /// A fresh 16-byte id for services and tasks.
+    pub fn next_id(&mut self) -> [u8; 16] {
+        self.next_id += 1;
+        let mut id = [0u8; 16];
+        id[0] = self.next_id;
+        id
+    }
+
+    pub fn default_terms(&self) -> ServiceTerms {
+        ServiceTerms {
+            price: PRICE,
+            min_reputation: 0,
+            arbiter: self.arbiter.pubkey(),
+            review_window: REVIEW_WINDOW,
+            cancellation_fee_bps: CANCELLATION_FEE_BPS,
+            mint: None,
+        }
+    }
+
+    /// Register a native SOL listing from `provider` with the default terms.
+    pub fn register_service(&mut self) -> Pubkey {
+        let terms = self.default_terms();
+        self.register_service_with(&terms)
+    }
+
+    pub fn register_service_with(&mut self, terms: &ServiceTerms) -> Pubkey {
+        let service_id = self.next_id();
+        let ix = self.register_service_ix(&self.provider.pubkey(), service_id, terms);
+        assert_ok(self.svm.process(&[ix], &[&self.provider]));
+        listing_pda(&self.provider.pubkey(), &service_id)
+    }
+
+    /// Create an open task on `listing` from `requester`.
+    pub fn create_task(&mut self, listing: &Pubkey) -> Pubkey {
+        let task_id = self.next_id();
+        let deadline = self.svm.now() + TASK_DURATION;
+        let ix = self.create_task_ix(&self.requester.pubkey(), listing, task_id, deadline);
+        assert_ok(self.svm.process(&[ix], &[&self.requester]));
+        task_pda(&self.requester.pubkey(), &task_id)
+    }
+
+    /// Post a native SOL bounty of `PRICE` from `requester`, claimable by providers
+    /// with `min_reputation` completed tasks.
+    pub fn create_bounty(&mut self, min_reputation: u64) -> Pubkey {
+        let task_id = self.next_id();
+        let deadline = self.svm.now() + TASK_DURATION;
+        let terms = BountyTerms {
+            min_reputation,
+            ..BountyTerms::default()
+        };
+        let ix = self.create_bounty_ix(&self.requester.pubkey(), task_id, deadline, &terms);
+        assert_ok(self.svm.process(&[ix], &[&self.requester]));
+        task_pda(&self.requester.pubkey(), &task_id)
+    }
+
+    /// A listing and a task with a submitted result, ready for review.
+    pub fn submitted_task(&mut self) -> (Pubkey, Pubkey) {
+        let listing = self.register_service();
+        let task = self.create_task(&listing);
+        let ix = self.submit_result_ix(&self.provider.pubkey(), &task, field(7));
+        assert_ok(self.svm.process(&[ix], &[&self.provider]));
+        (listing, task)
+    }
+
+    /// Register a complete key for `circuit`, then zero its points in place; see
+    /// `ZERO_PROOF`. The program refuses to register such a key itself.
+    pub fn register_zero_vk(&mut self, circuit: ZkCircuit, version: u16) -> Pubkey {
+        let vk = self.register_vk(&generator_vk(circuit, version));
+        let mut key: VerifyingKeyAccount = self.svm.read(&vk);
+        key.alpha_g1 = [0u8; 64];
+        key.beta_g2 = [0u8; 128];
+        key.gamma_g2 = [0u8; 128];
+        key.delta_g2 = [0u8; 128];
+        key.ic.fill([0u8; 64]);
+        self.svm.write(&vk, &key);
+        vk
+    }
+
+    /// Register `key` as generated by a trusted setup, IC points included.
+    pub fn register_vk(&mut self, key: &VerifyingKeyAccount) -> Pubkey {
+        let ixs = self.verifying_key_ixs(key);
+        assert_ok(self.svm.process(&ixs, &[&self.admin]));
+        vk_pda(key.circuit, key.version)
+    }
+
+    pub fn task(&self, task: &Pubkey) -> TaskRequest {
+        self.svm.read(task)
+    }
+
+    // ========================================================================
+    // Instruction builders
+    // ========================================================================
+
+    pub fn initialize_config_ix(
+        &self,
+        admin: &Pubkey,
+        fee_bps: u16,
+        treasury: Pubkey,
+    ) -> Instruction {
+        ix(
+            agentpay::accounts::InitializeConfig {
+                admin: *admin,
+                config: config_pda(),
+                program_data: program_data_pda(),
+                system_program: system_program::ID,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::InitializeConfig { fee_bps, treasury },
+        )
+    }
+
+    pub fn set_protocol_fee_ix(&self, admin: &Pubkey, fee_bps: u16) -> Instruction {
+        ix(
+            agentpay::accounts::UpdateConfig {
+                admin: *admin,
+                config: config_pda(),
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::SetProtocolFee { fee_bps },
+        )
+    }
+
+    pub fn set_paused_ix(&self, admin: &Pubkey, paused: bool) -> Instruction {
+        ix(
+            agentpay::accounts::UpdateConfig {
+                admin: *admin,
+                config: config_pda(),
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::SetPaused { paused },
+        )
+    }
+
+    pub fn transfer_admin_ix(&self, admin: &Pubkey, new_admin: &Pubkey) -> Instruction {
+        ix(
+            agentpay::accounts::TransferAdmin {
+                admin: *admin,
+                new_admin: *new_admin,
+                config: config_pda(),
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::TransferAdmin {},
+        )
+    }
+
+    /// `init_verifying_key` and one `append_verifying_key_ic` writing `key` whole.
+    pub fn verifying_key_ixs(&self, key: &VerifyingKeyAccount) -> [Instruction; 2] {
+        let vk = vk_pda(key.circuit, key.version);
+        let init = ix(
+            agentpay::accounts::InitVerifyingKey {
+                admin: self.admin.pubkey(),
+                config: config_pda(),
+                verifying_key: vk,
+                system_program: system_program::ID,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::InitVerifyingKey {
+                circuit: key.circuit,
+                version: key.version,
+                nr_pubinputs: key.nr_pubinputs,
+                alpha_g1: key.alpha_g1,
+                beta_g2: key.beta_g2,
+                gamma_g2: key.gamma_g2,
+                delta_g2: key.delta_g2,
+            },
+        );
+        let append = ix(
+            agentpay::accounts::UpdateVerifyingKey {
+                admin: self.admin.pubkey(),
+                config: config_pda(),
+                verifying_key: vk,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::AppendVerifyingKeyIc { ic: key.ic.clone() },
+        );
+        [init, append]
+    }
+
+    pub fn init_verifying_key_ix(
+        &self,
+        admin: &Pubkey,
+        circuit: ZkCircuit,
+        version: u16,
+        nr_pubinputs: u8,
+    ) -> Instruction {
+        ix(
+            agentpay::accounts::InitVerifyingKey {
+                admin: *admin,
+                config: config_pda(),
+                verifying_key: vk_pda(circuit, version),
+                system_program: system_program::ID,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::InitVerifyingKey {
+                circuit,
+                version,
+                nr_pubinputs,
+                alpha_g1: g1_generator(),
+                beta_g2: g2_generator(),
+                gamma_g2: g2_generator(),
+                delta_g2: g2_generator(),
+            },
+        )
+    }
+
+    /// Append `count` IC points, each the G1 generator.
+    pub fn append_verifying_key_ic_ix(
+        &self,
+        admin: &Pubkey,
+        vk: &Pubkey,
+        count: usize,
+    ) -> Instruction {
+        ix(
+            agentpay::accounts::UpdateVerifyingKey {
+                admin: *admin,
+                config: config_pda(),
+                verifying_key: *vk,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::AppendVerifyingKeyIc {
+                ic: vec![g1_generator(); count],
+            },
+        )
+    }
+
+    pub fn revoke_verifying_key_ix(&self, admin: &Pubkey, vk: &Pubkey) -> Instruction {
+        ix(
+            agentpay::accounts::UpdateVerifyingKey {
+                admin: *admin,
+                config: config_pda(),
+                verifying_key: *vk,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::RevokeVerifyingKey {},
+        )
+    }
+
+    pub fn register_service_ix(
+        &self,
+        provider: &Pubkey,
+        service_id: [u8; 16],
+        terms: &ServiceTerms,
+    ) -> Instruction {
+        ix(
+            agentpay::accounts::RegisterService {
+                provider: *provider,
+                service_listing: listing_pda(provider, &service_id),
+                provider_profile: profile_pda(provider),
+                config: config_pda(),
+                mint: terms.mint,
+                system_program: system_program::ID,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::RegisterService {
+                service_id,
+                description: padded("Summarize a document"),
+                price_lamports: terms.price,
+                min_reputation: terms.min_reputation,
+                arbiter: terms.arbiter,
+                review_window: terms.review_window,
+                cancellation_fee_bps: terms.cancellation_fee_bps,
+            },
+        )
+    }
+
+    pub fn deactivate_service_ix(&self, provider: &Pubkey, listing: &Pubkey) -> Instruction {
+        ix(
+            agentpay::accounts::DeactivateService {
+                provider: *provider,
+                service_listing: *listing,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::DeactivateService {},
+        )
+    }
+
+    pub fn update_service_ix(
+        &self,
+        provider: &Pubkey,
+        listing: &Pubkey,
+        price_lamports: Option<u64>,
+        min_reputation: Option<u64>,
+        cancellation_fee_bps: Option<u16>,
+    ) -> Instruction {
+        ix(
+            agentpay::accounts::UpdateService {
+                provider: *provider,
+                service_listing: *listing,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::UpdateService {
+                price_lamports,
+                description: None,
+                min_reputation,
+                cancellation_fee_bps,
+            },
+        )
+    }
+
+    pub fn reactivate_service_ix(&self, provider: &Pubkey, listing: &Pubkey) -> Instruction {
+        ix(
+            agentpay::accounts::UpdateService {
+                provider: *provider,
+                service_listing: *listing,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::ReactivateService {},
+        )
+    }
+
+    /// Native SOL `create_task`; token tasks build their own accounts.
+    pub fn create_task_ix(
+        &self,
+        requester: &Pubkey,
+        listing: &Pubkey,
+        task_id: [u8; 16],
+        deadline: i64,
+    ) -> Instruction {
+        ix(
+            agentpay::accounts::CreateTask {
+                requester: *requester,
+                service_listing: *listing,
+                task_request: task_pda(requester, &task_id),
+                provider_profile: profile_pda(&self.svm.read::<ServiceListing>(listing).provider),
+                config: config_pda(),
+                mint: None,
+                requester_token_account: None,
+                escrow_token_account: None,
+                token_program: None,
+                system_program: system_program::ID,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::CreateTask {
+                task_id,
+                description: padded("Summarize the attached paper"),
+                deadline,
+            },
+        )
+    }
+
+    pub fn start_task_ix(&self, provider: &Pubkey, task: &Pubkey) -> Instruction {
+        ix(
+            agentpay::accounts::StartTask {
+                provider: *provider,
+                task_request: *task,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::StartTask {},
+        )
+    }
+
+    pub fn cancel_task_ix(&self, requester: &Pubkey, task: &Pubkey) -> Instruction {
+        let state = self.task(task);
+        ix(
+            agentpay::accounts::CancelTask {
+                requester: *requester,
+                task_request: *task,
+                provider: state.provider,
+                provider_profile: profile_of(&state),
+                config: config_pda(),
+                treasury: self.treasury.pubkey(),
+                mint: None,
+                escrow_token_account: None,
+                requester_token_account: None,
+                provider_token_account: None,
+                treasury_token_account: None,
+                token_program: None,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::CancelTask {},
+        )
+    }
+
+    pub fn create_bounty_ix(
+        &self,
+        requester: &Pubkey,
+        task_id: [u8; 16],
+        deadline: i64,
+        terms: &BountyTerms,
+    ) -> Instruction {
+        ix(
+            agentpay::accounts::CreateBounty {
+                requester: *requester,
+                task_request: task_pda(requester, &task_id),
+                config: config_pda(),
+                mint: None,
+                requester_token_account: None,
+                escrow_token_account: None,
+                token_program: None,
+                system_program: system_program::ID,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::CreateBounty {
+                task_id,
+                description: padded("Label 500 images"),
+                amount: PRICE,
+                deadline,
+                min_reputation: terms.min_reputation,
+                claim_timeout: terms.claim_timeout,
+                arbiter: self.arbiter.pubkey(),
+                review_window: terms.review_window,
+            },
+        )
+    }
+
+    pub fn claim_bounty_ix(&self, provider: &Pubkey, task: &Pubkey) -> Instruction {
+        ix(
+            agentpay::accounts::ClaimBounty {
+                provider: *provider,
+                task_request: *task,
+                provider_profile: profile_pda(provider),
+                system_program: system_program::ID,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::ClaimBounty {},
+        )
+    }
+
+    pub fn cancel_bounty_ix(&self, requester: &Pubkey, task: &Pubkey) -> Instruction {
+        ix(
+            agentpay::accounts::CancelBounty {
+                requester: *requester,
+                task_request: *task,
+                mint: None,
+                escrow_token_account: None,
+                requester_token_account: None,
+                token_program: None,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::CancelBounty {},
+        )
+    }
+
+    pub fn submit_result_ix(
+        &self,
+        provider: &Pubkey,
+        task: &Pubkey,
+        result_hash: [u8; 32],
+    ) -> Instruction {
+        ix(
+            agentpay::accounts::SubmitResult {
+                provider: *provider,
+                task_request: *task,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::SubmitResult { result_hash },
+        )
+    }
+
+    pub fn accept_result_ix(&self, requester: &Pubkey, task: &Pubkey) -> Instruction {
+        self.accept_result_to_treasury_ix(requester, task, self.treasury.pubkey())
+    }
+
+    pub fn accept_result_to_treasury_ix(
+        &self,
+        requester: &Pubkey,
+        task: &Pubkey,
+        treasury: Pubkey,
+    ) -> Instruction {
+        let state = self.task(task);
+        ix(
+            agentpay::accounts::AcceptResult {
+                requester: *requester,
+                task_request: *task,
+                provider: state.provider,
+                service_listing: listing_of(&state),
+                provider_profile: profile_pda(&state.provider),
+                config: config_pda(),
+                treasury,
+                mint: None,
+                escrow_token_account: None,
+                provider_token_account: None,
+                treasury_token_account: None,
+                token_program: None,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::AcceptResult {},
+        )
+    }
+
+    pub fn dispute_task_ix(&self, requester: &Pubkey, task: &Pubkey) -> Instruction {
+        ix(
+            agentpay::accounts::DisputeTask {
+                requester: *requester,
+                task_request: *task,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::DisputeTask {},
+        )
+    }
+
+    pub fn auto_accept_ix(&self, task: &Pubkey) -> Instruction {
+        let state = self.task(task);
+        ix(
+            agentpay::accounts::AutoAccept {
+                task_request: *task,
+                provider: state.provider,
+                service_listing: listing_of(&state),
+                provider_profile: profile_pda(&state.provider),
+                config: config_pda(),
+                treasury: self.treasury.pubkey(),
+                mint: None,
+                escrow_token_account: None,
+                provider_token_account: None,
+                treasury_token_account: None,
+                token_program: None,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::AutoAccept {},
+        )
+    }
+
+    pub fn resolve_dispute_ix(
+        &self,
+        arbiter: &Pubkey,
+        task: &Pubkey,
+        provider_bps: u16,
+    ) -> Instruction {
+        let state = self.task(task);
+        ix(
+            agentpay::accounts::ResolveDispute {
+                arbiter: *arbiter,
+                task_request: *task,
+                requester: state.requester,
+                provider: state.provider,
+                provider_profile: profile_pda(&state.provider),
+                config: config_pda(),
+                treasury: self.treasury.pubkey(),
+                mint: None,
+                escrow_token_account: None,
+                requester_token_account: None,
+                provider_token_account: None,
+                treasury_token_account: None,
+                token_program: None,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::ResolveDispute { provider_bps },
+        )
+    }
+
+    pub fn expire_dispute_ix(&self, party: &Pubkey, task: &Pubkey) -> Instruction {
+        let state = self.task(task);
+        ix(
+            agentpay::accounts::ExpireDispute {
+                party: *party,
+                task_request: *task,
+                requester: state.requester,
+                provider_profile: profile_pda(&state.provider),
+                mint: None,
+                escrow_token_account: None,
+                requester_token_account: None,
+                token_program: None,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::ExpireDispute {},
+        )
+    }
+
+    pub fn expire_task_ix(&self, task: &Pubkey) -> Instruction {
+        let state = self.task(task);
+        ix(
+            agentpay::accounts::ExpireTask {
+                requester: state.requester,
+                task_request: *task,
+                provider_profile: profile_of(&state),
+                mint: None,
+                escrow_token_account: None,
+                requester_token_account: None,
+                token_program: None,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::ExpireTask {},
+        )
+    }
+
+    pub fn close_task_ix(&self, requester: &Pubkey, task: &Pubkey) -> Instruction {
+        ix(
+            agentpay::accounts::CloseTask {
+                requester: *requester,
+                task_request: *task,
+                review: self.svm.exists(&review_pda(task)).then(|| review_pda(task)),
+                mint: None,
+                escrow_token_account: None,
+                token_program: None,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::CloseTask {},
+        )
+    }
+
+    pub fn submit_review_ix(
+        &self,
+        requester: &Pubkey,
+        task: &Pubkey,
+        score: u8,
+        review_hash: [u8; 32],
+    ) -> Instruction {
+        ix(
+            agentpay::accounts::SubmitReview {
+                requester: *requester,
+                task_request: *task,
+                provider_profile: profile_pda(&self.task(task).provider),
+                review: review_pda(task),
+                system_program: system_program::ID,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::SubmitReview { score, review_hash },
+        )
+    }
+
+    pub fn add_milestone_ix(
+        &self,
+        requester: &Pubkey,
+        task: &Pubkey,
+        index: u8,
+        amount: u64,
+    ) -> Instruction {
+        ix(
+            agentpay::accounts::AddMilestone {
+                requester: *requester,
+                task_request: *task,
+                milestone: milestone_pda(task, index),
+                system_program: system_program::ID,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::AddMilestone {
+                index,
+                amount,
+                description: padded("Draft"),
+            },
+        )
+    }
+
+    pub fn submit_milestone_ix(&self, provider: &Pubkey, task: &Pubkey, index: u8) -> Instruction {
+        ix(
+            agentpay::accounts::SubmitMilestone {
+                provider: *provider,
+                task_request: *task,
+                milestone: milestone_pda(task, index),
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::SubmitMilestone {
+                result_hash: field(u64::from(index) + 1),
+            },
+        )
+    }
+
+    pub fn accept_milestone_ix(&self, requester: &Pubkey, task: &Pubkey, index: u8) -> Instruction {
+        let state = self.task(task);
+        ix(
+            agentpay::accounts::AcceptMilestone {
+                requester: *requester,
+                task_request: *task,
+                milestone: milestone_pda(task, index),
+                provider: state.provider,
+                service_listing: listing_of(&state),
+                provider_profile: profile_pda(&state.provider),
+                config: config_pda(),
+                treasury: self.treasury.pubkey(),
+                mint: None,
+                escrow_token_account: None,
+                provider_token_account: None,
+                treasury_token_account: None,
+                token_program: None,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::AcceptMilestone {},
+        )
+    }
+
+    pub fn auto_accept_milestone_ix(&self, task: &Pubkey, index: u8) -> Instruction {
+        let state = self.task(task);
+        ix(
+            agentpay::accounts::AutoAcceptMilestone {
+                task_request: *task,
+                milestone: milestone_pda(task, index),
+                provider: state.provider,
+                service_listing: listing_of(&state),
+                provider_profile: profile_pda(&state.provider),
+                config: config_pda(),
+                treasury: self.treasury.pubkey(),
+                mint: None,
+                escrow_token_account: None,
+                provider_token_account: None,
+                treasury_token_account: None,
+                token_program: None,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::AutoAcceptMilestone {},
+        )
+    }
+
+    pub fn dispute_milestone_ix(
+        &self,
+        requester: &Pubkey,
+        task: &Pubkey,
+        index: u8,
+    ) -> Instruction {
+        ix(
+            agentpay::accounts::DisputeMilestone {
+                requester: *requester,
+                task_request: *task,
+                milestone: milestone_pda(task, index),
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::DisputeMilestone {},
+        )
+    }
+
+    pub fn resolve_milestone_dispute_ix(
+        &self,
+        arbiter: &Pubkey,
+        task: &Pubkey,
+        index: u8,
+        provider_bps: u16,
+    ) -> Instruction {
+        let state = self.task(task);
+        ix(
+            agentpay::accounts::ResolveMilestoneDispute {
+                arbiter: *arbiter,
+                task_request: *task,
+                milestone: milestone_pda(task, index),
+                requester: state.requester,
+                provider: state.provider,
+                provider_profile: profile_pda(&state.provider),
+                config: config_pda(),
+                treasury: self.treasury.pubkey(),
+                mint: None,
+                escrow_token_account: None,
+                requester_token_account: None,
+                provider_token_account: None,
+                treasury_token_account: None,
+                token_program: None,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::ResolveMilestoneDispute { provider_bps },
+        )
+    }
+
+    pub fn close_milestone_ix(&self, requester: &Pubkey, task: &Pubkey, index: u8) -> Instruction {
+        ix(
+            agentpay::accounts::CloseMilestone {
+                requester: *requester,
+                task_request: *task,
+                milestone: milestone_pda(task, index),
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::CloseMilestone {},
+        )
+    }
+
+    /// `submit_result_zk` with a raised compute limit for the pairing check.
+    pub fn submit_result_zk_ixs(
+        &self,
+        provider: &Pubkey,
+        task: &Pubkey,
+        vk: &Pubkey,
+        proof: &Proof,
+        result_hash: [u8; 32],
+        chunk_count: u32,
+    ) -> [Instruction; 2] {
+        [
+            ComputeBudgetInstruction::set_compute_unit_limit(1_000_000),
+            ix(
+                agentpay::accounts::SubmitResultZk {
+                    provider: *provider,
+                    task_request: *task,
+                    verifying_key: *vk,
+                    event_authority: event_authority_pda(),
+                    program: agentpay::ID,
+                },
+                agentpay::instruction::SubmitResultZk {
+                    proof_a: proof.a,
+                    proof_b: proof.b,
+                    proof_c: proof.c,
+                    result_hash,
+                    chunk_count,
+                },
+            ),
+        ]
+    }
+
+    pub fn request_encrypted_delivery_ix(
+        &self,
+        requester: &Pubkey,
+        task: &Pubkey,
+        encryption_key: [u8; 64],
+        expected_hash: [u8; 32],
+    ) -> Instruction {
+        ix(
+            agentpay::accounts::RequestEncryptedDelivery {
+                requester: *requester,
+                task_request: *task,
+                delivery: delivery_pda(task),
+                system_program: system_program::ID,
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::RequestEncryptedDelivery {
+                encryption_key,
+                expected_hash,
+            },
+        )
+    }
+
+    pub fn submit_result_encrypted_ixs(
+        &self,
+        provider: &Pubkey,
+        task: &Pubkey,
+        vk: &Pubkey,
+        proof: &Proof,
+        result_hash: [u8; 32],
+    ) -> [Instruction; 2] {
+        let state = self.task(task);
+        [
+            ComputeBudgetInstruction::set_compute_unit_limit(1_000_000),
+            ix(
+                agentpay::accounts::SubmitResultEncrypted {
+                    provider: *provider,
+                    task_request: *task,
+                    delivery: delivery_pda(task),
+                    verifying_key: *vk,
+                    service_listing: listing_of(&state),
+                    provider_profile: profile_pda(&state.provider),
+                    config: config_pda(),
+                    treasury: self.treasury.pubkey(),
+                    mint: None,
+                    escrow_token_account: None,
+                    provider_token_account: None,
+                    treasury_token_account: None,
+                    token_program: None,
+                    event_authority: event_authority_pda(),
+                    program: agentpay::ID,
+                },
+                agentpay::instruction::SubmitResultEncrypted {
+                    proof_a: proof.a,
+                    proof_b: proof.b,
+                    proof_c: proof.c,
+                    result_hash,
+                    ephemeral_key: [0u8; 64],
+                    ciphertext: field(42),
+                },
+            ),
+        ]
+    }
+
+    pub fn close_delivery_ix(&self, requester: &Pubkey, task: &Pubkey) -> Instruction {
+        ix(
+            agentpay::accounts::CloseDelivery {
+                requester: *requester,
+                task_request: *task,
+                delivery: delivery_pda(task),
+                event_authority: event_authority_pda(),
+                program: agentpay::ID,
+            },
+            agentpay::instruction::CloseDelivery {},
+        )
+    }
+}
+
+/// The protocol fee the treasury withholds from a provider payout of `amount`.
+pub fn protocol_fee(amount: u64) -> u64 {
+    amount * u64::from(PROTOCOL_FEE_BPS) / 10_000
+}
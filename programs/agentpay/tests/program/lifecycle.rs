@@ -0,0 +1,293 @@
+//! Property-based fuzzing of the task lifecycle: random instruction sequences from
+//! random signers, with the escrow invariants checked after every step.
+
+use agentpay::state::{ProviderProfile, TaskStatus};
+use anchor_lang::solana_program::system_instruction;
+use proptest::prelude::*;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+use crate::harness::*;
+
+/// Tasks a sequence can open; later `Create` steps are no-ops.
+const MAX_TASKS: usize = 4;
+
+#[derive(Clone, Copy, Debug)]
+enum Role {
+    Requester,
+    Provider,
+    Arbiter,
+    Stranger,
+}
+
+#[derive(Clone, Debug)]
+enum Step {
+    Create,
+    Start(Role, usize),
+    Cancel(Role, usize),
+    Submit(Role, usize),
+    Accept(Role, usize),
+    Dispute(Role, usize),
+    AutoAccept(usize),
+    Resolve(Role, usize, u16),
+    Expire(usize),
+    Close(Role, usize),
+    /// Lamports sent straight to the task PDA by a stranger
+    Donate(usize, u64),
+    Warp(i64),
+}
+
+fn role() -> impl Strategy<Value = Role> {
+    prop_oneof![
+        Just(Role::Requester),
+        Just(Role::Provider),
+        Just(Role::Arbiter),
+        Just(Role::Stranger),
+    ]
+}
+
+fn step() -> impl Strategy<Value = Step> {
+    let task = 0..MAX_TASKS;
+    prop_oneof![
+        Just(Step::Create),
+        (role(), task.clone()).prop_map(|(role, task)| Step::Start(role, task)),
+        (role(), task.clone()).prop_map(|(role, task)| Step::Cancel(role, task)),
+        (role(), task.clone()).prop_map(|(role, task)| Step::Submit(role, task)),
+        (role(), task.clone()).prop_map(|(role, task)| Step::Accept(role, task)),
+        (role(), task.clone()).prop_map(|(role, task)| Step::Dispute(role, task)),
+        task.clone().prop_map(Step::AutoAccept),
+        (role(), task.clone(), 0..=10_500u16)
+            .prop_map(|(role, task, bps)| Step::Resolve(role, task, bps)),
+        task.clone().prop_map(Step::Expire),
+        (role(), task.clone()).prop_map(|(role, task)| Step::Close(role, task)),
+        (task, 1..PRICE).prop_map(|(task, lamports)| Step::Donate(task, lamports)),
+        (0..2 * TASK_DURATION).prop_map(Step::Warp),
+    ]
+}
+
+impl Step {
+    /// Index of the task the step acts on, if any.
+    fn task(&self) -> Option<usize> {
+        match *self {
+            Step::Create | Step::Warp(_) => None,
+            Step::Start(_, task)
+            | Step::Cancel(_, task)
+            | Step::Submit(_, task)
+            | Step::Accept(_, task)
+            | Step::Dispute(_, task)
+            | Step::AutoAccept(task)
+            | Step::Resolve(_, task, _)
+            | Step::Expire(task)
+            | Step::Close(_, task)
+            | Step::Donate(task, _) => Some(task),
+        }
+    }
+}
+
+struct Fuzz {
+    env: TestEnv,
+    listing: Pubkey,
+    tasks: Vec<Pubkey>,
+    /// Status each task first settled with, indexed like `tasks`.
+    settled: Vec<Option<TaskStatus>>,
+    closed: Vec<bool>,
+    /// Lamports donated to each task, which must never be paid out as escrow.
+    donated: Vec<u64>,
+}
+
+impl Fuzz {
+    fn new() -> Self {
+        let mut env = TestEnv::new();
+        let listing = env.register_service();
+        Self {
+            env,
+            listing,
+            tasks: Vec::new(),
+            settled: Vec::new(),
+            closed: Vec::new(),
+            donated: Vec::new(),
+        }
+    }
+
+    fn key(&self, role: Role) -> Pubkey {
+        self.keypair(role).pubkey()
+    }
+
+    fn keypair(&self, role: Role) -> &Keypair {
+        match role {
+            Role::Requester => &self.env.requester,
+            Role::Provider => &self.env.provider,
+            Role::Arbiter => &self.env.arbiter,
+            Role::Stranger => &self.env.stranger,
+        }
+    }
+
+    /// Send `ix` signed by `role`. The admin pays the transaction fee so the fee
+    /// never shows up in the lamport totals.
+    fn send(&mut self, ix: Instruction, role: Role) -> bool {
+        let env = &mut self.env;
+        let signer = match role {
+            Role::Requester => &env.requester,
+            Role::Provider => &env.provider,
+            Role::Arbiter => &env.arbiter,
+            Role::Stranger => &env.stranger,
+        };
+        env.svm.process(&[ix], &[&env.admin, signer]).is_ok()
+    }
+
+    /// Every account lamports can move between; the admin only pays fees.
+    fn total_lamports(&self) -> u64 {
+        let env = &self.env;
+        [
+            env.treasury.pubkey(),
+            env.provider.pubkey(),
+            env.requester.pubkey(),
+            env.arbiter.pubkey(),
+            env.stranger.pubkey(),
+            self.listing,
+            config_pda(),
+        ]
+        .iter()
+        .chain(&self.tasks)
+        .map(|address| env.svm.lamports(address))
+        .sum()
+    }
+
+    /// The instruction for a task step and the role that signs it.
+    fn instruction(&self, step: &Step, task: &Pubkey) -> (Instruction, Role) {
+        let env = &self.env;
+        match *step {
+            Step::Start(role, _) => (env.start_task_ix(&self.key(role), task), role),
+            Step::Cancel(role, _) => (env.cancel_task_ix(&self.key(role), task), role),
+            Step::Submit(role, _) => (env.submit_result_ix(&self.key(role), task, field(1)), role),
+            Step::Accept(role, _) => (env.accept_result_ix(&self.key(role), task), role),
+            Step::Dispute(role, _) => (env.dispute_task_ix(&self.key(role), task), role),
+            Step::AutoAccept(_) => (env.auto_accept_ix(task), Role::Stranger),
+            Step::Resolve(role, _, provider_bps) => (
+                env.resolve_dispute_ix(&self.key(role), task, provider_bps),
+                role,
+            ),
+            Step::Expire(_) => (env.expire_task_ix(task), Role::Stranger),
+            Step::Close(role, _) => (env.close_task_ix(&self.key(role), task), role),
+            Step::Donate(_, lamports) => (
+                system_instruction::transfer(&self.key(Role::Stranger), task, lamports),
+                Role::Stranger,
+            ),
+            Step::Create | Step::Warp(_) => unreachable!("not a task step"),
+        }
+    }
+
+    fn run(&mut self, step: &Step) -> Result<(), TestCaseError> {
+        match *step {
+            Step::Warp(seconds) => self.env.svm.warp(seconds),
+            Step::Create if self.tasks.len() < MAX_TASKS => {
+                let task_id = self.env.next_id();
+                let requester = self.key(Role::Requester);
+                let deadline = self.env.svm.now() + TASK_DURATION;
+                let ix = self
+                    .env
+                    .create_task_ix(&requester, &self.listing, task_id, deadline);
+                prop_assert!(self.send(ix, Role::Requester), "create_task failed");
+                self.tasks.push(task_pda(&requester, &task_id));
+                self.settled.push(None);
+                self.closed.push(false);
+                self.donated.push(0);
+            }
+            Step::Create => {}
+            _ => {
+                let index = step.task().unwrap();
+                if index >= self.tasks.len() || self.closed[index] {
+                    return Ok(());
+                }
+                let (ix, role) = self.instruction(step, &self.tasks[index]);
+                let before = self.total_lamports();
+                let succeeded = self.send(ix, role);
+                prop_assert_eq!(
+                    self.total_lamports(),
+                    before,
+                    "lamports not conserved by {:?}",
+                    step
+                );
+
+                let settled = self.settled[index].is_some();
+                match *step {
+                    Step::Close(..) => {
+                        prop_assert!(
+                            !succeeded || settled,
+                            "closed task {} while it held escrow",
+                            index
+                        );
+                        self.closed[index] = succeeded;
+                    }
+                    Step::Donate(_, lamports) => {
+                        prop_assert!(succeeded, "donation failed");
+                        self.donated[index] += lamports;
+                    }
+                    _ => prop_assert!(
+                        !(succeeded && settled),
+                        "{:?} succeeded on a settled task",
+                        step
+                    ),
+                }
+            }
+        }
+        self.check_settled()
+    }
+
+    /// A task that reached a terminal status keeps it and holds only its rent and
+    /// whatever was donated to it, and the provider's profile counts it once.
+    fn check_settled(&mut self) -> Result<(), TestCaseError> {
+        for (index, address) in self.tasks.iter().enumerate() {
+            if self.closed[index] {
+                prop_assert!(!self.env.svm.exists(address));
+                continue;
+            }
+            let status = self.env.task(address).status;
+            match self.settled[index] {
+                Some(settled) => {
+                    prop_assert!(status == settled, "task {} left its terminal status", index)
+                }
+                None if status.is_terminal() => self.settled[index] = Some(status),
+                None => continue,
+            }
+            let account = self.env.svm.get_account(address).unwrap();
+            let rent = self
+                .env
+                .svm
+                .minimum_balance_for_rent_exemption(account.data.len());
+            prop_assert_eq!(
+                account.lamports,
+                rent + self.donated[index],
+                "task {} settled with escrow left or donations paid out",
+                index
+            );
+        }
+
+        let profile: ProviderProfile = self.env.svm.read(&profile_pda(&self.env.provider.pubkey()));
+        let settled = |status: TaskStatus| {
+            self.settled
+                .iter()
+                .filter(|&&settled| settled == Some(status))
+                .count() as u64
+        };
+        prop_assert_eq!(profile.tasks_completed, settled(TaskStatus::Completed));
+        prop_assert_eq!(profile.tasks_disputed, settled(TaskStatus::Disputed));
+        prop_assert_eq!(profile.tasks_expired, settled(TaskStatus::Expired));
+        Ok(())
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn task_lifecycle_conserves_escrow(steps in prop::collection::vec(step(), 1..40)) {
+        let mut fuzz = Fuzz::new();
+        for step in &steps {
+            fuzz.run(step)?;
+        }
+    }
+}
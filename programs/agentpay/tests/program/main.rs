@@ -0,0 +1,33 @@
+//! Program tests: the compiled SBF binary runs in an in-process LiteSVM, so the whole
+//! suite runs offline with no validator. Build the program and the circuit fixtures
+//! first:
+//!
+//! ```sh
+//! anchor build
+//! circuits/build.sh
+//! cargo test -p agentpay
+//! ```
+//!
+//! Every `AgentPayError` in `errors.rs` is asserted somewhere below, except these
+//! five:
+//! - `InsufficientPayment` and `DescriptionTooLong`, which no instruction returns
+//!   (escrow always takes the listed price, descriptions are fixed-size arrays);
+//! - `UnauthorizedRequester` and `UnauthorizedServiceOwner`, whose `has_one` checks sit
+//!   behind PDA seeds derived from the signer, so a wrong signer fails `ConstraintSeeds`
+//!   first. The tests assert that instead;
+//! - `EscrowOverdrawn`, a guard no settlement path can reach since each pays out at most
+//!   the escrow it resolved.
+
+mod bounties;
+mod config;
+mod events;
+mod harness;
+mod lifecycle;
+mod milestones;
+mod profiles;
+mod proofs;
+mod reviews;
+mod services;
+mod tasks;
+mod tokens;
+mod zk;
@@ -0,0 +1,273 @@
+use agentpay::errors::AgentPayError;
+use agentpay::state::{Milestone, MilestoneStatus, ServiceListing, TaskStatus};
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+use crate::harness::*;
+
+const FIRST: u64 = PRICE * 4 / 10;
+const SECOND: u64 = PRICE - FIRST;
+
+/// An in-progress task split into two milestones covering the full escrow.
+fn milestone_task(env: &mut TestEnv) -> (Pubkey, Pubkey) {
+    let listing = env.register_service();
+    let task = env.create_task(&listing);
+    let requester = env.requester.pubkey();
+    let ixs = [
+        env.add_milestone_ix(&requester, &task, 0, FIRST),
+        env.add_milestone_ix(&requester, &task, 1, SECOND),
+    ];
+    assert_ok(env.svm.process(&ixs, &[&env.requester]));
+    let ix = env.start_task_ix(&env.provider.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    (listing, task)
+}
+
+fn submit(env: &mut TestEnv, task: &Pubkey, index: u8) {
+    let ix = env.submit_milestone_ix(&env.provider.pubkey(), task, index);
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+}
+
+#[test]
+fn add_milestone_checks_order_and_amount() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+    let task = env.create_task(&listing);
+    let requester = env.requester.pubkey();
+
+    let ix = env.add_milestone_ix(&requester, &task, 1, FIRST);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::InvalidMilestoneIndex,
+    );
+    for amount in [0, PRICE + 1] {
+        let ix = env.add_milestone_ix(&requester, &task, 0, amount);
+        assert_error(
+            env.svm.process(&[ix], &[&env.requester]),
+            AgentPayError::InvalidMilestoneAmount,
+        );
+    }
+
+    let ix = env.add_milestone_ix(&requester, &task, 0, FIRST);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let milestone: Milestone = env.svm.read(&milestone_pda(&task, 0));
+    assert_eq!(milestone.amount, FIRST);
+    assert!(milestone.status == MilestoneStatus::Pending);
+
+    // Work cannot start until the milestones cover the escrow
+    let ix = env.start_task_ix(&env.provider.pubkey(), &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.provider]),
+        AgentPayError::MilestonesNotFunded,
+    );
+}
+
+#[test]
+fn milestone_task_rejects_whole_task_results() {
+    let mut env = TestEnv::new();
+    let (_, task) = milestone_task(&mut env);
+
+    let ix = env.submit_result_ix(&env.provider.pubkey(), &task, field(1));
+    assert_error(
+        env.svm.process(&[ix], &[&env.provider]),
+        AgentPayError::TaskHasMilestones,
+    );
+}
+
+#[test]
+fn milestones_release_one_by_one_and_complete_the_task() {
+    let mut env = TestEnv::new();
+    let (listing, task) = milestone_task(&mut env);
+    let provider_before = env.svm.lamports(&env.provider.pubkey());
+
+    let ix = env.accept_milestone_ix(&env.requester.pubkey(), &task, 0);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::InvalidMilestoneStatus,
+    );
+
+    submit(&mut env, &task, 0);
+    let ix = env.submit_milestone_ix(&env.provider.pubkey(), &task, 0);
+    assert_error(
+        env.svm.process(&[ix], &[&env.provider]),
+        AgentPayError::InvalidMilestoneStatus,
+    );
+
+    let ix = env.accept_milestone_ix(&env.requester.pubkey(), &task, 0);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert!(env.task(&task).status == TaskStatus::InProgress);
+
+    submit(&mut env, &task, 1);
+    let ix = env.auto_accept_milestone_ix(&task, 1);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::ReviewWindowActive,
+    );
+    env.svm.warp(REVIEW_WINDOW + 1);
+    let ix = env.auto_accept_milestone_ix(&task, 1);
+    assert_ok(env.svm.process(&[ix], &[&env.stranger]));
+
+    assert_eq!(
+        env.svm.lamports(&env.provider.pubkey()),
+        provider_before + FIRST - protocol_fee(FIRST) + SECOND - protocol_fee(SECOND)
+    );
+    let state = env.task(&task);
+    assert!(state.status == TaskStatus::Completed);
+    assert_eq!(state.released_amount, PRICE);
+    assert_eq!(env.svm.read::<ServiceListing>(&listing).tasks_completed, 1);
+
+    for index in 0..2 {
+        let ix = env.close_milestone_ix(&env.requester.pubkey(), &task, index);
+        assert_ok(env.svm.process(&[ix], &[&env.requester]));
+        assert!(!env.svm.exists(&milestone_pda(&task, index)));
+    }
+}
+
+#[test]
+fn disputed_milestone_is_split_by_the_arbiter() {
+    let mut env = TestEnv::new();
+    let (_, task) = milestone_task(&mut env);
+    submit(&mut env, &task, 0);
+
+    let ix = env.dispute_milestone_ix(&env.requester.pubkey(), &task, 0);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    let ix = env.resolve_milestone_dispute_ix(&env.stranger.pubkey(), &task, 0, 5_000);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::UnauthorizedArbiter,
+    );
+    let ix = env.resolve_milestone_dispute_ix(&env.arbiter.pubkey(), &task, 0, 10_001);
+    assert_error(
+        env.svm.process(&[ix], &[&env.arbiter]),
+        AgentPayError::InvalidBasisPoints,
+    );
+
+    let requester_before = env.svm.lamports(&env.requester.pubkey());
+    let ix = env.resolve_milestone_dispute_ix(&env.arbiter.pubkey(), &task, 0, 5_000);
+    assert_ok(env.svm.process(&[ix], &[&env.arbiter]));
+
+    assert_eq!(
+        env.svm.lamports(&env.requester.pubkey()),
+        requester_before + FIRST / 2
+    );
+    let milestone: Milestone = env.svm.read(&milestone_pda(&task, 0));
+    assert!(milestone.status == MilestoneStatus::Resolved);
+
+    let ix = env.resolve_milestone_dispute_ix(&env.arbiter.pubkey(), &task, 0, 5_000);
+    assert_error(
+        env.svm.process(&[ix], &[&env.arbiter]),
+        AgentPayError::InvalidMilestoneStatus,
+    );
+}
+
+#[test]
+fn milestone_dispute_closes_with_the_review_window() {
+    let mut env = TestEnv::new();
+    let (_, task) = milestone_task(&mut env);
+    submit(&mut env, &task, 0);
+
+    env.svm.warp(REVIEW_WINDOW + 1);
+    let ix = env.dispute_milestone_ix(&env.requester.pubkey(), &task, 0);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::ReviewWindowClosed,
+    );
+}
+
+#[test]
+fn milestone_in_review_blocks_cancel_and_expiry() {
+    let mut env = TestEnv::new();
+    let (_, task) = milestone_task(&mut env);
+    submit(&mut env, &task, 0);
+
+    let ix = env.cancel_task_ix(&env.requester.pubkey(), &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::MilestonesInReview,
+    );
+
+    env.svm.warp(TASK_DURATION + 1);
+    let ix = env.expire_task_ix(&task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::MilestonesInReview,
+    );
+
+    let ix = env.close_milestone_ix(&env.requester.pubkey(), &task, 0);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::InvalidTaskStatus,
+    );
+}
+
+#[test]
+fn cancel_refunds_only_unreleased_milestones() {
+    let mut env = TestEnv::new();
+    let (_, task) = milestone_task(&mut env);
+    submit(&mut env, &task, 0);
+    let ix = env.accept_milestone_ix(&env.requester.pubkey(), &task, 0);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    let requester_before = env.svm.lamports(&env.requester.pubkey());
+    let ix = env.cancel_task_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.stranger, &env.requester]));
+
+    // The task was in progress, so the provider keeps the fee on what remained
+    let fee = SECOND * u64::from(CANCELLATION_FEE_BPS) / 10_000;
+    assert_eq!(
+        env.svm.lamports(&env.requester.pubkey()),
+        requester_before + SECOND - fee
+    );
+    assert!(env.task(&task).status == TaskStatus::Cancelled);
+}
+
+#[test]
+fn task_closes_only_after_its_milestones() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+    let requester = env.requester.pubkey();
+    let task_id = env.next_id();
+    let deadline = env.svm.now() + TASK_DURATION;
+    let create = env.create_task_ix(&requester, &listing, task_id, deadline);
+    assert_ok(
+        env.svm
+            .process(std::slice::from_ref(&create), &[&env.requester]),
+    );
+    let task = task_pda(&requester, &task_id);
+    let ixs = [
+        env.add_milestone_ix(&requester, &task, 0, FIRST),
+        env.add_milestone_ix(&requester, &task, 1, SECOND),
+    ];
+    assert_ok(env.svm.process(&ixs, &[&env.requester]));
+    let ix = env.cancel_task_ix(&requester, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert_eq!(env.task(&task).milestones_open, 2);
+
+    // Pending milestones would outlive the task and attach to one reusing its id
+    for index in 0..2 {
+        let ix = env.close_task_ix(&requester, &task);
+        assert_error(
+            env.svm.process(&[ix], &[&env.requester]),
+            AgentPayError::MilestonesNotClosed,
+        );
+        let ix = env.close_milestone_ix(&requester, &task, index);
+        assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    }
+    let ix = env.close_task_ix(&requester, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert!(!env.svm.exists(&task));
+
+    // The recreated task starts without milestones and can add its own
+    assert_ok(env.svm.process(&[create], &[&env.requester]));
+    let state = env.task(&task);
+    assert_eq!((state.milestone_count, state.milestones_open), (0, 0));
+    assert!(!env.svm.exists(&milestone_pda(&task, 0)));
+    let ix = env.submit_milestone_ix(&env.provider.pubkey(), &task, 0);
+    assert!(env.svm.process(&[ix], &[&env.provider]).is_err());
+    let ix = env.add_milestone_ix(&requester, &task, 0, PRICE);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert_eq!(
+        env.svm.read::<Milestone>(&milestone_pda(&task, 0)).amount,
+        PRICE
+    );
+}
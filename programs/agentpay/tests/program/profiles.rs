@@ -0,0 +1,120 @@
+use agentpay::state::{ProviderProfile, ServiceListing, TaskStatus};
+use solana_sdk::{account::Account, signature::Signer};
+
+use crate::harness::*;
+
+fn profile(env: &TestEnv) -> ProviderProfile {
+    env.svm.read(&profile_pda(&env.provider.pubkey()))
+}
+
+/// What the provider keeps of a payout of `amount` once the protocol fee is withheld.
+fn net(amount: u64) -> u64 {
+    amount - protocol_fee(amount)
+}
+
+#[test]
+fn profile_counts_every_outcome_across_listings() {
+    let mut env = TestEnv::new();
+    let first = env.register_service();
+    let state = profile(&env);
+    assert_eq!(state.provider, env.provider.pubkey());
+    assert_eq!(state.created_at, env.svm.now());
+    assert_eq!(state.tasks_completed, 0);
+
+    let task = env.create_task(&first);
+    let ix = env.submit_result_ix(&env.provider.pubkey(), &task, field(1));
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    let ix = env.accept_result_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    // A fresh listing starts its own counter but shares the provider's profile
+    let second = env.register_service();
+    assert_eq!(env.svm.read::<ServiceListing>(&second).tasks_completed, 0);
+    let task = env.create_task(&second);
+    let ix = env.submit_result_ix(&env.provider.pubkey(), &task, field(2));
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    let ix = env.dispute_task_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let ix = env.resolve_dispute_ix(&env.arbiter.pubkey(), &task, 6_000);
+    assert_ok(env.svm.process(&[ix], &[&env.arbiter]));
+
+    let task = env.create_task(&second);
+    env.svm.warp(TASK_DURATION + 1);
+    let ix = env.expire_task_ix(&task);
+    assert_ok(env.svm.process(&[ix], &[&env.stranger]));
+
+    let state = profile(&env);
+    assert_eq!(state.tasks_completed, 1);
+    assert_eq!(state.tasks_disputed, 1);
+    assert_eq!(state.tasks_expired, 1);
+    assert_eq!(state.total_earned, net(PRICE) + net(PRICE * 6 / 10));
+}
+
+#[test]
+fn profile_counts_a_milestone_task_once_and_books_cancellation_fees() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+    let requester = env.requester.pubkey();
+    let provider = env.provider.pubkey();
+
+    let task = env.create_task(&listing);
+    let ixs = [
+        env.add_milestone_ix(&requester, &task, 0, PRICE / 2),
+        env.add_milestone_ix(&requester, &task, 1, PRICE / 2),
+    ];
+    assert_ok(env.svm.process(&ixs, &[&env.requester]));
+    let ix = env.start_task_ix(&provider, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+
+    for index in 0..2 {
+        let ix = env.submit_milestone_ix(&provider, &task, index);
+        assert_ok(env.svm.process(&[ix], &[&env.provider]));
+        let ix = env.accept_milestone_ix(&requester, &task, index);
+        assert_ok(env.svm.process(&[ix], &[&env.requester]));
+        let completed = u64::from(index == 1);
+        assert_eq!(profile(&env).tasks_completed, completed);
+    }
+    assert!(env.task(&task).status == TaskStatus::Completed);
+    assert_eq!(profile(&env).total_earned, 2 * net(PRICE / 2));
+
+    // A cancellation after the start pays the provider a fee but is no outcome of theirs
+    let task = env.create_task(&listing);
+    let ix = env.start_task_ix(&provider, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    let ix = env.cancel_task_ix(&requester, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    let fee = PRICE * u64::from(CANCELLATION_FEE_BPS) / 10_000;
+    let state = profile(&env);
+    assert_eq!(state.total_earned, 2 * net(PRICE / 2) + net(fee));
+    assert_eq!(
+        (
+            state.tasks_completed,
+            state.tasks_disputed,
+            state.tasks_expired
+        ),
+        (1, 0, 0)
+    );
+}
+
+#[test]
+fn create_task_backfills_a_missing_profile() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+
+    // A listing registered before profiles existed
+    let address = profile_pda(&env.provider.pubkey());
+    env.svm.set_account(address, Account::default()).unwrap();
+    assert!(!env.svm.exists(&address));
+
+    let task = env.create_task(&listing);
+    let state = profile(&env);
+    assert_eq!(state.provider, env.provider.pubkey());
+    assert_eq!(state.tasks_completed, 0);
+
+    let ix = env.submit_result_ix(&env.provider.pubkey(), &task, field(1));
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    let ix = env.accept_result_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert_eq!(profile(&env).tasks_completed, 1);
+}
@@ -0,0 +1,232 @@
+//! Real Groth16 proofs, one per circuit, written by `circuits/build.sh` from the
+//! circuits' zkeys to `tests/fixtures/<circuit>.json`. Every other ZK test runs
+//! against the degenerate zero keys; these check that a proof from the actual
+//! trusted setup verifies, and stops verifying when any one public input changes.
+
+use agentpay::errors::AgentPayError;
+use agentpay::state::{TaskStatus, VerifyingKeyAccount, ZkCircuit};
+use agentpay::zk;
+use serde::Deserialize;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Signer,
+    signer::keypair::keypair_from_seed,
+};
+
+use crate::harness::*;
+
+/// Version the fixture keys are registered under.
+const VK_VERSION: u16 = 2;
+
+#[derive(Deserialize)]
+struct Fixture {
+    requester_seed: Vec<u8>,
+    provider_seed: Vec<u8>,
+    task_id: Vec<u8>,
+    task: Vec<u8>,
+    provider: Vec<u8>,
+    vk: FixtureKey,
+    proof: FixtureProof,
+    public_inputs: Vec<Vec<u8>>,
+}
+
+#[derive(Deserialize)]
+struct FixtureKey {
+    alpha_g1: Vec<u8>,
+    beta_g2: Vec<u8>,
+    gamma_g2: Vec<u8>,
+    delta_g2: Vec<u8>,
+    ic: Vec<Vec<u8>>,
+}
+
+#[derive(Deserialize)]
+struct FixtureProof {
+    a: Vec<u8>,
+    b: Vec<u8>,
+    c: Vec<u8>,
+}
+
+type Verify<const N: usize> = fn(
+    &VerifyingKeyAccount,
+    &[u8; 64],
+    &[u8; 128],
+    &[u8; 64],
+    &[[u8; 32]; N],
+) -> anchor_lang::Result<()>;
+
+fn bytes<const N: usize>(value: &[u8]) -> [u8; N] {
+    value
+        .try_into()
+        .expect("fixture field has the wrong length")
+}
+
+impl Fixture {
+    fn load(circuit: &str) -> Self {
+        let path = format!(
+            "{}/tests/fixtures/{circuit}.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let json = std::fs::read(&path)
+            .unwrap_or_else(|_| panic!("{path} is missing; run circuits/build.sh first"));
+        serde_json::from_slice(&json).expect("malformed proof fixture")
+    }
+
+    fn key(&self, circuit: ZkCircuit) -> VerifyingKeyAccount {
+        VerifyingKeyAccount {
+            circuit,
+            version: VK_VERSION,
+            nr_pubinputs: self.public_inputs.len() as u8,
+            alpha_g1: bytes(&self.vk.alpha_g1),
+            beta_g2: bytes(&self.vk.beta_g2),
+            gamma_g2: bytes(&self.vk.gamma_g2),
+            delta_g2: bytes(&self.vk.delta_g2),
+            ic: self.vk.ic.iter().map(|point| bytes(point)).collect(),
+            revoked: false,
+            created_at: 0,
+            bump: 0,
+        }
+    }
+
+    fn proof(&self) -> Proof {
+        Proof {
+            a: bytes(&self.proof.a),
+            b: bytes(&self.proof.b),
+            c: bytes(&self.proof.c),
+        }
+    }
+
+    fn input(&self, index: usize) -> [u8; 32] {
+        bytes(&self.public_inputs[index])
+    }
+
+    fn inputs<const N: usize>(&self) -> [[u8; 32]; N] {
+        assert_eq!(self.public_inputs.len(), N);
+        std::array::from_fn(|i| self.input(i))
+    }
+
+    fn task(&self) -> Pubkey {
+        Pubkey::new_from_array(bytes(&self.task))
+    }
+
+    fn provider(&self) -> Pubkey {
+        Pubkey::new_from_array(bytes(&self.provider))
+    }
+}
+
+/// The fixture proof verifies against `inputs`, and fails with each input in turn
+/// changed by one bit.
+fn assert_binds_every_input<const N: usize>(
+    fixture: &Fixture,
+    circuit: ZkCircuit,
+    inputs: [[u8; 32]; N],
+    verify: Verify<N>,
+) {
+    let key = fixture.key(circuit);
+    let proof = fixture.proof();
+    verify(&key, &proof.a, &proof.b, &proof.c, &inputs).expect("fixture proof must verify");
+
+    for i in 0..N {
+        let mut changed = inputs;
+        changed[i][31] ^= 1;
+        assert!(
+            verify(&key, &proof.a, &proof.b, &proof.c, &changed).is_err(),
+            "proof still verifies with public input {i} changed"
+        );
+    }
+}
+
+#[test]
+fn task_verify_proof_binds_every_input() {
+    let fixture = Fixture::load("task_verify_v2");
+    let inputs = zk::task_proof_inputs(fixture.input(0), &fixture.task(), &fixture.provider());
+    assert_eq!(inputs, fixture.inputs());
+
+    assert_binds_every_input(
+        &fixture,
+        ZkCircuit::TaskVerify,
+        inputs,
+        zk::verify_task_proof,
+    );
+}
+
+#[test]
+fn chunked_task_proof_binds_every_input() {
+    let fixture = Fixture::load("task_verify_chunked");
+    let chunk_count = u32::from_be_bytes(bytes(&fixture.input(1)[28..]));
+    let inputs = zk::chunked_task_proof_inputs(
+        fixture.input(0),
+        chunk_count,
+        &fixture.task(),
+        &fixture.provider(),
+    );
+    assert_eq!(inputs, fixture.inputs());
+
+    assert_binds_every_input(
+        &fixture,
+        ZkCircuit::TaskVerifyChunked,
+        inputs,
+        zk::verify_chunked_task_proof,
+    );
+}
+
+#[test]
+fn delivery_proof_binds_every_input() {
+    let fixture = Fixture::load("result_delivery");
+    let point =
+        |x: usize| -> [u8; 64] { bytes(&[fixture.input(x), fixture.input(x + 1)].concat()) };
+    let inputs = zk::delivery_proof_inputs(
+        fixture.input(0),
+        fixture.input(1),
+        &point(2),
+        &point(4),
+        &fixture.task(),
+        &fixture.provider(),
+    );
+    assert_eq!(inputs, fixture.inputs());
+
+    assert_binds_every_input(
+        &fixture,
+        ZkCircuit::ResultDelivery,
+        inputs,
+        zk::verify_delivery_proof,
+    );
+}
+
+#[test]
+fn submit_result_zk_accepts_a_real_proof() {
+    let fixture = Fixture::load("task_verify_v2");
+    let mut env = TestEnv::new();
+    env.requester = keypair_from_seed(&fixture.requester_seed).unwrap();
+    env.provider = keypair_from_seed(&fixture.provider_seed).unwrap();
+    for key in [env.requester.pubkey(), env.provider.pubkey()] {
+        env.svm.airdrop(&key, 100 * LAMPORTS_PER_SOL).unwrap();
+    }
+    let vk = env.register_vk(&fixture.key(ZkCircuit::TaskVerify));
+    let listing = env.register_service();
+
+    let task_id = bytes(&fixture.task_id);
+    let deadline = env.svm.now() + TASK_DURATION;
+    let requester = env.requester.pubkey();
+    let ix = env.create_task_ix(&requester, &listing, task_id, deadline);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let task = task_pda(&requester, &task_id);
+    assert_eq!(task, fixture.task());
+
+    let provider = env.provider.pubkey();
+    let proof = fixture.proof();
+    let result_hash = fixture.input(0);
+    let mut wrong_hash = result_hash;
+    wrong_hash[31] ^= 1;
+    let ixs = env.submit_result_zk_ixs(&provider, &task, &vk, &proof, wrong_hash, 1);
+    assert_error(
+        env.svm.process(&ixs, &[&env.provider]),
+        AgentPayError::ZkProofVerificationFailed,
+    );
+
+    let ixs = env.submit_result_zk_ixs(&provider, &task, &vk, &proof, result_hash, 1);
+    assert_ok(env.svm.process(&ixs, &[&env.provider]));
+    let state = env.task(&task);
+    assert!(state.status == TaskStatus::Submitted);
+    assert!(state.zk_verified);
+    assert_eq!(state.zk_vk_version, VK_VERSION);
+    assert_eq!(state.result_hash, result_hash);
+}
@@ -0,0 +1,130 @@
+use agentpay::errors::AgentPayError;
+use agentpay::events::ReviewSubmitted;
+use agentpay::state::{ProviderProfile, Review, TaskStatus};
+use anchor_lang::error::ErrorCode;
+use solana_sdk::signature::Signer;
+
+use crate::harness::*;
+
+fn profile(env: &TestEnv) -> ProviderProfile {
+    env.svm.read(&profile_pda(&env.provider.pubkey()))
+}
+
+#[test]
+fn review_records_the_score_once_and_updates_the_average() {
+    let mut env = TestEnv::new();
+    let (listing, task) = env.submitted_task();
+    let requester = env.requester.pubkey();
+
+    let ix = env.accept_result_ix(&requester, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let ix = env.submit_review_ix(&requester, &task, 4, field(11));
+    let meta = assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    let review: Review = env.svm.read(&review_pda(&task));
+    assert_eq!(review.task, task);
+    assert_eq!(review.reviewer, requester);
+    assert_eq!(review.provider, env.provider.pubkey());
+    assert_eq!(review.service_listing, listing);
+    assert_eq!((review.score, review.review_hash), (4, field(11)));
+    assert_eq!(review.created_at, env.svm.now());
+    assert_eq!(event::<ReviewSubmitted>(&meta).score, 4);
+
+    // The review PDA is keyed by the task, so a second review cannot be created
+    let ix = env.submit_review_ix(&requester, &task, 1, field(12));
+    assert!(env.svm.process(&[ix], &[&env.requester]).is_err());
+    assert_eq!(env.svm.read::<Review>(&review_pda(&task)).score, 4);
+
+    // A disputed task can be reviewed once the arbiter has ruled
+    let (_, task) = env.submitted_task();
+    let ix = env.dispute_task_ix(&requester, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let ix = env.submit_review_ix(&requester, &task, 1, field(13));
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::InvalidTaskStatus,
+    );
+    let ix = env.resolve_dispute_ix(&env.arbiter.pubkey(), &task, 5_000);
+    assert_ok(env.svm.process(&[ix], &[&env.arbiter]));
+    let ix = env.submit_review_ix(&requester, &task, 1, field(13));
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    let state = profile(&env);
+    assert_eq!((state.review_count, state.review_score_total), (2, 5));
+}
+
+#[test]
+fn only_the_requester_reviews_a_settled_task_with_a_valid_score() {
+    let mut env = TestEnv::new();
+    let (_, task) = env.submitted_task();
+    let requester = env.requester.pubkey();
+
+    let ix = env.submit_review_ix(&requester, &task, 5, field(1));
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::InvalidTaskStatus,
+    );
+
+    let ix = env.accept_result_ix(&requester, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    for score in [0, 6] {
+        let ix = env.submit_review_ix(&requester, &task, score, field(1));
+        assert_error(
+            env.svm.process(&[ix], &[&env.requester]),
+            AgentPayError::InvalidReviewScore,
+        );
+    }
+
+    // Neither the provider nor a stranger was the task's requester
+    for signer in [&env.provider, &env.stranger] {
+        let ix = env.submit_review_ix(&signer.pubkey(), &task, 5, field(1));
+        assert_error(
+            env.svm.process(&[ix], &[signer]),
+            ErrorCode::ConstraintSeeds,
+        );
+    }
+    assert!(!env.svm.exists(&review_pda(&task)));
+    assert_eq!(profile(&env).review_count, 0);
+}
+
+#[test]
+fn a_review_is_closed_with_its_task() {
+    let mut env = TestEnv::new();
+    let (listing, task) = env.submitted_task();
+    let requester = env.requester.pubkey();
+    let task_id = env.task(&task).task_id;
+
+    let ix = env.accept_result_ix(&requester, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    // Built before the review exists, so it leaves the review out
+    let stale_close = env.close_task_ix(&requester, &task);
+    let ix = env.submit_review_ix(&requester, &task, 5, field(1));
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert!(env.task(&task).review_open);
+
+    // Leaving the review behind would block reviews of a new task with the same id
+    assert_error(
+        env.svm.process(&[stale_close], &[&env.requester]),
+        AgentPayError::ReviewRequired,
+    );
+
+    let ix = env.close_task_ix(&requester, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert!(!env.svm.exists(&task));
+    assert!(!env.svm.exists(&review_pda(&task)));
+
+    let deadline = env.svm.now() + TASK_DURATION;
+    let ix = env.create_task_ix(&requester, &listing, task_id, deadline);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert!(!env.task(&task).review_open);
+    let ix = env.submit_result_ix(&env.provider.pubkey(), &task, field(2));
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    let ix = env.accept_result_ix(&requester, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert!(env.task(&task).status == TaskStatus::Completed);
+
+    let ix = env.submit_review_ix(&requester, &task, 3, field(3));
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert_eq!(env.svm.read::<Review>(&review_pda(&task)).score, 3);
+    assert_eq!(profile(&env).review_count, 2);
+}
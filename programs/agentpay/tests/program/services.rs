@@ -0,0 +1,132 @@
+use agentpay::errors::AgentPayError;
+use agentpay::state::{ServiceListing, MAX_REVIEW_WINDOW};
+use anchor_lang::error::ErrorCode;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+use crate::harness::*;
+
+#[test]
+fn register_service_stores_the_terms() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+
+    let state: ServiceListing = env.svm.read(&listing);
+    assert_eq!(state.provider, env.provider.pubkey());
+    assert_eq!(state.price_lamports, PRICE);
+    assert_eq!(state.arbiter, env.arbiter.pubkey());
+    assert_eq!(state.review_window, REVIEW_WINDOW);
+    assert_eq!(state.cancellation_fee_bps, CANCELLATION_FEE_BPS);
+    assert_eq!(state.mint, Pubkey::default());
+    assert_eq!(state.tasks_completed, 0);
+    assert!(state.is_active);
+}
+
+#[test]
+fn register_service_validates_terms() {
+    let mut env = TestEnv::new();
+    let provider = env.provider.pubkey();
+
+    for arbiter in [Pubkey::default(), provider] {
+        let terms = ServiceTerms {
+            arbiter,
+            ..env.default_terms()
+        };
+        let service_id = env.next_id();
+        let ix = env.register_service_ix(&provider, service_id, &terms);
+        assert_error(
+            env.svm.process(&[ix], &[&env.provider]),
+            AgentPayError::InvalidArbiter,
+        );
+    }
+
+    // A window past the cap would overflow the review deadline
+    for review_window in [0, MAX_REVIEW_WINDOW + 1, i64::MAX] {
+        let terms = ServiceTerms {
+            review_window,
+            ..env.default_terms()
+        };
+        let service_id = env.next_id();
+        let ix = env.register_service_ix(&provider, service_id, &terms);
+        assert_error(
+            env.svm.process(&[ix], &[&env.provider]),
+            AgentPayError::InvalidReviewWindow,
+        );
+    }
+
+    let terms = ServiceTerms {
+        cancellation_fee_bps: 10_001,
+        ..env.default_terms()
+    };
+    let service_id = env.next_id();
+    let ix = env.register_service_ix(&provider, service_id, &terms);
+    assert_error(
+        env.svm.process(&[ix], &[&env.provider]),
+        AgentPayError::InvalidBasisPoints,
+    );
+}
+
+#[test]
+fn update_service_changes_only_the_given_fields() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+
+    let ix = env.update_service_ix(
+        &env.provider.pubkey(),
+        &listing,
+        Some(2 * PRICE),
+        None,
+        None,
+    );
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+
+    let state: ServiceListing = env.svm.read(&listing);
+    assert_eq!(state.price_lamports, 2 * PRICE);
+    assert_eq!(state.cancellation_fee_bps, CANCELLATION_FEE_BPS);
+
+    let ix = env.update_service_ix(&env.provider.pubkey(), &listing, None, None, Some(10_001));
+    assert_error(
+        env.svm.process(&[ix], &[&env.provider]),
+        AgentPayError::InvalidBasisPoints,
+    );
+}
+
+#[test]
+fn only_the_provider_can_change_a_listing() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+    let stranger = env.stranger.pubkey();
+
+    // The listing PDA is derived from the signer, so the seeds check fails first
+    for ix in [
+        env.update_service_ix(&stranger, &listing, Some(1), None, None),
+        env.deactivate_service_ix(&stranger, &listing),
+        env.reactivate_service_ix(&stranger, &listing),
+    ] {
+        assert_error(
+            env.svm.process(&[ix], &[&env.stranger]),
+            ErrorCode::ConstraintSeeds,
+        );
+    }
+}
+
+#[test]
+fn deactivated_listing_takes_no_tasks_until_reactivated() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+
+    let ix = env.deactivate_service_ix(&env.provider.pubkey(), &listing);
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    assert!(!env.svm.read::<ServiceListing>(&listing).is_active);
+
+    let task_id = env.next_id();
+    let deadline = env.svm.now() + TASK_DURATION;
+    let ix = env.create_task_ix(&env.requester.pubkey(), &listing, task_id, deadline);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::ServiceNotActive,
+    );
+
+    let ix = env.reactivate_service_ix(&env.provider.pubkey(), &listing);
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    env.create_task(&listing);
+}
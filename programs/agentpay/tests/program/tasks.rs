@@ -0,0 +1,465 @@
+use agentpay::errors::AgentPayError;
+use agentpay::state::{ProviderProfile, ServiceListing, TaskStatus, ARBITRATION_WINDOW};
+use anchor_lang::error::ErrorCode;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signature::Signer};
+
+use crate::harness::*;
+
+#[test]
+fn create_task_locks_the_price_in_escrow() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+    let task = env.create_task(&listing);
+
+    let state = env.task(&task);
+    assert!(state.status == TaskStatus::Open);
+    assert_eq!(state.amount_lamports, PRICE);
+    assert_eq!(state.requester, env.requester.pubkey());
+    assert_eq!(state.provider, env.provider.pubkey());
+    assert_eq!(state.arbiter, env.arbiter.pubkey());
+    assert_eq!(state.protocol_fee_bps, PROTOCOL_FEE_BPS);
+
+    let rent = env
+        .svm
+        .minimum_balance_for_rent_exemption(agentpay::state::TaskRequest::SIZE);
+    assert_eq!(env.svm.lamports(&task), rent + PRICE);
+}
+
+#[test]
+fn create_task_rejects_a_past_deadline() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+
+    let task_id = env.next_id();
+    let deadline = env.svm.now();
+    let ix = env.create_task_ix(&env.requester.pubkey(), &listing, task_id, deadline);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::DeadlineInPast,
+    );
+}
+
+#[test]
+fn create_task_needs_the_listing_reputation_bar() {
+    let mut env = TestEnv::new();
+    let terms = ServiceTerms {
+        min_reputation: 3,
+        ..env.default_terms()
+    };
+    let listing = env.register_service_with(&terms);
+    let requester = env.requester.pubkey();
+
+    let task_id = env.next_id();
+    let deadline = env.svm.now() + TASK_DURATION;
+    let ix = env.create_task_ix(&requester, &listing, task_id, deadline);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::ReputationTooLow,
+    );
+
+    let profile = profile_pda(&env.provider.pubkey());
+    let mut state: ProviderProfile = env.svm.read(&profile);
+    state.tasks_completed = 3;
+    env.svm.write(&profile, &state);
+    env.create_task(&listing);
+}
+
+#[test]
+fn only_the_provider_starts_and_submits() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+    let task = env.create_task(&listing);
+
+    let ix = env.start_task_ix(&env.stranger.pubkey(), &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::UnauthorizedProvider,
+    );
+    let ix = env.submit_result_ix(&env.stranger.pubkey(), &task, field(1));
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::UnauthorizedProvider,
+    );
+
+    let ix = env.start_task_ix(&env.provider.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    assert!(env.task(&task).status == TaskStatus::InProgress);
+
+    let ix = env.start_task_ix(&env.provider.pubkey(), &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.provider]),
+        AgentPayError::InvalidTaskStatus,
+    );
+
+    let ix = env.submit_result_ix(&env.provider.pubkey(), &task, field(1));
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    let state = env.task(&task);
+    assert!(state.status == TaskStatus::Submitted);
+    assert_eq!(state.result_hash, field(1));
+    assert!(!state.zk_verified);
+}
+
+#[test]
+fn accept_result_pays_provider_and_treasury() {
+    let mut env = TestEnv::new();
+    let (listing, task) = env.submitted_task();
+    let provider_before = env.svm.lamports(&env.provider.pubkey());
+    let treasury_before = env.svm.lamports(&env.treasury.pubkey());
+
+    let ix = env.accept_result_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    assert_eq!(
+        env.svm.lamports(&env.provider.pubkey()),
+        provider_before + PRICE - protocol_fee(PRICE)
+    );
+    assert_eq!(
+        env.svm.lamports(&env.treasury.pubkey()),
+        treasury_before + protocol_fee(PRICE)
+    );
+    assert!(env.task(&task).status == TaskStatus::Completed);
+    assert_eq!(env.svm.read::<ServiceListing>(&listing).tasks_completed, 1);
+
+    let ix = env.accept_result_ix(&env.requester.pubkey(), &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::InvalidTaskStatus,
+    );
+}
+
+#[test]
+fn only_the_requester_reviews() {
+    let mut env = TestEnv::new();
+    let (_, task) = env.submitted_task();
+    let stranger = env.stranger.pubkey();
+
+    // The task PDA is derived from the signer, so the seeds check fails first
+    for ix in [
+        env.accept_result_ix(&stranger, &task),
+        env.dispute_task_ix(&stranger, &task),
+        env.cancel_task_ix(&stranger, &task),
+        env.close_task_ix(&stranger, &task),
+    ] {
+        assert_error(
+            env.svm.process(&[ix], &[&env.stranger]),
+            ErrorCode::ConstraintSeeds,
+        );
+    }
+}
+
+#[test]
+fn cancel_refunds_an_open_task_in_full() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+    let task = env.create_task(&listing);
+    let requester_before = env.svm.lamports(&env.requester.pubkey());
+
+    // The stranger pays the fee so the requester's balance moves by the refund only
+    let ix = env.cancel_task_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.stranger, &env.requester]));
+
+    assert_eq!(
+        env.svm.lamports(&env.requester.pubkey()),
+        requester_before + PRICE
+    );
+    assert!(env.task(&task).status == TaskStatus::Cancelled);
+}
+
+#[test]
+fn cancel_after_start_pays_the_cancellation_fee() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+    let task = env.create_task(&listing);
+    let ix = env.start_task_ix(&env.provider.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+
+    let requester_before = env.svm.lamports(&env.requester.pubkey());
+    let provider_before = env.svm.lamports(&env.provider.pubkey());
+    let treasury_before = env.svm.lamports(&env.treasury.pubkey());
+
+    let ix = env.cancel_task_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.stranger, &env.requester]));
+
+    let fee = PRICE * u64::from(CANCELLATION_FEE_BPS) / 10_000;
+    assert_eq!(
+        env.svm.lamports(&env.requester.pubkey()),
+        requester_before + PRICE - fee
+    );
+    assert_eq!(
+        env.svm.lamports(&env.provider.pubkey()),
+        provider_before + fee - protocol_fee(fee)
+    );
+    assert_eq!(
+        env.svm.lamports(&env.treasury.pubkey()),
+        treasury_before + protocol_fee(fee)
+    );
+
+    let ix = env.cancel_task_ix(&env.requester.pubkey(), &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::InvalidTaskStatus,
+    );
+}
+
+#[test]
+fn arbiter_splits_a_disputed_escrow() {
+    let mut env = TestEnv::new();
+    let (_, task) = env.submitted_task();
+
+    let ix = env.dispute_task_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert!(env.task(&task).status == TaskStatus::DisputePending);
+
+    let ix = env.resolve_dispute_ix(&env.stranger.pubkey(), &task, 5_000);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::UnauthorizedArbiter,
+    );
+    let ix = env.resolve_dispute_ix(&env.arbiter.pubkey(), &task, 10_001);
+    assert_error(
+        env.svm.process(&[ix], &[&env.arbiter]),
+        AgentPayError::InvalidBasisPoints,
+    );
+
+    let requester_before = env.svm.lamports(&env.requester.pubkey());
+    let provider_before = env.svm.lamports(&env.provider.pubkey());
+
+    let ix = env.resolve_dispute_ix(&env.arbiter.pubkey(), &task, 6_000);
+    assert_ok(env.svm.process(&[ix], &[&env.arbiter]));
+
+    let provider_share = PRICE * 6 / 10;
+    assert_eq!(
+        env.svm.lamports(&env.provider.pubkey()),
+        provider_before + provider_share - protocol_fee(provider_share)
+    );
+    assert_eq!(
+        env.svm.lamports(&env.requester.pubkey()),
+        requester_before + PRICE - provider_share
+    );
+    assert!(env.task(&task).status == TaskStatus::Disputed);
+
+    let ix = env.resolve_dispute_ix(&env.arbiter.pubkey(), &task, 6_000);
+    assert_error(
+        env.svm.process(&[ix], &[&env.arbiter]),
+        AgentPayError::InvalidTaskStatus,
+    );
+}
+
+#[test]
+fn unresolved_dispute_expires_to_a_refund() {
+    let mut env = TestEnv::new();
+    let (_, task) = env.submitted_task();
+
+    let ix = env.expire_dispute_ix(&env.requester.pubkey(), &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::InvalidTaskStatus,
+    );
+
+    let ix = env.dispute_task_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    env.svm.warp(ARBITRATION_WINDOW);
+    let ix = env.expire_dispute_ix(&env.provider.pubkey(), &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.provider]),
+        AgentPayError::ArbitrationWindowActive,
+    );
+
+    env.svm.warp(1);
+    let ix = env.expire_dispute_ix(&env.stranger.pubkey(), &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::UnauthorizedDisputeParty,
+    );
+
+    let requester_before = env.svm.lamports(&env.requester.pubkey());
+    let ix = env.expire_dispute_ix(&env.provider.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+
+    assert_eq!(
+        env.svm.lamports(&env.requester.pubkey()),
+        requester_before + PRICE
+    );
+    assert!(env.task(&task).status == TaskStatus::Disputed);
+    let profile: ProviderProfile = env.svm.read(&profile_pda(&env.provider.pubkey()));
+    assert_eq!(profile.tasks_disputed, 1);
+
+    let ix = env.resolve_dispute_ix(&env.arbiter.pubkey(), &task, 6_000);
+    assert_error(
+        env.svm.process(&[ix], &[&env.arbiter]),
+        AgentPayError::InvalidTaskStatus,
+    );
+}
+
+#[test]
+fn dispute_closes_with_the_review_window() {
+    let mut env = TestEnv::new();
+    let (_, task) = env.submitted_task();
+
+    env.svm.warp(REVIEW_WINDOW + 1);
+    let ix = env.dispute_task_ix(&env.requester.pubkey(), &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::ReviewWindowClosed,
+    );
+}
+
+#[test]
+fn auto_accept_waits_for_the_review_window() {
+    let mut env = TestEnv::new();
+    let (listing, task) = env.submitted_task();
+
+    let ix = env.auto_accept_ix(&task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::ReviewWindowActive,
+    );
+
+    env.svm.warp(REVIEW_WINDOW + 1);
+    let provider_before = env.svm.lamports(&env.provider.pubkey());
+    let ix = env.auto_accept_ix(&task);
+    assert_ok(env.svm.process(&[ix], &[&env.stranger]));
+
+    assert_eq!(
+        env.svm.lamports(&env.provider.pubkey()),
+        provider_before + PRICE - protocol_fee(PRICE)
+    );
+    assert!(env.task(&task).status == TaskStatus::Completed);
+    assert_eq!(env.svm.read::<ServiceListing>(&listing).tasks_completed, 1);
+}
+
+#[test]
+fn auto_accept_reports_a_completed_counter_overflow() {
+    let mut env = TestEnv::new();
+    let (listing, task) = env.submitted_task();
+
+    let mut state: ServiceListing = env.svm.read(&listing);
+    state.tasks_completed = u64::MAX;
+    env.svm.write(&listing, &state);
+
+    env.svm.warp(REVIEW_WINDOW + 1);
+    let ix = env.auto_accept_ix(&task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::MathOverflow,
+    );
+}
+
+#[test]
+fn deadline_gates_submission_and_expiry() {
+    let mut env = TestEnv::new();
+    let listing = env.register_service();
+    let task = env.create_task(&listing);
+
+    let ix = env.expire_task_ix(&task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::DeadlineNotReached,
+    );
+
+    env.svm.warp(TASK_DURATION + 1);
+    let ix = env.submit_result_ix(&env.provider.pubkey(), &task, field(1));
+    assert_error(
+        env.svm.process(&[ix], &[&env.provider]),
+        AgentPayError::DeadlinePassed,
+    );
+    let ix = env.start_task_ix(&env.provider.pubkey(), &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.provider]),
+        AgentPayError::DeadlinePassed,
+    );
+
+    let requester_before = env.svm.lamports(&env.requester.pubkey());
+    let ix = env.expire_task_ix(&task);
+    assert_ok(env.svm.process(&[ix], &[&env.stranger]));
+
+    assert_eq!(
+        env.svm.lamports(&env.requester.pubkey()),
+        requester_before + PRICE
+    );
+    assert!(env.task(&task).status == TaskStatus::Expired);
+
+    let ix = env.expire_task_ix(&task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::InvalidTaskStatus,
+    );
+}
+
+#[test]
+fn close_task_returns_rent_once_settled() {
+    let mut env = TestEnv::new();
+    let (_, task) = env.submitted_task();
+
+    let ix = env.close_task_ix(&env.requester.pubkey(), &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::InvalidTaskStatus,
+    );
+
+    let ix = env.accept_result_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    let rent = env.svm.lamports(&task);
+    let requester_before = env.svm.lamports(&env.requester.pubkey());
+    let ix = env.close_task_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.stranger, &env.requester]));
+
+    assert!(!env.svm.exists(&task));
+    assert_eq!(
+        env.svm.lamports(&env.requester.pubkey()),
+        requester_before + rent
+    );
+}
+
+#[test]
+fn lamports_sent_to_the_task_are_not_paid_out_as_escrow() {
+    let mut env = TestEnv::new();
+    let (_, task) = env.submitted_task();
+    let rent = env.svm.lamports(&task) - PRICE;
+    let surplus = LAMPORTS_PER_SOL;
+    env.svm.airdrop(&task, surplus).unwrap();
+
+    let provider_before = env.svm.lamports(&env.provider.pubkey());
+    let ix = env.accept_result_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    assert_eq!(
+        env.svm.lamports(&env.provider.pubkey()),
+        provider_before + PRICE - protocol_fee(PRICE)
+    );
+    assert_eq!(env.svm.lamports(&task), rent + surplus);
+
+    let requester_before = env.svm.lamports(&env.requester.pubkey());
+    let ix = env.close_task_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.stranger, &env.requester]));
+    assert_eq!(
+        env.svm.lamports(&env.requester.pubkey()),
+        requester_before + rent + surplus
+    );
+}
+
+#[test]
+fn settlement_refuses_an_underfunded_escrow() {
+    let mut env = TestEnv::new();
+    let (_, task) = env.submitted_task();
+
+    // Leave the task one lamport short of rent plus escrow
+    let mut account = env.svm.get_account(&task).unwrap();
+    account.lamports -= 1;
+    env.svm.set_account(task, account).unwrap();
+
+    let ix = env.accept_result_ix(&env.requester.pubkey(), &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::EscrowUnderfunded,
+    );
+    let ix = env.dispute_task_ix(&env.requester.pubkey(), &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let ix = env.resolve_dispute_ix(&env.arbiter.pubkey(), &task, 5_000);
+    assert_error(
+        env.svm.process(&[ix], &[&env.arbiter]),
+        AgentPayError::EscrowUnderfunded,
+    );
+}
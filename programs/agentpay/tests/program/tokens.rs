@@ -0,0 +1,430 @@
+use agentpay::errors::AgentPayError;
+use agentpay::state::TaskStatus;
+use anchor_lang::system_program;
+use anchor_spl::token::spl_token::{
+    self,
+    solana_program::{program_option::COption, program_pack::Pack},
+};
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        transfer_fee::{TransferFee, TransferFeeAmount, TransferFeeConfig},
+        BaseStateWithExtensions, BaseStateWithExtensionsMut, ExtensionType, StateWithExtensions,
+        StateWithExtensionsMut,
+    },
+    state::{Account as TokenAccount, AccountState, Mint},
+};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+use crate::harness::*;
+
+const TOKEN_PRICE: u64 = 25_000_000;
+
+/// The token program a test mint lives under, and its Token-2022 transfer fee.
+#[derive(Clone, Copy)]
+struct TokenKind {
+    program: Pubkey,
+    transfer_fee_bps: Option<u16>,
+}
+
+const SPL_TOKEN: TokenKind = TokenKind {
+    program: spl_token::ID,
+    transfer_fee_bps: None,
+};
+
+const TOKEN_2022: TokenKind = TokenKind {
+    program: spl_token_2022::ID,
+    transfer_fee_bps: None,
+};
+
+const TOKEN_2022_WITH_FEE: TokenKind = TokenKind {
+    program: spl_token_2022::ID,
+    transfer_fee_bps: Some(50),
+};
+
+impl TokenKind {
+    fn transfer_fee(&self) -> TransferFee {
+        TransferFee {
+            epoch: 0.into(),
+            maximum_fee: u64::MAX.into(),
+            transfer_fee_basis_points: self.transfer_fee_bps.unwrap_or(0).into(),
+        }
+    }
+
+    /// What arrives when `amount` is sent.
+    fn received(&self, amount: u64) -> u64 {
+        amount - self.transfer_fee().calculate_fee(amount).unwrap()
+    }
+}
+
+/// Write a token account or mint straight into the SVM, with room for `extensions`,
+/// which `init` fills in. Base states pack the same under both token programs.
+fn set_token_state<S: Pack + spl_token_2022::extension::BaseState>(
+    env: &mut TestEnv,
+    address: Pubkey,
+    kind: TokenKind,
+    base: S,
+    extensions: &[ExtensionType],
+    init: impl FnOnce(&mut StateWithExtensionsMut<S>),
+) {
+    let mut data = vec![0; ExtensionType::try_calculate_account_len::<S>(extensions).unwrap()];
+    let mut state = StateWithExtensionsMut::<S>::unpack_uninitialized(&mut data).unwrap();
+    init(&mut state);
+    state.base = base;
+    state.pack_base();
+    state.init_account_type().unwrap();
+    let account = Account {
+        lamports: env.svm.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner: kind.program,
+        executable: false,
+        rent_epoch: 0,
+    };
+    env.svm.set_account(address, account).unwrap();
+}
+
+fn create_mint(env: &mut TestEnv, kind: TokenKind) -> Pubkey {
+    let mint = Keypair::new().pubkey();
+    let state = Mint {
+        mint_authority: COption::Some(env.admin.pubkey()),
+        supply: u64::MAX / 2,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    let extensions: &[ExtensionType] = match kind.transfer_fee_bps {
+        Some(_) => &[ExtensionType::TransferFeeConfig],
+        None => &[],
+    };
+    set_token_state(env, mint, kind, state, extensions, |state| {
+        if kind.transfer_fee_bps.is_some() {
+            let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+            config.older_transfer_fee = kind.transfer_fee();
+            config.newer_transfer_fee = kind.transfer_fee();
+        }
+    });
+    mint
+}
+
+fn token_account(
+    env: &mut TestEnv,
+    kind: TokenKind,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let address = Keypair::new().pubkey();
+    let state = TokenAccount {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        delegate: COption::None,
+        state: AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    };
+    // Token-2022 needs somewhere to withhold fees on every account of a fee mint
+    let extensions: &[ExtensionType] = match kind.transfer_fee_bps {
+        Some(_) => &[ExtensionType::TransferFeeAmount],
+        None => &[],
+    };
+    set_token_state(env, address, kind, state, extensions, |state| {
+        if kind.transfer_fee_bps.is_some() {
+            state.init_extension::<TransferFeeAmount>(true).unwrap();
+        }
+    });
+    address
+}
+
+fn token_balance(env: &TestEnv, address: &Pubkey) -> u64 {
+    let account = env.svm.get_account(address).unwrap();
+    StateWithExtensions::<TokenAccount>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+/// Transfer fees withheld on a token account.
+fn withheld(env: &TestEnv, address: &Pubkey) -> u64 {
+    let account = env.svm.get_account(address).unwrap();
+    let state = StateWithExtensions::<TokenAccount>::unpack(&account.data).unwrap();
+    state
+        .get_extension::<TransferFeeAmount>()
+        .unwrap()
+        .withheld_amount
+        .into()
+}
+
+/// Transfer fees harvested to a mint.
+fn harvested(env: &TestEnv, mint: &Pubkey) -> u64 {
+    let account = env.svm.get_account(mint).unwrap();
+    let state = StateWithExtensions::<Mint>::unpack(&account.data).unwrap();
+    state
+        .get_extension::<TransferFeeConfig>()
+        .unwrap()
+        .withheld_amount
+        .into()
+}
+
+/// Token wallets for the requester, provider and treasury.
+struct Wallets {
+    kind: TokenKind,
+    requester: Pubkey,
+    provider: Pubkey,
+    treasury: Pubkey,
+}
+
+fn token_listing(env: &mut TestEnv, kind: TokenKind) -> (Pubkey, Pubkey, Wallets) {
+    let mint = create_mint(env, kind);
+    let terms = ServiceTerms {
+        price: TOKEN_PRICE,
+        mint: Some(mint),
+        ..env.default_terms()
+    };
+    let listing = env.register_service_with(&terms);
+    let (requester, provider, treasury) = (
+        env.requester.pubkey(),
+        env.provider.pubkey(),
+        env.treasury.pubkey(),
+    );
+    let wallets = Wallets {
+        kind,
+        requester: token_account(env, kind, &mint, &requester, 10 * TOKEN_PRICE),
+        provider: token_account(env, kind, &mint, &provider, 0),
+        treasury: token_account(env, kind, &mint, &treasury, 0),
+    };
+    (listing, mint, wallets)
+}
+
+fn create_token_task_ix(
+    env: &mut TestEnv,
+    kind: TokenKind,
+    listing: &Pubkey,
+    mint: &Pubkey,
+    requester_token_account: &Pubkey,
+) -> (Pubkey, Instruction) {
+    let requester = env.requester.pubkey();
+    let task_id = env.next_id();
+    let task = task_pda(&requester, &task_id);
+    let ix = ix(
+        agentpay::accounts::CreateTask {
+            requester,
+            service_listing: *listing,
+            task_request: task,
+            provider_profile: profile_pda(&env.provider.pubkey()),
+            config: config_pda(),
+            mint: Some(*mint),
+            requester_token_account: Some(*requester_token_account),
+            escrow_token_account: Some(escrow_pda(&task)),
+            token_program: Some(kind.program),
+            system_program: system_program::ID,
+            event_authority: event_authority_pda(),
+            program: agentpay::ID,
+        },
+        agentpay::instruction::CreateTask {
+            task_id,
+            description: padded("Translate the attached contract"),
+            deadline: env.svm.now() + TASK_DURATION,
+        },
+    );
+    (task, ix)
+}
+
+fn accept_token_result_ix(
+    env: &TestEnv,
+    task: &Pubkey,
+    mint: &Pubkey,
+    wallets: &Wallets,
+) -> Instruction {
+    let state = env.task(task);
+    ix(
+        agentpay::accounts::AcceptResult {
+            requester: env.requester.pubkey(),
+            task_request: *task,
+            provider: state.provider,
+            service_listing: listing_of(&state),
+            provider_profile: profile_pda(&state.provider),
+            config: config_pda(),
+            treasury: env.treasury.pubkey(),
+            mint: Some(*mint),
+            escrow_token_account: Some(escrow_pda(task)),
+            provider_token_account: Some(wallets.provider),
+            treasury_token_account: Some(wallets.treasury),
+            token_program: Some(wallets.kind.program),
+            event_authority: event_authority_pda(),
+            program: agentpay::ID,
+        },
+        agentpay::instruction::AcceptResult {},
+    )
+}
+
+fn expire_token_task_ix(
+    env: &TestEnv,
+    task: &Pubkey,
+    mint: &Pubkey,
+    wallets: &Wallets,
+) -> Instruction {
+    let state = env.task(task);
+    ix(
+        agentpay::accounts::ExpireTask {
+            requester: state.requester,
+            task_request: *task,
+            provider_profile: profile_of(&state),
+            mint: Some(*mint),
+            escrow_token_account: Some(escrow_pda(task)),
+            requester_token_account: Some(wallets.requester),
+            token_program: Some(wallets.kind.program),
+            event_authority: event_authority_pda(),
+            program: agentpay::ID,
+        },
+        agentpay::instruction::ExpireTask {},
+    )
+}
+
+fn close_token_task_ix(
+    env: &TestEnv,
+    task: &Pubkey,
+    mint: &Pubkey,
+    kind: TokenKind,
+) -> Instruction {
+    ix(
+        agentpay::accounts::CloseTask {
+            requester: env.requester.pubkey(),
+            task_request: *task,
+            review: None,
+            mint: Some(*mint),
+            escrow_token_account: Some(escrow_pda(task)),
+            token_program: Some(kind.program),
+            event_authority: event_authority_pda(),
+            program: agentpay::ID,
+        },
+        agentpay::instruction::CloseTask {},
+    )
+}
+
+/// A fee-free mint under `kind`'s program escrows, settles and closes like SOL.
+fn escrows_and_pays_out_in_the_listing_mint(kind: TokenKind) {
+    let mut env = TestEnv::new();
+    let (listing, mint, wallets) = token_listing(&mut env, kind);
+
+    let (task, ix) = create_token_task_ix(&mut env, kind, &listing, &mint, &wallets.requester);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert_eq!(token_balance(&env, &escrow_pda(&task)), TOKEN_PRICE);
+    assert_eq!(token_balance(&env, &wallets.requester), 9 * TOKEN_PRICE);
+    assert_eq!(env.task(&task).mint, mint);
+
+    let ix = env.submit_result_ix(&env.provider.pubkey(), &task, field(1));
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+
+    // Settling a token task without its token accounts is refused
+    let ix = env.accept_result_ix(&env.requester.pubkey(), &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::TokenAccountsRequired,
+    );
+
+    let ix = accept_token_result_ix(&env, &task, &mint, &wallets);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    let fee = protocol_fee(TOKEN_PRICE);
+    assert_eq!(token_balance(&env, &wallets.provider), TOKEN_PRICE - fee);
+    assert_eq!(token_balance(&env, &wallets.treasury), fee);
+    assert_eq!(token_balance(&env, &escrow_pda(&task)), 0);
+    assert!(env.task(&task).status == TaskStatus::Completed);
+
+    let ix = close_token_task_ix(&env, &task, &mint, kind);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert!(!env.svm.exists(&escrow_pda(&task)));
+    assert!(!env.svm.exists(&task));
+}
+
+#[test]
+fn token_task_escrows_and_pays_out_in_the_listing_mint() {
+    escrows_and_pays_out_in_the_listing_mint(SPL_TOKEN);
+}
+
+#[test]
+fn token_2022_task_escrows_and_pays_out_in_the_listing_mint() {
+    escrows_and_pays_out_in_the_listing_mint(TOKEN_2022);
+}
+
+#[test]
+fn transfer_fee_mints_escrow_what_arrives_and_still_close() {
+    let kind = TOKEN_2022_WITH_FEE;
+    let mut env = TestEnv::new();
+    let (listing, mint, wallets) = token_listing(&mut env, kind);
+
+    // The deposit fee is withheld on the escrow, which books only what arrived
+    let (accepted, ix) = create_token_task_ix(&mut env, kind, &listing, &mint, &wallets.requester);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let escrowed = kind.received(TOKEN_PRICE);
+    assert!(escrowed < TOKEN_PRICE);
+    assert_eq!(token_balance(&env, &escrow_pda(&accepted)), escrowed);
+    assert_eq!(env.task(&accepted).amount_lamports, escrowed);
+    let deposit_fee = withheld(&env, &escrow_pda(&accepted));
+    assert_eq!(deposit_fee, TOKEN_PRICE - escrowed);
+
+    // Release: the provider and treasury each pay the fee on what they are sent
+    let ix = env.submit_result_ix(&env.provider.pubkey(), &accepted, field(1));
+    assert_ok(env.svm.process(&[ix], &[&env.provider]));
+    let ix = accept_token_result_ix(&env, &accepted, &mint, &wallets);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let fee = protocol_fee(escrowed);
+    assert_eq!(
+        token_balance(&env, &wallets.provider),
+        kind.received(escrowed - fee)
+    );
+    assert_eq!(token_balance(&env, &wallets.treasury), kind.received(fee));
+    assert_eq!(token_balance(&env, &escrow_pda(&accepted)), 0);
+
+    // Refund: an expired task returns the escrow, less the fee on the way back
+    let (expired, ix) = create_token_task_ix(&mut env, kind, &listing, &mint, &wallets.requester);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let before = token_balance(&env, &wallets.requester);
+    env.svm.warp(TASK_DURATION + 1);
+    let ix = expire_token_task_ix(&env, &expired, &mint, &wallets);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert_eq!(
+        token_balance(&env, &wallets.requester),
+        before + kind.received(escrowed)
+    );
+    assert!(env.task(&expired).status == TaskStatus::Expired);
+
+    // Fees still withheld on an empty escrow are harvested to the mint so it can close
+    for task in [accepted, expired] {
+        let ix = close_token_task_ix(&env, &task, &mint, kind);
+        assert_ok(env.svm.process(&[ix], &[&env.requester]));
+        assert!(!env.svm.exists(&escrow_pda(&task)));
+        assert!(!env.svm.exists(&task));
+    }
+    assert_eq!(harvested(&env, &mint), 2 * deposit_fee);
+}
+
+#[test]
+fn token_task_requires_the_listing_mint_and_accounts() {
+    let mut env = TestEnv::new();
+    let (listing, _, _) = token_listing(&mut env, SPL_TOKEN);
+
+    let other_mint = create_mint(&mut env, SPL_TOKEN);
+    let requester = env.requester.pubkey();
+    let other_wallet = token_account(&mut env, SPL_TOKEN, &other_mint, &requester, TOKEN_PRICE);
+    let (_, ix) = create_token_task_ix(&mut env, SPL_TOKEN, &listing, &other_mint, &other_wallet);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::MintMismatch,
+    );
+
+    let task_id = env.next_id();
+    let deadline = env.svm.now() + TASK_DURATION;
+    let ix = env.create_task_ix(&requester, &listing, task_id, deadline);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::TokenAccountsRequired,
+    );
+}
@@ -0,0 +1,315 @@
+use agentpay::errors::AgentPayError;
+use agentpay::state::{
+    EncryptedDelivery, ServiceListing, TaskStatus, VerifyingKeyAccount, ZkCircuit,
+};
+use anchor_lang::error::ErrorCode;
+use solana_sdk::signature::Signer;
+
+use crate::harness::*;
+
+#[test]
+fn verifying_keys_are_admin_only_and_sized_to_the_circuit() {
+    let mut env = TestEnv::new();
+    let admin = env.admin.pubkey();
+
+    let ix = env.init_verifying_key_ix(&env.stranger.pubkey(), ZkCircuit::TaskVerify, 1, 5);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::UnauthorizedAdmin,
+    );
+    let ix = env.init_verifying_key_ix(&admin, ZkCircuit::TaskVerify, 1, 2);
+    assert_error(
+        env.svm.process(&[ix], &[&env.admin]),
+        AgentPayError::InvalidVerifyingKey,
+    );
+
+    let ix = env.init_verifying_key_ix(&admin, ZkCircuit::TaskVerify, 1, 5);
+    assert_ok(env.svm.process(&[ix], &[&env.admin]));
+    let vk = vk_pda(ZkCircuit::TaskVerify, 1);
+
+    let ix = env.append_verifying_key_ic_ix(&admin, &vk, 7);
+    assert_error(
+        env.svm.process(&[ix], &[&env.admin]),
+        AgentPayError::InvalidVerifyingKey,
+    );
+    let ix = env.append_verifying_key_ic_ix(&env.stranger.pubkey(), &vk, 1);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::UnauthorizedAdmin,
+    );
+
+    let ix = env.append_verifying_key_ic_ix(&admin, &vk, 6);
+    assert_ok(env.svm.process(&[ix], &[&env.admin]));
+    let key: VerifyingKeyAccount = env.svm.read(&vk);
+    assert!(key.is_usable());
+
+    let ix = env.revoke_verifying_key_ix(&env.stranger.pubkey(), &vk);
+    assert_error(
+        env.svm.process(&[ix], &[&env.stranger]),
+        AgentPayError::UnauthorizedAdmin,
+    );
+    let ix = env.revoke_verifying_key_ix(&admin, &vk);
+    assert_ok(env.svm.process(&[ix], &[&env.admin]));
+    assert!(env.svm.read::<VerifyingKeyAccount>(&vk).revoked);
+}
+
+#[test]
+fn verifying_keys_reject_points_at_infinity() {
+    let mut env = TestEnv::new();
+    let key = generator_vk(ZkCircuit::TaskVerify, 1);
+
+    for zeroed in [
+        VerifyingKeyAccount {
+            alpha_g1: [0u8; 64],
+            ..key.clone()
+        },
+        VerifyingKeyAccount {
+            beta_g2: [0u8; 128],
+            ..key.clone()
+        },
+        VerifyingKeyAccount {
+            gamma_g2: [0u8; 128],
+            ..key.clone()
+        },
+        VerifyingKeyAccount {
+            delta_g2: [0u8; 128],
+            ..key.clone()
+        },
+    ] {
+        let [init, _] = env.verifying_key_ixs(&zeroed);
+        assert_error(
+            env.svm.process(&[init], &[&env.admin]),
+            AgentPayError::InvalidVerifyingKey,
+        );
+    }
+
+    // One zero IC point spoils the whole chunk
+    let mut ic = key.ic.clone();
+    ic[3] = [0u8; 64];
+    let [init, append] = env.verifying_key_ixs(&VerifyingKeyAccount { ic, ..key.clone() });
+    assert_ok(env.svm.process(&[init], &[&env.admin]));
+    assert_error(
+        env.svm.process(&[append], &[&env.admin]),
+        AgentPayError::InvalidVerifyingKey,
+    );
+
+    let [_, append] = env.verifying_key_ixs(&key);
+    assert_ok(env.svm.process(&[append], &[&env.admin]));
+    let vk: VerifyingKeyAccount = env.svm.read(&vk_pda(ZkCircuit::TaskVerify, 1));
+    assert!(vk.is_usable());
+}
+
+#[test]
+fn submit_result_zk_marks_the_task_verified() {
+    let mut env = TestEnv::new();
+    let vk = env.register_zero_vk(ZkCircuit::TaskVerify, 1);
+    let listing = env.register_service();
+    let task = env.create_task(&listing);
+
+    let ixs =
+        env.submit_result_zk_ixs(&env.provider.pubkey(), &task, &vk, &ZERO_PROOF, field(9), 1);
+    assert_ok(env.svm.process(&ixs, &[&env.provider]));
+
+    let state = env.task(&task);
+    assert!(state.status == TaskStatus::Submitted);
+    assert!(state.zk_verified);
+    assert_eq!(state.zk_vk_version, 1);
+    assert_eq!(state.result_chunk_count, 1);
+    assert_eq!(state.result_hash, field(9));
+}
+
+#[test]
+fn submit_result_zk_accepts_chunked_roots_in_range() {
+    let mut env = TestEnv::new();
+    let single = env.register_zero_vk(ZkCircuit::TaskVerify, 1);
+    let chunked = env.register_zero_vk(ZkCircuit::TaskVerifyChunked, 1);
+    let listing = env.register_service();
+    let task = env.create_task(&listing);
+    let provider = env.provider.pubkey();
+
+    for (vk, chunk_count) in [(&single, 2), (&chunked, 0), (&chunked, 1025)] {
+        let ixs =
+            env.submit_result_zk_ixs(&provider, &task, vk, &ZERO_PROOF, field(9), chunk_count);
+        assert_error(
+            env.svm.process(&ixs, &[&env.provider]),
+            AgentPayError::InvalidChunkCount,
+        );
+    }
+
+    let ixs = env.submit_result_zk_ixs(&provider, &task, &chunked, &ZERO_PROOF, field(9), 40);
+    assert_ok(env.svm.process(&ixs, &[&env.provider]));
+    assert_eq!(env.task(&task).result_chunk_count, 40);
+}
+
+#[test]
+fn submit_result_zk_rejects_bad_proofs_and_keys() {
+    let mut env = TestEnv::new();
+    let vk = env.register_zero_vk(ZkCircuit::TaskVerify, 1);
+    let delivery_vk = env.register_zero_vk(ZkCircuit::ResultDelivery, 1);
+    let listing = env.register_service();
+    let task = env.create_task(&listing);
+    let provider = env.provider.pubkey();
+
+    let ixs = env.submit_result_zk_ixs(&provider, &task, &vk, &invalid_proof(), field(9), 1);
+    assert_error(
+        env.svm.process(&ixs, &[&env.provider]),
+        AgentPayError::ZkProofVerificationFailed,
+    );
+
+    let ixs = env.submit_result_zk_ixs(&provider, &task, &delivery_vk, &ZERO_PROOF, field(9), 1);
+    assert_error(
+        env.svm.process(&ixs, &[&env.provider]),
+        AgentPayError::InvalidVerifyingKey,
+    );
+
+    let ixs =
+        env.submit_result_zk_ixs(&env.stranger.pubkey(), &task, &vk, &ZERO_PROOF, field(9), 1);
+    assert_error(
+        env.svm.process(&ixs, &[&env.stranger]),
+        AgentPayError::UnauthorizedProvider,
+    );
+
+    // A key with IC points still missing cannot verify anything
+    let ix = env.init_verifying_key_ix(&env.admin.pubkey(), ZkCircuit::TaskVerify, 2, 5);
+    assert_ok(env.svm.process(&[ix], &[&env.admin]));
+    let incomplete = vk_pda(ZkCircuit::TaskVerify, 2);
+    let ixs = env.submit_result_zk_ixs(&provider, &task, &incomplete, &ZERO_PROOF, field(9), 1);
+    assert_error(
+        env.svm.process(&ixs, &[&env.provider]),
+        AgentPayError::ZkVerifyingKeyMissing,
+    );
+
+    let ix = env.revoke_verifying_key_ix(&env.admin.pubkey(), &vk);
+    assert_ok(env.svm.process(&[ix], &[&env.admin]));
+    let ixs = env.submit_result_zk_ixs(&provider, &task, &vk, &ZERO_PROOF, field(9), 1);
+    assert_error(
+        env.svm.process(&ixs, &[&env.provider]),
+        AgentPayError::ZkVerifyingKeyMissing,
+    );
+}
+
+#[test]
+fn encrypted_delivery_with_expected_hash_pays_on_proof() {
+    let mut env = TestEnv::new();
+    let vk = env.register_zero_vk(ZkCircuit::ResultDelivery, 1);
+    let listing = env.register_service();
+    let task = env.create_task(&listing);
+    let requester = env.requester.pubkey();
+    let provider = env.provider.pubkey();
+
+    let mut encryption_key = [0u8; 64];
+    encryption_key[..32].copy_from_slice(&field(3));
+    encryption_key[32..].copy_from_slice(&field(4));
+    let ix = env.request_encrypted_delivery_ix(&requester, &task, encryption_key, field(77));
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    let ixs = env.submit_result_encrypted_ixs(&provider, &task, &vk, &ZERO_PROOF, field(78));
+    assert_error(
+        env.svm.process(&ixs, &[&env.provider]),
+        AgentPayError::DeliveryHashMismatch,
+    );
+    let ix = env.close_delivery_ix(&requester, &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::InvalidTaskStatus,
+    );
+
+    let provider_before = env.svm.lamports(&provider);
+    let ixs = env.submit_result_encrypted_ixs(&provider, &task, &vk, &ZERO_PROOF, field(77));
+    assert_ok(env.svm.process(&ixs, &[&env.stranger, &env.provider]));
+
+    assert_eq!(
+        env.svm.lamports(&provider),
+        provider_before + PRICE - protocol_fee(PRICE)
+    );
+    let state = env.task(&task);
+    assert!(state.status == TaskStatus::Completed);
+    assert!(state.zk_verified);
+    assert_eq!(env.svm.read::<ServiceListing>(&listing).tasks_completed, 1);
+    let delivery: EncryptedDelivery = env.svm.read(&delivery_pda(&task));
+    assert_eq!(delivery.ciphertext, field(42));
+    assert!(delivery.delivered_at > 0);
+
+    let ix = env.close_delivery_ix(&requester, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert!(!env.svm.exists(&delivery_pda(&task)));
+}
+
+#[test]
+fn encrypted_delivery_without_expected_hash_goes_to_review() {
+    let mut env = TestEnv::new();
+    let vk = env.register_zero_vk(ZkCircuit::ResultDelivery, 1);
+    let listing = env.register_service();
+    let task = env.create_task(&listing);
+
+    let ix =
+        env.request_encrypted_delivery_ix(&env.requester.pubkey(), &task, [0u8; 64], [0u8; 32]);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    let ixs = env.submit_result_encrypted_ixs(
+        &env.provider.pubkey(),
+        &task,
+        &vk,
+        &invalid_proof(),
+        field(5),
+    );
+    assert_error(
+        env.svm.process(&ixs, &[&env.provider]),
+        AgentPayError::ZkProofVerificationFailed,
+    );
+
+    let ixs =
+        env.submit_result_encrypted_ixs(&env.provider.pubkey(), &task, &vk, &ZERO_PROOF, field(5));
+    assert_ok(env.svm.process(&ixs, &[&env.provider]));
+
+    let state = env.task(&task);
+    assert!(state.status == TaskStatus::Submitted);
+    assert_eq!(state.result_hash, field(5));
+}
+
+#[test]
+fn stale_delivery_cannot_pay_out_a_recreated_task() {
+    let mut env = TestEnv::new();
+    let vk = env.register_zero_vk(ZkCircuit::ResultDelivery, 1);
+    let listing = env.register_service();
+    let requester = env.requester.pubkey();
+    let provider = env.provider.pubkey();
+    let task_id = env.next_id();
+    let deadline = env.svm.now() + TASK_DURATION;
+    let create = env.create_task_ix(&requester, &listing, task_id, deadline);
+    let recreate = create.clone();
+    assert_ok(env.svm.process(&[create], &[&env.requester]));
+    let task = task_pda(&requester, &task_id);
+
+    let ix = env.request_encrypted_delivery_ix(&requester, &task, [0u8; 64], field(77));
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    assert!(env.task(&task).delivery_open);
+    let ix = env.cancel_task_ix(&requester, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    // A delivery with an expected hash would pay the next task reusing this id on sight
+    let ix = env.close_task_ix(&requester, &task);
+    assert_error(
+        env.svm.process(&[ix], &[&env.requester]),
+        AgentPayError::DeliveryNotClosed,
+    );
+    let ix = env.close_delivery_ix(&requester, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+    let ix = env.close_task_ix(&requester, &task);
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+
+    // Replaying the old proof against the recreated task finds no delivery to match
+    assert_ok(env.svm.process(&[recreate], &[&env.requester]));
+    assert!(!env.task(&task).delivery_open);
+    let provider_before = env.svm.lamports(&provider);
+    let ixs = env.submit_result_encrypted_ixs(&provider, &task, &vk, &ZERO_PROOF, field(77));
+    assert_error(
+        env.svm.process(&ixs, &[&env.provider]),
+        ErrorCode::AccountNotInitialized,
+    );
+    assert_eq!(env.svm.lamports(&provider), provider_before);
+    assert!(env.task(&task).status == TaskStatus::Open);
+
+    let ix = env.request_encrypted_delivery_ix(&requester, &task, [0u8; 64], field(78));
+    assert_ok(env.svm.process(&[ix], &[&env.requester]));
+}
@@ -14,6 +14,8 @@ describe("agentpay", () => {
   // Agents
   let providerAgent: Keypair;
   let requesterAgent: Keypair;
+  let arbiterAgent: Keypair;
+  let treasury: Keypair;
 
   // IDs
   let serviceId: Uint8Array;
@@ -22,8 +24,17 @@ describe("agentpay", () => {
   // PDAs
   let serviceListingPda: PublicKey;
   let taskRequestPda: PublicKey;
+  let configPda: PublicKey;
 
   const PRICE_LAMPORTS = 0.01 * LAMPORTS_PER_SOL; // 0.01 SOL
+  const REVIEW_WINDOW_SECONDS = 24 * 3600; // 1 day
+  const CANCELLATION_FEE_BPS = 2000; // 20% to the provider once work has started
+  const PROTOCOL_FEE_BPS = 100; // 1% of every provider payout
+
+  // What the provider receives once the protocol fee is withheld
+  function afterProtocolFee(amount: number): number {
+    return amount - Math.floor((amount * PROTOCOL_FEE_BPS) / 10000);
+  }
 
   function padBytes(input: string, length: number): number[] {
     const buf = Buffer.alloc(length);
@@ -35,6 +46,8 @@ describe("agentpay", () => {
     // Create and fund two agent wallets
     providerAgent = Keypair.generate();
     requesterAgent = Keypair.generate();
+    arbiterAgent = Keypair.generate();
+    treasury = Keypair.generate();
 
     // Airdrop to both agents
     const airdropProvider = await provider.connection.requestAirdrop(
@@ -49,6 +62,13 @@ describe("agentpay", () => {
     );
     await provider.connection.confirmTransaction(airdropRequester);
 
+    // Fund the treasury so small fee transfers stay above rent exemption
+    const airdropTreasury = await provider.connection.requestAirdrop(
+      treasury.publicKey,
+      LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdropTreasury);
+
     // Generate IDs
     serviceId = crypto.randomBytes(16);
     taskId = crypto.randomBytes(16);
@@ -63,6 +83,110 @@ describe("agentpay", () => {
       [Buffer.from("task"), requesterAgent.publicKey.toBuffer(), Buffer.from(taskId)],
       program.programId
     );
+
+    [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+
+    // The local wallet deployed the program, so it is the upgrade authority
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
+    await program.methods
+      .initializeConfig(PROTOCOL_FEE_BPS, treasury.publicKey)
+      .accounts({
+        admin: provider.wallet.publicKey,
+        config: configPda,
+        program: program.programId,
+        programData,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  });
+
+  // =========================================================================
+  // protocol config
+  // =========================================================================
+
+  it("fails to set a protocol fee above the cap", async () => {
+    try {
+      await program.methods
+        .setProtocolFee(5000)
+        .accounts({
+          admin: provider.wallet.publicKey,
+          config: configPda,
+        })
+        .rpc();
+      expect.fail("Should have thrown");
+    } catch (err: any) {
+      expect(err.error.errorCode.code).to.equal("ProtocolFeeTooHigh");
+    }
+  });
+
+  it("fails when a non-admin changes the protocol fee", async () => {
+    try {
+      await program.methods
+        .setProtocolFee(0)
+        .accounts({
+          admin: requesterAgent.publicKey,
+          config: configPda,
+        })
+        .signers([requesterAgent])
+        .rpc();
+      expect.fail("Should have thrown");
+    } catch (err: any) {
+      expect(err.error.errorCode.code).to.equal("UnauthorizedAdmin");
+    }
+  });
+
+  it("blocks new listings while paused and resumes after unpausing", async () => {
+    await program.methods
+      .setPaused(true)
+      .accounts({
+        admin: provider.wallet.publicKey,
+        config: configPda,
+      })
+      .rpc();
+
+    const pausedServiceId = crypto.randomBytes(16);
+    const [pausedServicePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("service"), providerAgent.publicKey.toBuffer(), Buffer.from(pausedServiceId)],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .registerService(
+          Array.from(pausedServiceId),
+          padBytes("Paused service", 128),
+          new anchor.BN(PRICE_LAMPORTS),
+          new anchor.BN(0),
+          arbiterAgent.publicKey,
+          new anchor.BN(REVIEW_WINDOW_SECONDS),
+          CANCELLATION_FEE_BPS
+        )
+        .accounts({
+          config: configPda,
+          provider: providerAgent.publicKey,
+          serviceListing: pausedServicePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([providerAgent])
+        .rpc();
+      expect.fail("Should have thrown");
+    } catch (err: any) {
+      expect(err.error.errorCode.code).to.equal("ProgramPaused");
+    }
+
+    await program.methods
+      .setPaused(false)
+      .accounts({
+        admin: provider.wallet.publicKey,
+        config: configPda,
+      })
+      .rpc();
+
+    const config = await program.account.config.fetch(configPda);
+    expect(config.paused).to.equal(false);
   });
 
   // =========================================================================
@@ -77,9 +201,13 @@ describe("agentpay", () => {
         Array.from(serviceId),
         description,
         new anchor.BN(PRICE_LAMPORTS),
-        new anchor.BN(0)
+        new anchor.BN(0),
+        arbiterAgent.publicKey,
+        new anchor.BN(REVIEW_WINDOW_SECONDS),
+        CANCELLATION_FEE_BPS
       )
       .accounts({
+        config: configPda,
         provider: providerAgent.publicKey,
         serviceListing: serviceListingPda,
         systemProgram: SystemProgram.programId,
@@ -111,6 +239,7 @@ describe("agentpay", () => {
         new anchor.BN(deadline)
       )
       .accounts({
+        config: configPda,
         requester: requesterAgent.publicKey,
         serviceListing: serviceListingPda,
         taskRequest: taskRequestPda,
@@ -163,6 +292,7 @@ describe("agentpay", () => {
           new anchor.BN(Math.floor(Date.now() / 1000) + 3600)
         )
         .accounts({
+          config: configPda,
           requester: requesterAgent.publicKey,
           serviceListing: serviceListingPda,
           taskRequest: newTaskPda,
@@ -179,6 +309,202 @@ describe("agentpay", () => {
     // so we just note this is deactivated and re-test flow won't need it)
   });
 
+  // =========================================================================
+  // update_service / reactivate_service
+  // =========================================================================
+
+  it("updates price and reactivates the service without touching escrow", async () => {
+    const taskBefore = await program.account.taskRequest.fetch(taskRequestPda);
+
+    await program.methods
+      .updateService(new anchor.BN(PRICE_LAMPORTS * 2), null, new anchor.BN(5), null)
+      .accounts({
+        provider: providerAgent.publicKey,
+        serviceListing: serviceListingPda,
+      })
+      .signers([providerAgent])
+      .rpc();
+
+    await program.methods
+      .reactivateService()
+      .accounts({
+        provider: providerAgent.publicKey,
+        serviceListing: serviceListingPda,
+      })
+      .signers([providerAgent])
+      .rpc();
+
+    const listing = await program.account.serviceListing.fetch(serviceListingPda);
+    expect(listing.priceLamports.toNumber()).to.equal(PRICE_LAMPORTS * 2);
+    expect(listing.minReputation.toNumber()).to.equal(5);
+    expect(listing.isActive).to.be.true;
+
+    // Existing escrow keeps the original price
+    const taskAfter = await program.account.taskRequest.fetch(taskRequestPda);
+    expect(taskAfter.amountLamports.toNumber()).to.equal(taskBefore.amountLamports.toNumber());
+  });
+
+  it("fails when non-provider tries to update the service", async () => {
+    try {
+      await program.methods
+        .updateService(new anchor.BN(1), null, null, null)
+        .accounts({
+          provider: requesterAgent.publicKey, // wrong signer
+          serviceListing: serviceListingPda,
+        })
+        .signers([requesterAgent])
+        .rpc();
+      expect.fail("Should have thrown");
+    } catch (err: any) {
+      // Seeds / has_one constraint violation
+      expect(err).to.exist;
+    }
+  });
+
+  // =========================================================================
+  // Verifying keys and the reputation gate
+  // =========================================================================
+
+  const TASK_VK_VERSION = 1;
+
+  // BN254 generators (EIP-197 encoding) standing in for real key points, since the
+  // program rejects points at infinity
+  const G1_GENERATOR = [...new Array(31).fill(0), 1, ...new Array(31).fill(0), 2];
+  const G2_GENERATOR = Array.from(
+    Buffer.from(
+      "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2" +
+        "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed" +
+        "090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b" +
+        "12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
+      "hex"
+    )
+  );
+
+  const verifyingKeyPda = (circuit: number, version: number): PublicKey => {
+    const versionBytes = Buffer.alloc(2);
+    versionBytes.writeUInt16LE(version);
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("vk"), Buffer.from([circuit]), versionBytes],
+      program.programId
+    )[0];
+  };
+  const taskVkPda = verifyingKeyPda(0, TASK_VK_VERSION);
+
+  it("rejects a verifying key whose input count does not match the circuit", async () => {
+    try {
+      await program.methods
+        .initVerifyingKey(
+          { taskVerify: {} },
+          99,
+          3, // task_verify_v2 has 5 public inputs
+          G1_GENERATOR,
+          G2_GENERATOR,
+          G2_GENERATOR,
+          G2_GENERATOR
+        )
+        .accounts({
+          admin: provider.wallet.publicKey,
+          config: configPda,
+          verifyingKey: verifyingKeyPda(0, 99),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      expect.fail("Should have thrown");
+    } catch (err: any) {
+      expect(err.error.errorCode.code).to.equal("InvalidVerifyingKey");
+    }
+  });
+
+  it("rejects a verifying key with a point at infinity", async () => {
+    try {
+      await program.methods
+        .initVerifyingKey(
+          { taskVerify: {} },
+          99,
+          5,
+          new Array(64).fill(0),
+          G2_GENERATOR,
+          G2_GENERATOR,
+          G2_GENERATOR
+        )
+        .accounts({
+          admin: provider.wallet.publicKey,
+          config: configPda,
+          verifyingKey: verifyingKeyPda(0, 99),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      expect.fail("Should have thrown");
+    } catch (err: any) {
+      expect(err.error.errorCode.code).to.equal("InvalidVerifyingKey");
+    }
+  });
+
+  it("registers a task verifying key in chunks", async () => {
+    await program.methods
+      .initVerifyingKey(
+        { taskVerify: {} },
+        TASK_VK_VERSION,
+        5,
+        G1_GENERATOR,
+        G2_GENERATOR,
+        G2_GENERATOR,
+        G2_GENERATOR
+      )
+      .accounts({
+        admin: provider.wallet.publicKey,
+        config: configPda,
+        verifyingKey: taskVkPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    for (const chunk of [new Array(3).fill(G1_GENERATOR), new Array(3).fill(G1_GENERATOR)]) {
+      await program.methods
+        .appendVerifyingKeyIc(chunk)
+        .accounts({
+          admin: provider.wallet.publicKey,
+          config: configPda,
+          verifyingKey: taskVkPda,
+        })
+        .rpc();
+    }
+
+    const vk = await program.account.verifyingKeyAccount.fetch(taskVkPda);
+    expect(vk.ic.length).to.equal(6);
+    expect(vk.revoked).to.be.false;
+  });
+
+  it("refuses new tasks until the provider has completed the listing's minimum", async () => {
+    // The listing now requires min_reputation = 5 and the provider has completed none
+    const newTaskId = crypto.randomBytes(16);
+    const [newTaskPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("task"), requesterAgent.publicKey.toBuffer(), Buffer.from(newTaskId)],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .createTask(
+          Array.from(newTaskId),
+          padBytes("Should fail", 256),
+          new anchor.BN(Math.floor(Date.now() / 1000) + 3600)
+        )
+        .accounts({
+          config: configPda,
+          requester: requesterAgent.publicKey,
+          serviceListing: serviceListingPda,
+          taskRequest: newTaskPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([requesterAgent])
+        .rpc();
+      expect.fail("Should have thrown");
+    } catch (err: any) {
+      expect(err.error.errorCode.code).to.equal("ReputationTooLow");
+    }
+  });
+
   // =========================================================================
   // submit_result
   // =========================================================================
@@ -234,6 +560,8 @@ describe("agentpay", () => {
     await program.methods
       .acceptResult()
       .accounts({
+        config: configPda,
+        treasury: treasury.publicKey,
         requester: requesterAgent.publicKey,
         taskRequest: taskRequestPda,
         provider: providerAgent.publicKey,
@@ -247,98 +575,332 @@ describe("agentpay", () => {
 
     // Provider should have received the escrowed SOL
     const providerBalanceAfter = await provider.connection.getBalance(providerAgent.publicKey);
-    expect(providerBalanceAfter - providerBalanceBefore).to.equal(PRICE_LAMPORTS);
+    expect(providerBalanceAfter - providerBalanceBefore).to.equal(afterProtocolFee(PRICE_LAMPORTS));
 
     // Service listing tasks_completed should be incremented
     const listing = await program.account.serviceListing.fetch(serviceListingPda);
     expect(listing.tasksCompleted.toNumber()).to.equal(1);
+
+    // So should the provider's profile, which spans all of their listings
+    const [profilePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("provider"), providerAgent.publicKey.toBuffer()],
+      program.programId
+    );
+    const profile = await program.account.providerProfile.fetch(profilePda);
+    expect(profile.tasksCompleted.toNumber()).to.equal(1);
+    expect(profile.totalEarned.toNumber()).to.equal(afterProtocolFee(PRICE_LAMPORTS));
   });
 
   // =========================================================================
-  // dispute_task (new task for this test)
+  // submit_review
   // =========================================================================
 
-  describe("dispute flow", () => {
-    let disputeTaskId: Uint8Array;
-    let disputeTaskPda: PublicKey;
-    let disputeServiceId: Uint8Array;
-    let disputeServicePda: PublicKey;
+  it("requester reviews the completed task once", async () => {
+    const [reviewPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("review"), taskRequestPda.toBuffer()],
+      program.programId
+    );
+    const reviewHash = Array.from(crypto.randomBytes(32));
 
-    before(async () => {
-      // Register a new active service
-      disputeServiceId = crypto.randomBytes(16);
-      [disputeServicePda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("service"), providerAgent.publicKey.toBuffer(), Buffer.from(disputeServiceId)],
-        program.programId
-      );
+    await program.methods
+      .submitReview(4, reviewHash)
+      .accounts({
+        requester: requesterAgent.publicKey,
+        taskRequest: taskRequestPda,
+      })
+      .signers([requesterAgent])
+      .rpc();
 
-      await program.methods
-        .registerService(
-          Array.from(disputeServiceId),
-          padBytes("Code review service", 128),
-          new anchor.BN(PRICE_LAMPORTS),
-          new anchor.BN(0)
-        )
-        .accounts({
-          provider: providerAgent.publicKey,
-          serviceListing: disputeServicePda,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([providerAgent])
-        .rpc();
+    const review = await program.account.review.fetch(reviewPda);
+    expect(review.score).to.equal(4);
+    expect(review.reviewHash).to.deep.equal(reviewHash);
+    expect(review.provider.toBase58()).to.equal(providerAgent.publicKey.toBase58());
 
-      // Create a task
-      disputeTaskId = crypto.randomBytes(16);
-      [disputeTaskPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("task"), requesterAgent.publicKey.toBuffer(), Buffer.from(disputeTaskId)],
-        program.programId
-      );
+    const [profilePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("provider"), providerAgent.publicKey.toBuffer()],
+      program.programId
+    );
+    const profile = await program.account.providerProfile.fetch(profilePda);
+    expect(profile.reviewCount.toNumber()).to.equal(1);
+    expect(profile.reviewScoreTotal.toNumber()).to.equal(4);
 
+    try {
       await program.methods
-        .createTask(
-          Array.from(disputeTaskId),
-          padBytes("Review my smart contract", 256),
-          new anchor.BN(Math.floor(Date.now() / 1000) + 3600)
-        )
+        .submitReview(1, reviewHash)
         .accounts({
           requester: requesterAgent.publicKey,
-          serviceListing: disputeServicePda,
-          taskRequest: disputeTaskPda,
-          systemProgram: SystemProgram.programId,
+          taskRequest: taskRequestPda,
         })
         .signers([requesterAgent])
         .rpc();
+      expect.fail("Should have thrown");
+    } catch (err: any) {
+      // The review PDA already exists
+      expect(err).to.exist;
+    }
+  });
 
-      // Provider submits result
-      const resultHash = crypto.createHash("sha256").update("bad review").digest();
-      await program.methods
-        .submitResult(Array.from(resultHash))
-        .accounts({
-          provider: providerAgent.publicKey,
-          taskRequest: disputeTaskPda,
-        })
-        .signers([providerAgent])
-        .rpc();
-    });
-
-    it("requester disputes and gets refund", async () => {
-      const requesterBalanceBefore = await provider.connection.getBalance(requesterAgent.publicKey);
+  // =========================================================================
+  // create_bounty / claim_bounty / cancel_bounty
+  // =========================================================================
 
-      await program.methods
-        .disputeTask()
-        .accounts({
-          requester: requesterAgent.publicKey,
-          taskRequest: disputeTaskPda,
+  async function createBounty(minReputation: number): Promise<PublicKey> {
+    const bountyId = crypto.randomBytes(16);
+    const [bountyPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("task"), requesterAgent.publicKey.toBuffer(), bountyId],
+      program.programId
+    );
+    await program.methods
+      .createBounty(
+        Array.from(bountyId),
+        padBytes("Label 500 images", 256),
+        new anchor.BN(PRICE_LAMPORTS),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+        new anchor.BN(minReputation),
+        new anchor.BN(600),
+        arbiterAgent.publicKey,
+        new anchor.BN(REVIEW_WINDOW_SECONDS)
+      )
+      .accounts({
+        config: configPda,
+        requester: requesterAgent.publicKey,
+        taskRequest: bountyPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([requesterAgent])
+      .rpc();
+    return bountyPda;
+  }
+
+  it("provider with enough completed tasks claims a bounty", async () => {
+    // The provider has completed one task so far
+    const bountyPda = await createBounty(1);
+    let bounty = await program.account.taskRequest.fetch(bountyPda);
+    expect(bounty.status).to.deep.equal({ open: {} });
+    expect(bounty.provider.toBase58()).to.equal(PublicKey.default.toBase58());
+
+    await program.methods
+      .claimBounty()
+      .accounts({
+        provider: providerAgent.publicKey,
+        taskRequest: bountyPda,
+      })
+      .signers([providerAgent])
+      .rpc();
+
+    bounty = await program.account.taskRequest.fetch(bountyPda);
+    expect(bounty.status).to.deep.equal({ inProgress: {} });
+    expect(bounty.provider.toBase58()).to.equal(providerAgent.publicKey.toBase58());
+
+    // A claimed bounty can no longer be withdrawn
+    try {
+      await program.methods
+        .cancelBounty()
+        .accounts({
+          requester: requesterAgent.publicKey,
+          taskRequest: bountyPda,
+        })
+        .signers([requesterAgent])
+        .rpc();
+      expect.fail("Should have thrown");
+    } catch (err: any) {
+      expect(err.error.errorCode.code).to.equal("InvalidTaskStatus");
+    }
+  });
+
+  it("fails to claim a bounty above the provider's reputation", async () => {
+    const bountyPda = await createBounty(5);
+    try {
+      await program.methods
+        .claimBounty()
+        .accounts({
+          provider: providerAgent.publicKey,
+          taskRequest: bountyPda,
+        })
+        .signers([providerAgent])
+        .rpc();
+      expect.fail("Should have thrown");
+    } catch (err: any) {
+      expect(err.error.errorCode.code).to.equal("ReputationTooLow");
+    }
+
+    // Nobody qualified, so the requester takes the reward back
+    await program.methods
+      .cancelBounty()
+      .accounts({
+        requester: requesterAgent.publicKey,
+        taskRequest: bountyPda,
+      })
+      .signers([requesterAgent])
+      .rpc();
+    const bounty = await program.account.taskRequest.fetch(bountyPda);
+    expect(bounty.status).to.deep.equal({ cancelled: {} });
+  });
+
+  // =========================================================================
+  // close_task
+  // =========================================================================
+
+  it("requester closes the settled task and reclaims rent", async () => {
+    const taskRent = await provider.connection.getBalance(taskRequestPda);
+    const requesterBalanceBefore = await provider.connection.getBalance(requesterAgent.publicKey);
+
+    await program.methods
+      .closeTask()
+      .accounts({
+        requester: requesterAgent.publicKey,
+        taskRequest: taskRequestPda,
+      })
+      .signers([requesterAgent])
+      .rpc();
+
+    const closed = await provider.connection.getAccountInfo(taskRequestPda);
+    expect(closed).to.be.null;
+
+    // Requester pays the tx fee, so allow for it
+    const requesterBalanceAfter = await provider.connection.getBalance(requesterAgent.publicKey);
+    expect(requesterBalanceAfter - requesterBalanceBefore).to.be.greaterThan(taskRent - 10000);
+  });
+
+  // =========================================================================
+  // dispute_task (new task for this test)
+  // =========================================================================
+
+  describe("dispute flow", () => {
+    let disputeTaskId: Uint8Array;
+    let disputeTaskPda: PublicKey;
+    let disputeServiceId: Uint8Array;
+    let disputeServicePda: PublicKey;
+
+    before(async () => {
+      // Register a new active service
+      disputeServiceId = crypto.randomBytes(16);
+      [disputeServicePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("service"), providerAgent.publicKey.toBuffer(), Buffer.from(disputeServiceId)],
+        program.programId
+      );
+
+      await program.methods
+        .registerService(
+          Array.from(disputeServiceId),
+          padBytes("Code review service", 128),
+          new anchor.BN(PRICE_LAMPORTS),
+          new anchor.BN(0),
+          arbiterAgent.publicKey,
+          new anchor.BN(REVIEW_WINDOW_SECONDS),
+          CANCELLATION_FEE_BPS
+        )
+        .accounts({
+          config: configPda,
+          provider: providerAgent.publicKey,
+          serviceListing: disputeServicePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([providerAgent])
+        .rpc();
+
+      // Create a task
+      disputeTaskId = crypto.randomBytes(16);
+      [disputeTaskPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("task"), requesterAgent.publicKey.toBuffer(), Buffer.from(disputeTaskId)],
+        program.programId
+      );
+
+      await program.methods
+        .createTask(
+          Array.from(disputeTaskId),
+          padBytes("Review my smart contract", 256),
+          new anchor.BN(Math.floor(Date.now() / 1000) + 3600)
+        )
+        .accounts({
+          config: configPda,
+          requester: requesterAgent.publicKey,
+          serviceListing: disputeServicePda,
+          taskRequest: disputeTaskPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([requesterAgent])
+        .rpc();
+
+      // Provider submits result
+      const resultHash = crypto.createHash("sha256").update("bad review").digest();
+      await program.methods
+        .submitResult(Array.from(resultHash))
+        .accounts({
+          provider: providerAgent.publicKey,
+          taskRequest: disputeTaskPda,
+        })
+        .signers([providerAgent])
+        .rpc();
+    });
+
+    it("requester disputes and escrow stays locked", async () => {
+      const taskBalanceBefore = await provider.connection.getBalance(disputeTaskPda);
+
+      await program.methods
+        .disputeTask()
+        .accounts({
+          requester: requesterAgent.publicKey,
+          taskRequest: disputeTaskPda,
         })
         .signers([requesterAgent])
         .rpc();
 
+      const task = await program.account.taskRequest.fetch(disputeTaskPda);
+      expect(task.status).to.deep.equal({ disputePending: {} });
+      expect(task.disputedAt.toNumber()).to.be.greaterThan(0);
+
+      // No refund until the arbiter rules
+      const taskBalanceAfter = await provider.connection.getBalance(disputeTaskPda);
+      expect(taskBalanceAfter).to.equal(taskBalanceBefore);
+    });
+
+    it("fails when non-arbiter tries to resolve", async () => {
+      try {
+        await program.methods
+          .resolveDispute(10000)
+          .accounts({
+            config: configPda,
+            treasury: treasury.publicKey,
+            arbiter: providerAgent.publicKey, // wrong signer
+            taskRequest: disputeTaskPda,
+            requester: requesterAgent.publicKey,
+            provider: providerAgent.publicKey,
+          })
+          .signers([providerAgent])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("UnauthorizedArbiter");
+      }
+    });
+
+    it("arbiter splits escrow between requester and provider", async () => {
+      const requesterBalanceBefore = await provider.connection.getBalance(requesterAgent.publicKey);
+      const providerBalanceBefore = await provider.connection.getBalance(providerAgent.publicKey);
+
+      // 30% to the provider, 70% back to the requester
+      await program.methods
+        .resolveDispute(3000)
+        .accounts({
+          config: configPda,
+          treasury: treasury.publicKey,
+          arbiter: arbiterAgent.publicKey,
+          taskRequest: disputeTaskPda,
+          requester: requesterAgent.publicKey,
+          provider: providerAgent.publicKey,
+        })
+        .signers([arbiterAgent])
+        .rpc();
+
       const task = await program.account.taskRequest.fetch(disputeTaskPda);
       expect(task.status).to.deep.equal({ disputed: {} });
 
-      // Requester should get the refund
+      const providerShare = (PRICE_LAMPORTS * 3000) / 10000;
       const requesterBalanceAfter = await provider.connection.getBalance(requesterAgent.publicKey);
-      expect(requesterBalanceAfter - requesterBalanceBefore).to.equal(PRICE_LAMPORTS);
+      const providerBalanceAfter = await provider.connection.getBalance(providerAgent.publicKey);
+      expect(providerBalanceAfter - providerBalanceBefore).to.equal(afterProtocolFee(providerShare));
+      expect(requesterBalanceAfter - requesterBalanceBefore).to.equal(PRICE_LAMPORTS - providerShare);
     });
   });
 
@@ -365,9 +927,13 @@ describe("agentpay", () => {
           Array.from(expireServiceId),
           padBytes("Quick task service", 128),
           new anchor.BN(PRICE_LAMPORTS),
-          new anchor.BN(0)
+          new anchor.BN(0),
+          arbiterAgent.publicKey,
+          new anchor.BN(REVIEW_WINDOW_SECONDS),
+          CANCELLATION_FEE_BPS
         )
         .accounts({
+          config: configPda,
           provider: providerAgent.publicKey,
           serviceListing: expireServicePda,
           systemProgram: SystemProgram.programId,
@@ -389,6 +955,7 @@ describe("agentpay", () => {
           new anchor.BN(Math.floor(Date.now() / 1000) + 2) // 2 seconds from now
         )
         .accounts({
+          config: configPda,
           requester: requesterAgent.publicKey,
           serviceListing: expireServicePda,
           taskRequest: expireTaskPda,
@@ -419,4 +986,634 @@ describe("agentpay", () => {
       expect(requesterBalanceAfter - requesterBalanceBefore).to.equal(PRICE_LAMPORTS);
     });
   });
+  // =========================================================================
+  // auto_accept
+  // =========================================================================
+
+  describe("auto-accept flow", () => {
+    let autoTaskId: Uint8Array;
+    let autoTaskPda: PublicKey;
+    let autoServiceId: Uint8Array;
+    let autoServicePda: PublicKey;
+
+    before(async () => {
+      // Register a service with a very short review window (2 seconds)
+      autoServiceId = crypto.randomBytes(16);
+      [autoServicePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("service"), providerAgent.publicKey.toBuffer(), Buffer.from(autoServiceId)],
+        program.programId
+      );
+
+      await program.methods
+        .registerService(
+          Array.from(autoServiceId),
+          padBytes("Fast review service", 128),
+          new anchor.BN(PRICE_LAMPORTS),
+          new anchor.BN(0),
+          arbiterAgent.publicKey,
+          new anchor.BN(2),
+          CANCELLATION_FEE_BPS
+        )
+        .accounts({
+          config: configPda,
+          provider: providerAgent.publicKey,
+          serviceListing: autoServicePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([providerAgent])
+        .rpc();
+
+      autoTaskId = crypto.randomBytes(16);
+      [autoTaskPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("task"), requesterAgent.publicKey.toBuffer(), Buffer.from(autoTaskId)],
+        program.programId
+      );
+
+      await program.methods
+        .createTask(
+          Array.from(autoTaskId),
+          padBytes("Summarize this thread", 256),
+          new anchor.BN(Math.floor(Date.now() / 1000) + 3600)
+        )
+        .accounts({
+          config: configPda,
+          requester: requesterAgent.publicKey,
+          serviceListing: autoServicePda,
+          taskRequest: autoTaskPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([requesterAgent])
+        .rpc();
+
+      const resultHash = crypto.createHash("sha256").update("summary").digest();
+      await program.methods
+        .submitResult(Array.from(resultHash))
+        .accounts({
+          provider: providerAgent.publicKey,
+          taskRequest: autoTaskPda,
+        })
+        .signers([providerAgent])
+        .rpc();
+    });
+
+    it("fails to auto-accept during the review window", async () => {
+      try {
+        await program.methods
+          .autoAccept()
+          .accounts({
+            config: configPda,
+            treasury: treasury.publicKey,
+            taskRequest: autoTaskPda,
+            provider: providerAgent.publicKey,
+            serviceListing: autoServicePda,
+          })
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("ReviewWindowActive");
+      }
+    });
+
+    it("auto-accepts after the review window and pays the provider", async () => {
+      // Wait for the review window to pass
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+
+      const providerBalanceBefore = await provider.connection.getBalance(providerAgent.publicKey);
+
+      await program.methods
+        .autoAccept()
+        .accounts({
+          config: configPda,
+          treasury: treasury.publicKey,
+          taskRequest: autoTaskPda,
+          provider: providerAgent.publicKey,
+          serviceListing: autoServicePda,
+        })
+        .rpc(); // Anyone can call this
+
+      const task = await program.account.taskRequest.fetch(autoTaskPda);
+      expect(task.status).to.deep.equal({ completed: {} });
+
+      const providerBalanceAfter = await provider.connection.getBalance(providerAgent.publicKey);
+      expect(providerBalanceAfter - providerBalanceBefore).to.equal(afterProtocolFee(PRICE_LAMPORTS));
+
+      const listing = await program.account.serviceListing.fetch(autoServicePda);
+      expect(listing.tasksCompleted.toNumber()).to.equal(1);
+    });
+  });
+  // =========================================================================
+  // start_task / cancel_task
+  // =========================================================================
+
+  describe("cancel flow", () => {
+    let cancelServiceId: Uint8Array;
+    let cancelServicePda: PublicKey;
+
+    async function createCancelTask(): Promise<PublicKey> {
+      const id = crypto.randomBytes(16);
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("task"), requesterAgent.publicKey.toBuffer(), Buffer.from(id)],
+        program.programId
+      );
+
+      await program.methods
+        .createTask(
+          Array.from(id),
+          padBytes("Translate this document", 256),
+          new anchor.BN(Math.floor(Date.now() / 1000) + 3600)
+        )
+        .accounts({
+          config: configPda,
+          requester: requesterAgent.publicKey,
+          serviceListing: cancelServicePda,
+          taskRequest: pda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([requesterAgent])
+        .rpc();
+
+      return pda;
+    }
+
+    before(async () => {
+      cancelServiceId = crypto.randomBytes(16);
+      [cancelServicePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("service"), providerAgent.publicKey.toBuffer(), Buffer.from(cancelServiceId)],
+        program.programId
+      );
+
+      await program.methods
+        .registerService(
+          Array.from(cancelServiceId),
+          padBytes("Translation service", 128),
+          new anchor.BN(PRICE_LAMPORTS),
+          new anchor.BN(0),
+          arbiterAgent.publicKey,
+          new anchor.BN(REVIEW_WINDOW_SECONDS),
+          CANCELLATION_FEE_BPS
+        )
+        .accounts({
+          config: configPda,
+          provider: providerAgent.publicKey,
+          serviceListing: cancelServicePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([providerAgent])
+        .rpc();
+    });
+
+    it("cancels an open task with a full refund", async () => {
+      const taskPda = await createCancelTask();
+      const taskBalanceBefore = await provider.connection.getBalance(taskPda);
+
+      await program.methods
+        .cancelTask()
+        .accounts({
+          config: configPda,
+          treasury: treasury.publicKey,
+          requester: requesterAgent.publicKey,
+          taskRequest: taskPda,
+          provider: providerAgent.publicKey,
+        })
+        .signers([requesterAgent])
+        .rpc();
+
+      const task = await program.account.taskRequest.fetch(taskPda);
+      expect(task.status).to.deep.equal({ cancelled: {} });
+
+      const taskBalanceAfter = await provider.connection.getBalance(taskPda);
+      expect(taskBalanceBefore - taskBalanceAfter).to.equal(PRICE_LAMPORTS);
+    });
+
+    it("pays the provider the cancellation fee once work has started", async () => {
+      const taskPda = await createCancelTask();
+
+      await program.methods
+        .startTask()
+        .accounts({
+          provider: providerAgent.publicKey,
+          taskRequest: taskPda,
+        })
+        .signers([providerAgent])
+        .rpc();
+
+      const started = await program.account.taskRequest.fetch(taskPda);
+      expect(started.status).to.deep.equal({ inProgress: {} });
+
+      const providerBalanceBefore = await provider.connection.getBalance(providerAgent.publicKey);
+
+      await program.methods
+        .cancelTask()
+        .accounts({
+          config: configPda,
+          treasury: treasury.publicKey,
+          requester: requesterAgent.publicKey,
+          taskRequest: taskPda,
+          provider: providerAgent.publicKey,
+        })
+        .signers([requesterAgent])
+        .rpc();
+
+      const task = await program.account.taskRequest.fetch(taskPda);
+      expect(task.status).to.deep.equal({ cancelled: {} });
+
+      const providerBalanceAfter = await provider.connection.getBalance(providerAgent.publicKey);
+      expect(providerBalanceAfter - providerBalanceBefore).to.equal(
+        afterProtocolFee((PRICE_LAMPORTS * CANCELLATION_FEE_BPS) / 10000)
+      );
+    });
+  });
+
+  describe("milestone flow", () => {
+    let milestoneServiceId: Uint8Array;
+    let milestoneServicePda: PublicKey;
+    let milestoneTaskPda: PublicKey;
+
+    const milestonePda = (task: PublicKey, index: number): PublicKey =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("milestone"), task.toBuffer(), Buffer.from([index])],
+        program.programId
+      )[0];
+
+    before(async () => {
+      milestoneServiceId = crypto.randomBytes(16);
+      [milestoneServicePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("service"), providerAgent.publicKey.toBuffer(), Buffer.from(milestoneServiceId)],
+        program.programId
+      );
+
+      await program.methods
+        .registerService(
+          Array.from(milestoneServiceId),
+          padBytes("Website build", 128),
+          new anchor.BN(PRICE_LAMPORTS),
+          new anchor.BN(0),
+          arbiterAgent.publicKey,
+          new anchor.BN(REVIEW_WINDOW_SECONDS),
+          CANCELLATION_FEE_BPS
+        )
+        .accounts({
+          config: configPda,
+          provider: providerAgent.publicKey,
+          serviceListing: milestoneServicePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([providerAgent])
+        .rpc();
+
+      const id = crypto.randomBytes(16);
+      [milestoneTaskPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("task"), requesterAgent.publicKey.toBuffer(), Buffer.from(id)],
+        program.programId
+      );
+
+      await program.methods
+        .createTask(
+          Array.from(id),
+          padBytes("Design and build a landing page", 256),
+          new anchor.BN(Math.floor(Date.now() / 1000) + 3600)
+        )
+        .accounts({
+          config: configPda,
+          requester: requesterAgent.publicKey,
+          serviceListing: milestoneServicePda,
+          taskRequest: milestoneTaskPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([requesterAgent])
+        .rpc();
+    });
+
+    it("fails to add a milestone larger than the escrow", async () => {
+      try {
+        await program.methods
+          .addMilestone(0, new anchor.BN(PRICE_LAMPORTS + 1), padBytes("Everything", 128))
+          .accounts({
+            requester: requesterAgent.publicKey,
+            taskRequest: milestoneTaskPda,
+            milestone: milestonePda(milestoneTaskPda, 0),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([requesterAgent])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("InvalidMilestoneAmount");
+      }
+    });
+
+    it("splits the escrow into two milestones", async () => {
+      const amounts = [PRICE_LAMPORTS * 0.4, PRICE_LAMPORTS * 0.6];
+      const labels = ["Design", "Build"];
+
+      for (let i = 0; i < amounts.length; i++) {
+        await program.methods
+          .addMilestone(i, new anchor.BN(amounts[i]), padBytes(labels[i], 128))
+          .accounts({
+            requester: requesterAgent.publicKey,
+            taskRequest: milestoneTaskPda,
+            milestone: milestonePda(milestoneTaskPda, i),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([requesterAgent])
+          .rpc();
+      }
+
+      const task = await program.account.taskRequest.fetch(milestoneTaskPda);
+      expect(task.milestoneCount).to.equal(2);
+      expect(task.milestoneAllocated.toNumber()).to.equal(PRICE_LAMPORTS);
+    });
+
+    it("releases each milestone as it is accepted and completes the task", async () => {
+      for (let i = 0; i < 2; i++) {
+        const milestone = milestonePda(milestoneTaskPda, i);
+
+        await program.methods
+          .submitMilestone(Array.from(crypto.randomBytes(32)))
+          .accounts({
+            provider: providerAgent.publicKey,
+            taskRequest: milestoneTaskPda,
+            milestone,
+          })
+          .signers([providerAgent])
+          .rpc();
+
+        const providerBalanceBefore = await provider.connection.getBalance(providerAgent.publicKey);
+
+        await program.methods
+          .acceptMilestone()
+          .accounts({
+            config: configPda,
+            treasury: treasury.publicKey,
+            requester: requesterAgent.publicKey,
+            taskRequest: milestoneTaskPda,
+            milestone,
+            provider: providerAgent.publicKey,
+            serviceListing: milestoneServicePda,
+          })
+          .signers([requesterAgent])
+          .rpc();
+
+        const released = await program.account.milestone.fetch(milestone);
+        expect(released.status).to.deep.equal({ released: {} });
+
+        const providerBalanceAfter = await provider.connection.getBalance(providerAgent.publicKey);
+        expect(providerBalanceAfter - providerBalanceBefore).to.equal(
+          afterProtocolFee(released.amount.toNumber())
+        );
+      }
+
+      const task = await program.account.taskRequest.fetch(milestoneTaskPda);
+      expect(task.status).to.deep.equal({ completed: {} });
+      expect(task.releasedAmount.toNumber()).to.equal(PRICE_LAMPORTS);
+    });
+  });
+
+  describe("encrypted delivery", () => {
+    let deliveryServicePda: PublicKey;
+    let deliveryTaskPda: PublicKey;
+    let deliveryPda: PublicKey;
+
+    const DELIVERY_VK_VERSION = 1;
+    const deliveryVkPda = verifyingKeyPda(1, DELIVERY_VK_VERSION);
+    const expectedHash = crypto.randomBytes(32);
+
+    before(async () => {
+      const serviceId = crypto.randomBytes(16);
+      [deliveryServicePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("service"), providerAgent.publicKey.toBuffer(), Buffer.from(serviceId)],
+        program.programId
+      );
+
+      await program.methods
+        .registerService(
+          Array.from(serviceId),
+          padBytes("Dataset export", 128),
+          new anchor.BN(PRICE_LAMPORTS),
+          new anchor.BN(0),
+          arbiterAgent.publicKey,
+          new anchor.BN(REVIEW_WINDOW_SECONDS),
+          CANCELLATION_FEE_BPS
+        )
+        .accounts({
+          config: configPda,
+          provider: providerAgent.publicKey,
+          serviceListing: deliveryServicePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([providerAgent])
+        .rpc();
+
+      const id = crypto.randomBytes(16);
+      [deliveryTaskPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("task"), requesterAgent.publicKey.toBuffer(), Buffer.from(id)],
+        program.programId
+      );
+      [deliveryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("delivery"), deliveryTaskPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .createTask(
+          Array.from(id),
+          padBytes("Export the dataset", 256),
+          new anchor.BN(Math.floor(Date.now() / 1000) + 3600)
+        )
+        .accounts({
+          config: configPda,
+          requester: requesterAgent.publicKey,
+          serviceListing: deliveryServicePda,
+          taskRequest: deliveryTaskPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([requesterAgent])
+        .rpc();
+
+      // Placeholder key: the tests below fail before the pairing check
+      await program.methods
+        .initVerifyingKey(
+          { resultDelivery: {} },
+          DELIVERY_VK_VERSION,
+          10,
+          G1_GENERATOR,
+          G2_GENERATOR,
+          G2_GENERATOR,
+          G2_GENERATOR
+        )
+        .accounts({
+          admin: provider.wallet.publicKey,
+          config: configPda,
+          verifyingKey: deliveryVkPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .appendVerifyingKeyIc(new Array(11).fill(G1_GENERATOR))
+        .accounts({
+          admin: provider.wallet.publicKey,
+          config: configPda,
+          verifyingKey: deliveryVkPda,
+        })
+        .rpc();
+    });
+
+    it("requester publishes an encryption key and the expected hash", async () => {
+      const encryptionKey = crypto.randomBytes(64);
+
+      await program.methods
+        .requestEncryptedDelivery(Array.from(encryptionKey), Array.from(expectedHash))
+        .accounts({
+          requester: requesterAgent.publicKey,
+          taskRequest: deliveryTaskPda,
+          delivery: deliveryPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([requesterAgent])
+        .rpc();
+
+      const delivery = await program.account.encryptedDelivery.fetch(deliveryPda);
+      expect(Buffer.from(delivery.encryptionKey)).to.deep.equal(encryptionKey);
+      expect(delivery.deliveredAt.toNumber()).to.equal(0);
+    });
+
+    it("fails to deliver a result other than the one being paid for", async () => {
+      try {
+        await program.methods
+          .submitResultEncrypted(
+            new Array(64).fill(0),
+            new Array(128).fill(0),
+            new Array(64).fill(0),
+            Array.from(crypto.randomBytes(32)),
+            new Array(64).fill(0),
+            new Array(32).fill(0)
+          )
+          .accounts({
+            provider: providerAgent.publicKey,
+            taskRequest: deliveryTaskPda,
+            delivery: deliveryPda,
+            verifyingKey: deliveryVkPda,
+            serviceListing: deliveryServicePda,
+            config: configPda,
+            treasury: treasury.publicKey,
+          })
+          .signers([providerAgent])
+          .rpc();
+        expect.fail("Should have thrown");
+      } catch (err: any) {
+        expect(err.error.errorCode.code).to.equal("DeliveryHashMismatch");
+      }
+    });
+  });
+
+  describe("chunked result commitments", () => {
+    let chunkedServicePda: PublicKey;
+    let chunkedTaskPda: PublicKey;
+
+    const CHUNKED_VK_VERSION = 1;
+    const chunkedVkPda = verifyingKeyPda(2, CHUNKED_VK_VERSION);
+
+    before(async () => {
+      const serviceId = crypto.randomBytes(16);
+      [chunkedServicePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("service"), providerAgent.publicKey.toBuffer(), Buffer.from(serviceId)],
+        program.programId
+      );
+
+      await program.methods
+        .registerService(
+          Array.from(serviceId),
+          padBytes("Report generation", 128),
+          new anchor.BN(PRICE_LAMPORTS),
+          new anchor.BN(0),
+          arbiterAgent.publicKey,
+          new anchor.BN(REVIEW_WINDOW_SECONDS),
+          CANCELLATION_FEE_BPS
+        )
+        .accounts({
+          config: configPda,
+          provider: providerAgent.publicKey,
+          serviceListing: chunkedServicePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([providerAgent])
+        .rpc();
+
+      const id = crypto.randomBytes(16);
+      [chunkedTaskPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("task"), requesterAgent.publicKey.toBuffer(), Buffer.from(id)],
+        program.programId
+      );
+
+      await program.methods
+        .createTask(
+          Array.from(id),
+          padBytes("Write the quarterly report", 256),
+          new anchor.BN(Math.floor(Date.now() / 1000) + 3600)
+        )
+        .accounts({
+          config: configPda,
+          requester: requesterAgent.publicKey,
+          serviceListing: chunkedServicePda,
+          taskRequest: chunkedTaskPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([requesterAgent])
+        .rpc();
+
+      // Placeholder key: the tests below fail before the pairing check
+      await program.methods
+        .initVerifyingKey(
+          { taskVerifyChunked: {} },
+          CHUNKED_VK_VERSION,
+          6,
+          G1_GENERATOR,
+          G2_GENERATOR,
+          G2_GENERATOR,
+          G2_GENERATOR
+        )
+        .accounts({
+          admin: provider.wallet.publicKey,
+          config: configPda,
+          verifyingKey: chunkedVkPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .appendVerifyingKeyIc(new Array(7).fill(G1_GENERATOR))
+        .accounts({
+          admin: provider.wallet.publicKey,
+          config: configPda,
+          verifyingKey: chunkedVkPda,
+        })
+        .rpc();
+    });
+
+    for (const chunkCount of [0, 1025]) {
+      it(`rejects a chunk count of ${chunkCount}`, async () => {
+        try {
+          await program.methods
+            .submitResultZk(
+              new Array(64).fill(0),
+              new Array(128).fill(0),
+              new Array(64).fill(0),
+              Array.from(crypto.randomBytes(32)),
+              chunkCount
+            )
+            .accounts({
+              provider: providerAgent.publicKey,
+              taskRequest: chunkedTaskPda,
+              verifyingKey: chunkedVkPda,
+            })
+            .signers([providerAgent])
+            .rpc();
+          expect.fail("Should have thrown");
+        } catch (err: any) {
+          expect(err.error.errorCode.code).to.equal("InvalidChunkCount");
+        }
+      });
+    }
+  });
 });